@@ -0,0 +1,28 @@
+-- Tables of the server which are not part of the Serlo database dump in `001-init.sql`, so that
+-- they exist before the server is started (e.g. for the tests). The server creates them in
+-- existing databases with the migrations in `server/migrations`, which need to be kept in sync.
+USE serlo;
+
+-- Idempotency keys of mutations and their responses (see server/src/idempotency.rs)
+CREATE TABLE IF NOT EXISTS idempotency_key (
+  `key` VARCHAR(255) NOT NULL PRIMARY KEY,
+  fingerprint CHAR(32) NOT NULL,
+  status SMALLINT UNSIGNED NULL,
+  response LONGBLOB NULL,
+  created_at DATETIME NOT NULL,
+  INDEX (created_at)
+);
+
+-- Events which still need to be sent to webhooks (see server/src/outbox.rs). Rows without
+-- `webhook_url` are new events which have not been assigned to the configured webhooks yet.
+CREATE TABLE IF NOT EXISTS event_outbox (
+  id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT PRIMARY KEY,
+  event_id INT NOT NULL,
+  webhook_url VARCHAR(2048) NULL,
+  payload JSON NOT NULL,
+  attempts INT UNSIGNED NOT NULL DEFAULT 0,
+  next_attempt_at DATETIME NULL,
+  last_error TEXT NULL,
+  created_at DATETIME NOT NULL,
+  INDEX (next_attempt_at)
+);
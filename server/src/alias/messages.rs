@@ -1,12 +1,16 @@
 use actix_web::HttpResponse;
 use async_trait::async_trait;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::model::{fetch, fetch_via_transaction};
+use crate::cache::{CacheKey, Dependency};
 use crate::database::Connection;
 use crate::instance::Instance;
 use crate::message::MessageResponder;
 use crate::operation::{self, Operation};
+use crate::schema::{MessageSchema, MessageSchemas};
 
 #[derive(Deserialize, Serialize)]
 #[serde(tag = "type", content = "payload")]
@@ -24,17 +28,26 @@ impl MessageResponder for AliasMessage {
     }
 }
 
+impl MessageSchemas for AliasMessage {
+    fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema> {
+        vec![MessageSchema::new::<alias_query::Payload>(
+            "AliasQuery",
+            generator,
+        )]
+    }
+}
+
 pub mod alias_query {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub instance: Instance,
         pub path: String,
     }
 
-    #[derive(Serialize)]
+    #[derive(Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub id: i32,
@@ -46,6 +59,18 @@ pub mod alias_query {
     impl Operation for Payload {
         type Output = Output;
 
+        fn cache_key(&self) -> Option<CacheKey> {
+            Some(CacheKey::Alias {
+                instance: self.instance.clone(),
+                path: self.path.clone(),
+            })
+        }
+
+        fn cache_dependencies(&self, output: &Self::Output) -> Vec<Dependency> {
+            // Aliases may contain the name of a taxonomy term.
+            vec![Dependency::Uuid(output.id), Dependency::Taxonomy]
+        }
+
         #[allow(clippy::async_yields_async)]
         async fn execute(&self, connection: Connection<'_, '_>) -> operation::Result<Self::Output> {
             let path = self.path.as_str();
@@ -75,7 +75,9 @@ where
         || path.starts_with("user/remove/")
         || path.starts_with("uuid/")
     {
-        return Err(operation::Error::NotFoundError);
+        return Err(operation::Error::NotFoundError {
+            code: operation::ErrorCode::AliasNotFound,
+        });
     }
 
     let re = Regex::new(r"^user/profile/(?P<username>.+)$").unwrap();
@@ -95,7 +97,9 @@ where
         .fetch_optional(&mut transaction)
         .await?
         .map(|x| x.id as i32)
-        .ok_or(operation::Error::NotFoundError)?
+        .ok_or(operation::Error::NotFoundError {
+            code: operation::ErrorCode::AliasNotFound,
+        })?
     } else {
         let re = Regex::new(r"^(?P<subject>[^/]+/)?(?P<id>\d+)/(?P<title>[^/]*)$").unwrap();
         if let Some(captures) = re.captures(path) {
@@ -114,7 +118,9 @@ where
             .fetch_optional(&mut transaction)
             .await?
             .map(|result| result.uuid_id as i32)
-            .ok_or(operation::Error::NotFoundError)?
+            .ok_or(operation::Error::NotFoundError {
+                code: operation::ErrorCode::AliasNotFound,
+            })?
         }
     };
 
@@ -0,0 +1,122 @@
+//! Optional authentication of the message endpoints `POST /` and `POST /batch` as well as of
+//! `GET /events/stream`.
+//!
+//! Clients send a key as `Authorization: Bearer <key>`. Keys are configured in [`AuthConfig`]:
+//! read-only keys may only send queries (see [`Message::is_query`]) and open the event stream
+//! whereas read-write keys may send all messages. When no key is configured, all requests are
+//! accepted.
+//!
+//! The key is checked with [`authenticate`] before the body is parsed into messages, so that
+//! unauthenticated requests cannot make the server parse them. Only the check whether a
+//! read-only key sends mutations ([`authorize`]) needs the parsed messages.
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{HttpRequest, HttpResponse};
+
+use crate::config::AuthConfig;
+use crate::message::Message;
+use crate::operation::ErrorCode;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Access {
+    ReadOnly,
+    ReadWrite,
+}
+
+impl AuthConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.read_only_keys.is_empty() || !self.read_write_keys.is_empty()
+    }
+
+    /// Returns the access granted to `key` or `None` for an unknown key.
+    pub fn access(&self, key: &str) -> Option<Access> {
+        if contains_key(&self.read_write_keys, key) {
+            Some(Access::ReadWrite)
+        } else if contains_key(&self.read_only_keys, key) {
+            Some(Access::ReadOnly)
+        } else {
+            None
+        }
+    }
+}
+
+/// Checks the key of the request and returns the access granted to it (full access when no key
+/// is configured). Returns the error response for a missing or unknown key.
+pub fn authenticate(auth: Option<&AuthConfig>, req: &HttpRequest) -> Result<Access, HttpResponse> {
+    let auth = match auth {
+        Some(auth) if auth.is_enabled() => auth,
+        _ => return Ok(Access::ReadWrite),
+    };
+
+    bearer_token(req)
+        .and_then(|key| auth.access(key))
+        .ok_or_else(|| {
+            tracing::warn!("rejected request with missing or unknown key");
+            HttpResponse::Unauthorized()
+                .insert_header(("WWW-Authenticate", "Bearer"))
+                .content_type("application/json; charset=utf-8")
+                .json(ErrorCode::Unauthorized.to_body("Missing or unknown key."))
+        })
+}
+
+/// Checks that `access` suffices to send `messages`. Returns the error response otherwise.
+pub fn authorize(access: Access, messages: &[Message]) -> Result<(), HttpResponse> {
+    if access == Access::ReadOnly && !messages.iter().all(|message| message.is_query()) {
+        tracing::warn!("rejected mutation sent with read-only key");
+        return Err(HttpResponse::Forbidden()
+            .content_type("application/json; charset=utf-8")
+            .json(ErrorCode::Forbidden.to_body("Key may only send queries.")));
+    }
+
+    Ok(())
+}
+
+fn bearer_token(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+fn contains_key(keys: &[String], key: &str) -> bool {
+    // Every key is compared so that the duration does not depend on which key matches.
+    keys.iter().fold(false, |found, candidate| {
+        constant_time_eq(candidate, key) | found
+    })
+}
+
+/// Compares two strings in a time which only depends on their length.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |difference, (x, y)| difference | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{constant_time_eq, Access};
+    use crate::config::AuthConfig;
+
+    #[test]
+    fn access_depends_on_key() {
+        let auth = AuthConfig {
+            read_only_keys: vec!["read-only-key".to_string()],
+            read_write_keys: vec!["read-write-key".to_string()],
+        };
+
+        assert_eq!(auth.access("read-only-key"), Some(Access::ReadOnly));
+        assert_eq!(auth.access("read-write-key"), Some(Access::ReadWrite));
+        assert_eq!(auth.access("read-only"), None);
+        assert_eq!(auth.access(""), None);
+    }
+
+    #[test]
+    fn compares_strings() {
+        assert!(constant_time_eq("secret", "secret"));
+        assert!(!constant_time_eq("secret", "secreT"));
+        assert!(!constant_time_eq("secret", "secrets"));
+    }
+}
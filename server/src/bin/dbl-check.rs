@@ -0,0 +1,122 @@
+//! Checks the invariants of the database (see [`server::integrity`]) and reports the ids of the
+//! rows violating them.
+//!
+//! Usage: `cargo run --bin dbl-check -- [OPTIONS]`, e.g.
+//! `cargo run --bin dbl-check -- --rule entity-without-required-parent --json`
+use std::process::ExitCode;
+
+use server::cli::{self, Args as _};
+use server::integrity::{find_rule, Rule, Violations, RULES};
+
+struct Args {
+    rules: Vec<&'static Rule>,
+    list: bool,
+    json: bool,
+}
+
+impl cli::Args for Args {
+    const USAGE: &'static str = "\
+Usage: dbl-check [OPTIONS]
+
+Checks the invariants of the database and reports the ids of the rows violating them. Fails when
+there are violations.
+
+Options:
+  --rule <ID>    Only check the given rule (can be repeated)
+  --list         Print the rules instead of checking them
+  --json         Print the result as JSON
+  -h, --help     Print this help";
+
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut rules = Vec::new();
+        let mut list = false;
+        let mut json = false;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--rule" => {
+                    let id = args
+                        .next()
+                        .ok_or_else(|| "missing id of rule after `--rule`".to_string())?;
+                    let rule = find_rule(&id).ok_or_else(|| {
+                        let ids: Vec<&str> = RULES.iter().map(|rule| rule.id).collect();
+                        format!("unknown rule `{id}`, known rules: {}", ids.join(", "))
+                    })?;
+                    rules.push(rule);
+                }
+                "--list" => list = true,
+                "--json" => json = true,
+                "-h" | "--help" => return Ok(None),
+                argument => return Err(format!("unexpected argument `{argument}`")),
+            }
+        }
+
+        if rules.is_empty() {
+            rules = RULES.iter().collect();
+        }
+
+        Ok(Some(Self { rules, list, json }))
+    }
+}
+
+#[actix_web::main]
+async fn main() -> ExitCode {
+    let args = match Args::from_env() {
+        Ok(args) => args,
+        Err(exit_code) => return exit_code,
+    };
+
+    if args.list {
+        for rule in &args.rules {
+            println!("{}\n  {}", rule.id, rule.description);
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    let pool = match cli::connect().await {
+        Ok(pool) => pool,
+        Err(exit_code) => return exit_code,
+    };
+
+    let mut results = Vec::new();
+    for rule in &args.rules {
+        match rule.check(&pool).await {
+            Ok(violations) => results.push(violations),
+            Err(error) => {
+                eprintln!("error: could not check rule `{}`: {error}", rule.id);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    pool.close().await;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&results).unwrap());
+    } else {
+        print_report(&results);
+    }
+
+    if results.iter().all(|violations| violations.ids.is_empty()) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn print_report(results: &[Violations]) {
+    for violations in results {
+        if violations.ids.is_empty() {
+            println!("ok    {}", violations.rule);
+        } else {
+            let ids: Vec<String> = violations.ids.iter().map(i32::to_string).collect();
+            println!(
+                "FAIL  {} ({} violations): {}\n      ids: {}",
+                violations.rule,
+                violations.ids.len(),
+                violations.description,
+                ids.join(", ")
+            );
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Sends a single message to the database without a running server and prints the response.
+//!
+//! Usage: `cargo run --bin dbl-cli -- [OPTIONS] <MessageType> [Payload]`, e.g.
+//! `cargo run --bin dbl-cli -- --rollback UuidSetStateMutation '{"ids":[1855],"userId":1,"trashed":true}'`
+use std::process::ExitCode;
+
+use actix_web::body::to_bytes;
+use actix_web::HttpResponse;
+use serde_json::{json, Value};
+use tracing_subscriber::EnvFilter;
+
+use server::cli::{self, Args as _};
+use server::database::Connection;
+use server::message::{Message, MessageResponder};
+use server::operation::ErrorCode;
+
+struct Args {
+    message_type: String,
+    payload: Option<String>,
+    rollback: bool,
+    explain: bool,
+    json: bool,
+}
+
+impl cli::Args for Args {
+    const USAGE: &'static str = "\
+Usage: dbl-cli [OPTIONS] <MessageType> [Payload]
+
+Sends a message to the database and prints the response.
+
+Arguments:
+  <MessageType>  Type of the message, e.g. UuidQuery
+  [Payload]      Payload of the message as JSON, e.g. '{\"id\":1}'
+
+Options:
+  --rollback     Execute the message inside a transaction which is rolled back
+  --explain      Print the SQL statements issued by the message (to stderr)
+  --json         Print the response as compact JSON instead of pretty-printing it
+  -h, --help     Print this help";
+
+    fn parse(args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut positional = Vec::new();
+        let mut rollback = false;
+        let mut explain = false;
+        let mut json = false;
+
+        for arg in args {
+            match arg.as_str() {
+                "--rollback" => rollback = true,
+                "--explain" => explain = true,
+                "--json" => json = true,
+                "-h" | "--help" => return Ok(None),
+                option if option.starts_with("--") => {
+                    return Err(format!("unknown option `{option}`"))
+                }
+                _ => positional.push(arg),
+            }
+        }
+
+        let mut positional = positional.into_iter();
+        let message_type = positional
+            .next()
+            .ok_or_else(|| "missing message type".to_string())?;
+        let payload = positional.next();
+
+        if let Some(argument) = positional.next() {
+            return Err(format!("unexpected argument `{argument}`"));
+        }
+
+        Ok(Some(Self {
+            message_type,
+            payload,
+            rollback,
+            explain,
+            json,
+        }))
+    }
+}
+
+impl Args {
+    fn message(&self) -> Result<Message, String> {
+        let mut message = json!({ "type": self.message_type });
+
+        if let Some(payload) = &self.payload {
+            message["payload"] = serde_json::from_str(payload)
+                .map_err(|error| format!("payload is no valid JSON: {error}"))?;
+        }
+
+        Message::from_value(message).map_err(|error| match error.error_code() {
+            ErrorCode::UnknownMessageType { types } => {
+                format!("{error}, known types: {}", types.join(", "))
+            }
+            _ => error.to_string(),
+        })
+    }
+}
+
+#[actix_web::main]
+async fn main() -> ExitCode {
+    let args = match Args::from_env() {
+        Ok(args) => args,
+        Err(exit_code) => return exit_code,
+    };
+
+    let message = match args.message() {
+        Ok(message) => message,
+        Err(error) => {
+            eprintln!("error: {error}");
+            return ExitCode::from(2);
+        }
+    };
+
+    if args.explain {
+        // sqlx logs every statement with the target `sqlx::query` at the level of
+        // `DB_LOG_STATEMENTS` (`debug` by default), so all levels need to pass the filter.
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .without_time()
+            .with_target(false)
+            .with_env_filter(EnvFilter::new("sqlx::query=trace"))
+            .init();
+    }
+
+    let pool = match cli::connect().await {
+        Ok(pool) => pool,
+        Err(exit_code) => return exit_code,
+    };
+
+    let response = if args.rollback {
+        let mut transaction = pool.begin().await.expect("Failed to begin transaction.");
+        let response = message
+            .handle(Connection::Transaction(&mut transaction))
+            .await;
+        transaction
+            .rollback()
+            .await
+            .expect("Failed to roll back transaction.");
+        response
+    } else {
+        message.handle(Connection::Pool(&pool)).await
+    };
+
+    let success = response.status().is_success();
+    eprintln!("{}", response.status());
+    println!("{}", format_body(response, args.json).await);
+
+    pool.close().await;
+
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+async fn format_body(response: HttpResponse, compact: bool) -> String {
+    let body = to_bytes(response.into_body()).await.unwrap_or_default();
+
+    match serde_json::from_slice::<Value>(&body) {
+        Ok(value) if compact => value.to_string(),
+        Ok(value) => serde_json::to_string_pretty(&value).unwrap_or_else(|_| value.to_string()),
+        Err(_) => String::from_utf8_lossy(&body).into_owned(),
+    }
+}
@@ -0,0 +1,11 @@
+//! Prints the OpenAPI document of the database layer, see [`server::schema`].
+//!
+//! Usage: `cargo run --bin schema > schema.json`
+fn main() {
+    let document = server::schema::openapi();
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&document).expect("document can be serialized")
+    );
+}
@@ -0,0 +1,580 @@
+//! In-process cache for the outputs of `UuidQuery`, `AliasQuery` and `NavigationQuery`.
+//!
+//! The cache is opt-in and only enabled when [`CacheConfig::size`] (`CACHE_SIZE`) is set, see
+//! [`init`]. Entries expire after [`CacheConfig::ttl_seconds`]. Only outputs fetched outside of a
+//! transaction are cached, see [`Operation::handle`].
+//!
+//! Mutations call [`Cache::invalidate`] with the ids of all uuids they change, which removes the
+//! outputs depending on those uuids. Outputs derived from the taxonomy (canonical subjects and
+//! aliases containing the name of a taxonomy term) additionally depend on
+//! [`Dependency::Taxonomy`], which mutations of taxonomy terms invalidate with
+//! [`Cache::invalidate_taxonomy`]. Since a mutation might run inside a transaction which is
+//! committed later, everything is invalidated a second time when the request has finished (see
+//! [`invalidate_after`]). The navigation is only edited outside of this server and thus only
+//! expires.
+//!
+//! A read replica might not have seen the latest changes yet. Thus outputs read from the replica
+//! (see [`read_from_replica`]) are not cached during [`CacheConfig::replica_lag_seconds`] after
+//! an invalidation.
+//!
+//! [`Operation::handle`]: crate::operation::Operation::handle
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use actix_web::web::Bytes;
+use actix_web::HttpResponse;
+use async_trait::async_trait;
+use lru::LruCache;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::config::CacheConfig;
+use crate::database::Connection;
+use crate::instance::Instance;
+use crate::message::MessageResponder;
+use crate::operation::{self, Operation};
+use crate::schema::{MessageSchema, MessageSchemas};
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum CacheKey {
+    Uuid(i32),
+    Alias { instance: Instance, path: String },
+    Navigation(Instance),
+}
+
+/// Something a cached output depends on. The output is removed as soon as it is invalidated.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Dependency {
+    Uuid(i32),
+    /// Names and hierarchy of all taxonomy terms.
+    Taxonomy,
+}
+
+pub struct Cache {
+    state: Option<Mutex<CacheState>>,
+    ttl: Duration,
+    replica_lag: Duration,
+    generation: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+struct CacheState {
+    entries: LruCache<CacheKey, CacheEntry>,
+    dependents: HashMap<Dependency, HashSet<CacheKey>>,
+    last_invalidation: Option<Instant>,
+}
+
+struct CacheEntry {
+    body: Bytes,
+    dependencies: Vec<Dependency>,
+    inserted_at: Instant,
+}
+
+static CACHE: OnceLock<Cache> = OnceLock::new();
+
+/// Creates the cache shared by all workers of the server. Needs to be called before the first
+/// message is handled, afterwards the cache stays disabled.
+pub fn init(config: &CacheConfig) {
+    let _ = CACHE.set(Cache::new(
+        config.size.and_then(NonZeroUsize::new),
+        Duration::from_secs(config.ttl_seconds),
+        Duration::from_secs(config.replica_lag_seconds),
+    ));
+}
+
+/// Returns the cache shared by all workers of the server.
+pub fn cache() -> &'static Cache {
+    CACHE.get_or_init(|| Cache::new(None, Duration::ZERO, Duration::ZERO))
+}
+
+tokio::task_local! {
+    static PENDING_INVALIDATIONS: RefCell<PendingInvalidations>;
+    static FROM_REPLICA: bool;
+}
+
+#[derive(Default)]
+struct PendingInvalidations {
+    dependencies: Vec<Dependency>,
+    all: bool,
+}
+
+/// Runs `future`, which reads from the read replica when `replica` is `true`.
+pub async fn read_from_replica<F: Future>(replica: bool, future: F) -> F::Output {
+    FROM_REPLICA.scope(replica, future).await
+}
+
+/// Runs `future` and afterwards invalidates all ids again which have been invalidated while it
+/// was running. Wrap everything in it which might commit a transaction.
+pub async fn invalidate_after<F: Future>(future: F) -> F::Output {
+    let (output, pending) = PENDING_INVALIDATIONS
+        .scope(RefCell::default(), async {
+            let output = future.await;
+            let pending = PENDING_INVALIDATIONS.with(|pending| pending.take());
+            (output, pending)
+        })
+        .await;
+
+    if pending.all {
+        cache().clear();
+    } else if !pending.dependencies.is_empty() {
+        cache().remove_dependents(&pending.dependencies);
+    }
+
+    output
+}
+
+impl Cache {
+    fn new(size: Option<NonZeroUsize>, ttl: Duration, replica_lag: Duration) -> Self {
+        Self {
+            state: size.map(|size| {
+                Mutex::new(CacheState {
+                    entries: LruCache::new(size),
+                    dependents: HashMap::new(),
+                    last_invalidation: None,
+                })
+            }),
+            ttl,
+            replica_lag,
+            generation: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.state.is_some()
+    }
+
+    /// Returns a counter which changes with every invalidation. Pass it to [`Cache::insert`] to
+    /// make sure that outputs fetched before an invalidation are not cached afterwards.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    pub fn get(&self, key: &CacheKey) -> Option<Bytes> {
+        let mut state = self.state.as_ref()?.lock().unwrap();
+
+        let body = match state
+            .entries
+            .get(key)
+            .map(|entry| (entry.inserted_at.elapsed() < self.ttl, entry.body.clone()))
+        {
+            Some((true, body)) => Some(body),
+            Some((false, _)) => {
+                state.remove(key);
+                None
+            }
+            None => None,
+        };
+
+        match body {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+
+        body
+    }
+
+    /// Caches `body` under `key` unless an invalidation happened since `generation` was read.
+    /// The entry is removed as soon as one of its `dependencies` is invalidated.
+    pub fn insert(
+        &self,
+        key: CacheKey,
+        body: Bytes,
+        dependencies: Vec<Dependency>,
+        generation: u64,
+    ) {
+        let Some(state) = self.state.as_ref() else {
+            return;
+        };
+        let mut state = state.lock().unwrap();
+
+        // Checked while holding the lock so that no invalidation can happen in between.
+        if self.generation() != generation {
+            return;
+        }
+
+        let from_replica = FROM_REPLICA.try_with(|replica| *replica).unwrap_or(false);
+        if from_replica
+            && state
+                .last_invalidation
+                .is_some_and(|invalidated_at| invalidated_at.elapsed() < self.replica_lag)
+        {
+            return;
+        }
+
+        for dependency in dependencies.iter() {
+            state
+                .dependents
+                .entry(*dependency)
+                .or_default()
+                .insert(key.clone());
+        }
+
+        let entry = CacheEntry {
+            body,
+            dependencies,
+            inserted_at: Instant::now(),
+        };
+
+        if let Some((evicted_key, evicted)) = state.entries.push(key.clone(), entry) {
+            if evicted_key != key {
+                state.remove_from_dependents(&evicted_key, &evicted);
+            }
+        }
+    }
+
+    /// Removes all outputs depending on the uuids `ids`. Mutations need to call it for every uuid
+    /// whose output changes.
+    pub fn invalidate(&self, ids: &[i32]) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let dependencies: Vec<_> = ids.iter().copied().map(Dependency::Uuid).collect();
+        let _ = PENDING_INVALIDATIONS.try_with(|pending| {
+            pending
+                .borrow_mut()
+                .dependencies
+                .extend(dependencies.iter().copied())
+        });
+
+        self.invalidations
+            .fetch_add(ids.len() as u64, Ordering::Relaxed);
+        self.remove_dependents(&dependencies);
+    }
+
+    /// Removes all outputs derived from the taxonomy. Mutations of taxonomy terms need to call it
+    /// besides [`Cache::invalidate`].
+    pub fn invalidate_taxonomy(&self) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let _ = PENDING_INVALIDATIONS
+            .try_with(|pending| pending.borrow_mut().dependencies.push(Dependency::Taxonomy));
+
+        self.invalidations.fetch_add(1, Ordering::Relaxed);
+        self.remove_dependents(&[Dependency::Taxonomy]);
+    }
+
+    /// Removes all outputs. Only meant for mutations which change too many uuids to list them.
+    pub fn invalidate_all(&self) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let _ = PENDING_INVALIDATIONS.try_with(|pending| pending.borrow_mut().all = true);
+
+        self.invalidations.fetch_add(1, Ordering::Relaxed);
+        self.clear();
+    }
+
+    fn remove_dependents(&self, dependencies: &[Dependency]) {
+        let Some(state) = self.state.as_ref() else {
+            return;
+        };
+        let mut state = state.lock().unwrap();
+
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        state.last_invalidation = Some(Instant::now());
+
+        for dependency in dependencies {
+            for key in state.dependents.remove(dependency).unwrap_or_default() {
+                state.remove(&key);
+            }
+        }
+    }
+
+    fn clear(&self) {
+        let Some(state) = self.state.as_ref() else {
+            return;
+        };
+        let mut state = state.lock().unwrap();
+
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        state.last_invalidation = Some(Instant::now());
+
+        state.entries.clear();
+        state.dependents.clear();
+    }
+
+    pub fn stats(&self) -> cache_stats_query::Output {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+
+        cache_stats_query::Output {
+            enabled: self.is_enabled(),
+            size: self
+                .state
+                .as_ref()
+                .map_or(0, |state| state.lock().unwrap().entries.len()),
+            capacity: self
+                .state
+                .as_ref()
+                .map_or(0, |state| state.lock().unwrap().entries.cap().get()),
+            ttl_seconds: self.ttl.as_secs(),
+            hits,
+            misses,
+            hit_rate: if hits + misses > 0 {
+                hits as f64 / (hits + misses) as f64
+            } else {
+                0.0
+            },
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl CacheState {
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.pop(key) {
+            self.remove_from_dependents(key, &entry);
+        }
+    }
+
+    fn remove_from_dependents(&mut self, key: &CacheKey, entry: &CacheEntry) {
+        for dependency in entry.dependencies.iter() {
+            if let Some(keys) = self.dependents.get_mut(dependency) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.dependents.remove(dependency);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "type", content = "payload")]
+pub enum CacheMessage {
+    CacheStatsQuery(Option<cache_stats_query::Payload>),
+}
+
+#[async_trait]
+impl MessageResponder for CacheMessage {
+    #[allow(clippy::async_yields_async)]
+    async fn handle(&self, connection: Connection<'_, '_>) -> HttpResponse {
+        match self {
+            CacheMessage::CacheStatsQuery(_) => {
+                cache_stats_query::Payload {}
+                    .handle("CacheStatsQuery", connection)
+                    .await
+            }
+        }
+    }
+}
+
+impl MessageSchemas for CacheMessage {
+    fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema> {
+        vec![MessageSchema::new::<cache_stats_query::Payload>(
+            "CacheStatsQuery",
+            generator,
+        )]
+    }
+}
+
+pub mod cache_stats_query {
+    use super::*;
+
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
+    pub struct Payload {}
+
+    #[derive(Serialize, JsonSchema)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Output {
+        pub enabled: bool,
+        pub size: usize,
+        pub capacity: usize,
+        pub ttl_seconds: u64,
+        pub hits: u64,
+        pub misses: u64,
+        pub hit_rate: f64,
+        pub invalidations: u64,
+    }
+
+    #[async_trait]
+    impl Operation for Payload {
+        type Output = Output;
+
+        async fn execute(
+            &self,
+            _connection: Connection<'_, '_>,
+        ) -> operation::Result<Self::Output> {
+            Ok(cache().stats())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::time::Duration;
+
+    use actix_web::web::Bytes;
+
+    use super::{Cache, CacheKey, Dependency, FROM_REPLICA};
+    use crate::instance::Instance;
+
+    fn new_cache(size: usize) -> Cache {
+        Cache::new(
+            NonZeroUsize::new(size),
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        )
+    }
+
+    #[test]
+    fn returns_inserted_outputs() {
+        let cache = new_cache(10);
+
+        cache.insert(
+            CacheKey::Uuid(1),
+            Bytes::from("1"),
+            vec![Dependency::Uuid(1)],
+            cache.generation(),
+        );
+
+        assert_eq!(cache.get(&CacheKey::Uuid(1)), Some(Bytes::from("1")));
+        assert_eq!(cache.get(&CacheKey::Uuid(2)), None);
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(cache.stats().misses, 1);
+    }
+
+    #[test]
+    fn invalidates_outputs_depending_on_uuid() {
+        let cache = new_cache(10);
+        let alias = CacheKey::Alias {
+            instance: Instance::De,
+            path: "/mathe".to_string(),
+        };
+
+        cache.insert(
+            CacheKey::Uuid(1),
+            Bytes::from("1"),
+            vec![Dependency::Uuid(1)],
+            cache.generation(),
+        );
+        cache.insert(
+            alias.clone(),
+            Bytes::from("1"),
+            vec![Dependency::Uuid(1)],
+            cache.generation(),
+        );
+        cache.insert(
+            CacheKey::Uuid(2),
+            Bytes::from("2"),
+            vec![Dependency::Uuid(2)],
+            cache.generation(),
+        );
+
+        cache.invalidate(&[1]);
+
+        assert_eq!(cache.get(&CacheKey::Uuid(1)), None);
+        assert_eq!(cache.get(&alias), None);
+        assert_eq!(cache.get(&CacheKey::Uuid(2)), Some(Bytes::from("2")));
+    }
+
+    #[test]
+    fn invalidates_outputs_derived_from_taxonomy() {
+        let cache = new_cache(10);
+
+        cache.insert(
+            CacheKey::Uuid(1),
+            Bytes::from("1"),
+            vec![Dependency::Uuid(1), Dependency::Taxonomy],
+            cache.generation(),
+        );
+        cache.insert(
+            CacheKey::Uuid(2),
+            Bytes::from("2"),
+            vec![Dependency::Uuid(2)],
+            cache.generation(),
+        );
+
+        cache.invalidate_taxonomy();
+
+        assert_eq!(cache.get(&CacheKey::Uuid(1)), None);
+        assert_eq!(cache.get(&CacheKey::Uuid(2)), Some(Bytes::from("2")));
+    }
+
+    #[test]
+    fn does_not_insert_replica_reads_shortly_after_invalidation() {
+        let cache = new_cache(10);
+
+        cache.invalidate(&[3]);
+
+        FROM_REPLICA.sync_scope(true, || {
+            cache.insert(
+                CacheKey::Uuid(1),
+                Bytes::from("1"),
+                vec![Dependency::Uuid(1)],
+                cache.generation(),
+            )
+        });
+        cache.insert(
+            CacheKey::Uuid(2),
+            Bytes::from("2"),
+            vec![Dependency::Uuid(2)],
+            cache.generation(),
+        );
+
+        assert_eq!(cache.get(&CacheKey::Uuid(1)), None);
+        assert_eq!(cache.get(&CacheKey::Uuid(2)), Some(Bytes::from("2")));
+    }
+
+    #[test]
+    fn does_not_insert_outputs_fetched_before_invalidation() {
+        let cache = new_cache(10);
+        let generation = cache.generation();
+
+        cache.invalidate(&[1]);
+        cache.insert(
+            CacheKey::Uuid(1),
+            Bytes::from("1"),
+            vec![Dependency::Uuid(1)],
+            generation,
+        );
+
+        assert_eq!(cache.get(&CacheKey::Uuid(1)), None);
+    }
+
+    #[test]
+    fn does_not_return_expired_outputs() {
+        let cache = Cache::new(NonZeroUsize::new(10), Duration::ZERO, Duration::ZERO);
+
+        cache.insert(
+            CacheKey::Uuid(1),
+            Bytes::from("1"),
+            vec![Dependency::Uuid(1)],
+            cache.generation(),
+        );
+
+        assert_eq!(cache.get(&CacheKey::Uuid(1)), None);
+    }
+
+    #[test]
+    fn disabled_cache_does_not_store_outputs() {
+        let cache = new_cache(0);
+
+        cache.insert(
+            CacheKey::Uuid(1),
+            Bytes::from("1"),
+            vec![Dependency::Uuid(1)],
+            cache.generation(),
+        );
+
+        assert!(!cache.stats().enabled);
+        assert_eq!(cache.get(&CacheKey::Uuid(1)), None);
+    }
+}
@@ -0,0 +1,71 @@
+//! Collects the changes of a mutation: the uuids it affected and the events it created.
+//!
+//! Mutations report the uuids they change via [`record_affected_uuids`], independently of whether
+//! they can invalidate them one by one in the cache. Additionally [`EventPayload::save`] reports
+//! the id of the created event as well as the object and the uuid parameters of the event.
+//! [`Operation::handle`] adds the collected changes to the output of every mutation as
+//! `affectedUuids` and `eventIds`, so that clients know which of their cached uuids are outdated.
+//!
+//! [`EventPayload::save`]: crate::event::EventPayload::save
+//! [`Operation::handle`]: crate::operation::Operation::handle
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::future::Future;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Debug, Default, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Changes {
+    pub affected_uuids: BTreeSet<i32>,
+    pub event_ids: Vec<i32>,
+}
+
+tokio::task_local! {
+    static CHANGES: RefCell<Changes>;
+}
+
+/// Runs `future` and returns the changes recorded while it was running.
+pub async fn collect<F: Future>(future: F) -> (F::Output, Changes) {
+    CHANGES
+        .scope(RefCell::default(), async {
+            let output = future.await;
+            let changes = CHANGES.with(|changes| changes.take());
+            (output, changes)
+        })
+        .await
+}
+
+pub fn record_affected_uuids(ids: &[i32]) {
+    let _ = CHANGES.try_with(|changes| changes.borrow_mut().affected_uuids.extend(ids));
+}
+
+pub fn record_event(id: i32) {
+    let _ = CHANGES.try_with(|changes| changes.borrow_mut().event_ids.push(id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{collect, record_affected_uuids, record_event, Changes};
+
+    #[actix_rt::test]
+    async fn collects_changes_of_future() {
+        let (output, changes) = collect(async {
+            record_affected_uuids(&[3, 1]);
+            record_event(42);
+            record_affected_uuids(&[1, 2]);
+            "output"
+        })
+        .await;
+
+        assert_eq!(output, "output");
+        assert_eq!(
+            changes,
+            Changes {
+                affected_uuids: [1, 2, 3].into(),
+                event_ids: vec![42],
+            }
+        );
+    }
+}
@@ -0,0 +1,50 @@
+//! Plumbing shared by the command line tools `dbl-cli` and `dbl-check`.
+//!
+//! The tools parse their arguments via [`Args`] and connect to the database which is configured
+//! like for the server (see [`crate::config`]).
+use std::env;
+use std::process::ExitCode;
+
+use sqlx::MySqlPool;
+
+use crate::config::Config;
+use crate::connect_database;
+
+/// Command line arguments of a tool.
+pub trait Args: Sized {
+    /// Help which is printed for `-h` / `--help` and after invalid arguments.
+    const USAGE: &'static str;
+
+    /// Parses the command line arguments (without the name of the binary). Returns `None` when
+    /// the help shall be printed.
+    fn parse(args: impl Iterator<Item = String>) -> Result<Option<Self>, String>;
+
+    /// Parses the arguments of the process. Prints the help or the error otherwise and returns the
+    /// exit code the tool shall end with.
+    fn from_env() -> Result<Self, ExitCode> {
+        match Self::parse(env::args().skip(1)) {
+            Ok(Some(args)) => Ok(args),
+            Ok(None) => {
+                println!("{}", Self::USAGE);
+                Err(ExitCode::SUCCESS)
+            }
+            Err(error) => {
+                eprintln!("error: {error}\n\n{}", Self::USAGE);
+                Err(ExitCode::from(2))
+            }
+        }
+    }
+}
+
+/// Connects to the database of the configuration. Prints the error otherwise.
+pub async fn connect() -> Result<MySqlPool, ExitCode> {
+    let pool = match Config::load() {
+        Ok(config) => connect_database(&config.database).await,
+        Err(error) => Err(error),
+    };
+
+    pool.map_err(|error| {
+        eprintln!("error: {error}");
+        ExitCode::FAILURE
+    })
+}
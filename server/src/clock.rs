@@ -0,0 +1,139 @@
+//! Source of the current time.
+//!
+//! Models take the current time from [`now`] and not from the system, so that tests can freeze
+//! or advance it with a [`MockClock`]:
+//!
+//! ```rust
+//! use chrono::Duration;
+//! use server::clock::{self, MockClock};
+//! use server::datetime::DateTime;
+//!
+//! let clock = MockClock::freeze(DateTime::ymd(2014, 3, 1));
+//! assert_eq!(clock::now(), DateTime::ymd(2014, 3, 1));
+//!
+//! clock.advance(Duration::days(1));
+//! assert_eq!(clock::now(), DateTime::ymd(2014, 3, 2));
+//! ```
+//!
+//! A mock only applies to the thread which created it. Tests with `#[actix_rt::test]` run their
+//! requests and spawned tasks on the thread of the test, so parallel tests do not interfere.
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use chrono::{Duration, Utc};
+
+use crate::datetime::DateTime;
+
+static CLOCK: OnceLock<Box<dyn Clock>> = OnceLock::new();
+
+thread_local! {
+    static MOCKED_NOW: RefCell<Option<Arc<Mutex<DateTime>>>> = const { RefCell::new(None) };
+}
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime;
+}
+
+/// Clock of the operating system.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime {
+        DateTime::from_utc(Utc::now())
+    }
+}
+
+/// Sets the clock of the process. Only the first call has an effect and it needs to happen before
+/// the time is read for the first time. Without a call the [`SystemClock`] is used.
+pub fn set(clock: impl Clock + 'static) {
+    let _ = CLOCK.set(Box::new(clock));
+}
+
+/// Returns the current time of the mock of this thread or else of the clock of the process.
+pub fn now() -> DateTime {
+    MOCKED_NOW
+        .with(|mocked_now| {
+            mocked_now
+                .borrow()
+                .as_ref()
+                .map(|now| now.lock().unwrap().clone())
+        })
+        .unwrap_or_else(|| CLOCK.get_or_init(|| Box::new(SystemClock)).now())
+}
+
+/// Returns whether the current thread uses a [`MockClock`].
+pub fn is_mocked() -> bool {
+    MOCKED_NOW.with(|mocked_now| mocked_now.borrow().is_some())
+}
+
+/// Clock for tests which stands still until it is advanced. It is used by the current thread as
+/// long as it is not dropped.
+pub struct MockClock {
+    now: Arc<Mutex<DateTime>>,
+}
+
+impl MockClock {
+    pub fn freeze(now: DateTime) -> Self {
+        let now = Arc::new(Mutex::new(now));
+        MOCKED_NOW.with(|mocked_now| *mocked_now.borrow_mut() = Some(now.clone()));
+        Self { now }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now = now.clone() + duration;
+    }
+
+    pub fn set(&self, now: DateTime) {
+        *self.now.lock().unwrap() = now;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime {
+        self.now.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockClock {
+    fn drop(&mut self) {
+        MOCKED_NOW.with(|mocked_now| {
+            let mut mocked_now = mocked_now.borrow_mut();
+            if mocked_now
+                .as_ref()
+                .iter()
+                .all(|now| Arc::ptr_eq(now, &self.now))
+            {
+                *mocked_now = None;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::{is_mocked, now, MockClock};
+    use crate::datetime::DateTime;
+
+    #[test]
+    fn mock_clock_can_be_frozen_and_advanced() {
+        let clock = MockClock::freeze(DateTime::ymd(2014, 3, 1));
+        assert!(is_mocked());
+        assert_eq!(now(), DateTime::ymd(2014, 3, 1));
+
+        clock.advance(Duration::hours(36));
+        assert_eq!(
+            now().signed_duration_since(DateTime::ymd(2014, 3, 2)),
+            Duration::hours(12)
+        );
+
+        clock.set(DateTime::ymd(2020, 1, 1));
+        assert_eq!(now(), DateTime::ymd(2020, 1, 1));
+
+        drop(clock);
+        assert!(!is_mocked());
+        assert!(now().signed_duration_since(DateTime::ymd(2020, 1, 1)) > Duration::zero());
+    }
+}
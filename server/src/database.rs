@@ -54,3 +54,22 @@ where
     Pool(&'e sqlx::MySqlPool),
     Transaction(&'e mut sqlx::Transaction<'c, sqlx::MySql>),
 }
+
+/// Connection pool of a read replica of the database.
+///
+/// When it is registered as app data (see [`crate::configure_app_with_replica`]), messages which
+/// only read data are executed on the replica while all other messages (and all messages inside
+/// a transaction) are executed on the primary database.
+#[derive(Clone, Debug)]
+pub struct ReplicaPool(pub sqlx::MySqlPool);
+
+/// Returns a comma separated list of `count` placeholders, e.g. for a `WHERE id IN (...)` clause.
+///
+/// ```rust
+/// use server::database::placeholders;
+///
+/// assert_eq!(placeholders(3), "?, ?, ?");
+/// ```
+pub fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
@@ -6,6 +6,9 @@ use std::fmt;
 
 use chrono::{Duration, TimeZone, Utc};
 use chrono_tz::Europe::Berlin;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize, Serializer};
 use sqlx::database::HasArguments;
 use sqlx::encode::IsNull;
@@ -17,12 +20,13 @@ use crate::operation;
 ///
 /// # Constructing a `DateTime`
 ///
-/// To construct a `DateTime` representing the current date, use [`DateTime::now`]:
+/// To construct a `DateTime` representing the current date, use [`crate::clock::now`] (which can be
+/// frozen in tests):
 ///
 /// ```rust
-/// use server::datetime::DateTime;
+/// use server::clock;
 ///
-/// let current_datetime = DateTime::now();
+/// let current_datetime = clock::now();
 /// ```
 ///
 /// Timestamps from the database can be converted using the `From` trait:
@@ -39,13 +43,14 @@ use crate::operation;
 /// # Ok(())
 /// # }
 /// ```
-#[derive(Deserialize, Debug, Eq, PartialEq)]
+#[derive(Clone, Deserialize, Debug, Eq, PartialEq)]
 pub struct DateTime(chrono::DateTime<Utc>);
 
 impl DateTime {
-    pub fn now() -> Self {
-        let current_datetime = Utc::now();
-        DateTime(current_datetime)
+    /// Wraps a timestamp which is actually UTC (unlike the timestamps of the database, see the
+    /// `From` implementation).
+    pub fn from_utc(datetime: chrono::DateTime<Utc>) -> Self {
+        DateTime(datetime)
     }
 
     pub fn ymd(year: i32, month: u32, date: u32) -> Self {
@@ -57,6 +62,12 @@ impl DateTime {
         )
     }
 
+    /// Formats the timestamp as RFC 3339 in UTC (unlike `Display` which uses the time zone of
+    /// Berlin).
+    pub fn to_rfc3339(&self) -> String {
+        self.0.to_rfc3339()
+    }
+
     pub fn signed_duration_since(&self, rhs: DateTime) -> Duration {
         self.0.signed_duration_since(rhs.0)
     }
@@ -64,6 +75,7 @@ impl DateTime {
     pub fn parse_from_rfc3339(string_ref: &str) -> Result<Self, operation::Error> {
         let date = chrono::DateTime::parse_from_rfc3339(string_ref).map_err(|_| {
             operation::Error::BadRequest {
+                code: operation::ErrorCode::InvalidParameter,
                 reason: "The date format should be YYYY-MM-DDThh:mm:ss{Timezone}".to_string(),
             }
         })?;
@@ -71,6 +83,14 @@ impl DateTime {
     }
 }
 
+impl std::ops::Add<Duration> for DateTime {
+    type Output = Self;
+
+    fn add(self, duration: Duration) -> Self {
+        DateTime(self.0 + duration)
+    }
+}
+
 impl From<chrono::DateTime<Utc>> for DateTime {
     fn from(datetime: chrono::DateTime<Utc>) -> Self {
         let datetime = datetime.naive_utc();
@@ -97,6 +117,21 @@ impl Serialize for DateTime {
     }
 }
 
+impl JsonSchema for DateTime {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "DateTime".to_string()
+    }
+
+    /// Like [`chrono::DateTime`] our timestamps are serialized as RFC 3339 strings.
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        <chrono::DateTime<Utc> as JsonSchema>::json_schema(generator)
+    }
+}
+
 impl sqlx::Type<MySql> for DateTime {
     fn type_info() -> MySqlTypeInfo {
         <sqlx::types::chrono::DateTime<Utc> as sqlx::Type<MySql>>::type_info()
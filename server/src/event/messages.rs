@@ -1,5 +1,7 @@
 use actix_web::HttpResponse;
 use async_trait::async_trait;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::model::{Event, EventError};
@@ -7,6 +9,7 @@ use crate::database::Connection;
 use crate::instance::Instance;
 use crate::message::MessageResponder;
 use crate::operation::{self, Operation};
+use crate::schema::{MessageSchema, MessageSchemas};
 
 #[derive(Deserialize, Serialize)]
 #[serde(tag = "type", content = "payload")]
@@ -26,11 +29,20 @@ impl MessageResponder for EventMessage {
     }
 }
 
+impl MessageSchemas for EventMessage {
+    fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema> {
+        vec![
+            MessageSchema::new::<event_query::Payload>("EventQuery", generator),
+            MessageSchema::new::<events_query::Payload>("EventsQuery", generator),
+        ]
+    }
+}
+
 pub mod event_query {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub id: i32,
     }
@@ -47,20 +59,30 @@ pub mod event_query {
                 }
             }
             .map_err(|e| match e {
-                EventError::InvalidType
-                | EventError::MissingRequiredField
-                | EventError::NotFound => operation::Error::NotFoundError,
-                _ => operation::Error::InternalServerError { error: Box::new(e) },
+                EventError::InvalidType => operation::Error::NotFoundError {
+                    code: operation::ErrorCode::InvalidEventType,
+                },
+                EventError::MissingRequiredField => operation::Error::NotFoundError {
+                    code: operation::ErrorCode::EventMissingRequiredField,
+                },
+                EventError::NotFound => operation::Error::NotFoundError {
+                    code: operation::ErrorCode::EventNotFound,
+                },
+                _ => e.into(),
             })?)
         }
     }
 }
 
 pub mod events_query {
+    use futures::TryStreamExt;
+    use sqlx::MySqlPool;
+
     use super::*;
+    use crate::ndjson::{Cursor, Lines};
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Clone, Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub after: Option<i32>,
         pub actor_id: Option<i32>,
@@ -69,7 +91,7 @@ pub mod events_query {
         pub first: i32,
     }
 
-    #[derive(Debug, Eq, PartialEq, Serialize)]
+    #[derive(Debug, Eq, PartialEq, Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub events: Vec<Event>,
@@ -81,11 +103,7 @@ pub mod events_query {
         type Output = Output;
 
         async fn execute(&self, connection: Connection<'_, '_>) -> operation::Result<Self::Output> {
-            if self.first > 10_000 {
-                return Err(operation::Error::BadRequest {
-                    reason: "parameter `first` is too high".to_string(),
-                });
-            }
+            self.validate()?;
 
             Ok(match connection {
                 Connection::Pool(pool) => Event::fetch_events(self, pool).await?,
@@ -95,4 +113,53 @@ pub mod events_query {
             })
         }
     }
+
+    impl Payload {
+        fn validate(&self) -> operation::Result<()> {
+            if self.first > 10_000 {
+                return Err(operation::Error::BadRequest {
+                    code: operation::ErrorCode::InvalidParameter,
+                    reason: "parameter `first` is too high".to_string(),
+                });
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Streams the events as NDJSON, see [`crate::ndjson`].
+    pub async fn stream(
+        payload: &Payload,
+        pool: &MySqlPool,
+        lines: &Lines,
+    ) -> operation::Result<Cursor> {
+        payload.validate()?;
+
+        let instance_id = match payload.instance.as_ref() {
+            Some(instance) => Some(instance.fetch_id(pool).await?),
+            None => None,
+        };
+        let mut events = Event::stream_events(payload, instance_id, pool);
+        let mut cursor = Cursor {
+            after: payload.after,
+            has_next_page: false,
+        };
+        let mut count = 0;
+
+        while let Some(event) = events.try_next().await? {
+            if count == payload.first {
+                cursor.has_next_page = true;
+                break;
+            }
+            if let Some(event) = event {
+                if !lines.send(&event).await? {
+                    break;
+                }
+                count += 1;
+                cursor.after = Some(event.abstract_event.id);
+            }
+        }
+
+        Ok(cursor)
+    }
 }
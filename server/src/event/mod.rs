@@ -1,4 +1,4 @@
-pub use messages::EventMessage;
+pub use messages::{events_query, EventMessage};
 pub use model::*;
 
 mod messages;
@@ -9,8 +9,8 @@ pub(crate) mod test_helpers {
     use chrono::Duration;
 
     use super::Event;
+    use crate::clock;
     use crate::database::Executor;
-    use crate::datetime::DateTime;
 
     pub(crate) async fn fetch_age_of_newest_event<'a, E>(
         object_id: i32,
@@ -33,7 +33,7 @@ pub(crate) mod test_helpers {
                 let event = Event::fetch_via_transaction(event.id as i32, &mut transaction)
                     .await
                     .unwrap();
-                Ok(DateTime::now().signed_duration_since(event.abstract_event.date))
+                Ok(clock::now().signed_duration_since(event.abstract_event.date))
             }
             Err(sqlx::Error::RowNotFound) => Ok(Duration::max_value()),
             Err(inner) => Err(inner),
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use futures::join;
+use schemars::JsonSchema;
 use serde::Serialize;
 use sqlx::MySqlPool;
 
@@ -10,7 +11,7 @@ use crate::database::Executor;
 use crate::datetime::DateTime;
 use crate::instance::Instance;
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
+#[derive(Debug, Eq, PartialEq, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AbstractEvent {
     #[serde(rename(serialize = "__typename"))]
@@ -1,13 +1,14 @@
 use std::collections::HashMap;
 use std::convert::TryFrom;
 
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use crate::database::Executor;
 
 use super::{AbstractEvent, Event, EventError, EventPayload, RawEventType};
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
+#[derive(Debug, Eq, PartialEq, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateCommentEvent {
     thread_id: i32,
@@ -69,8 +70,7 @@ impl CreateCommentEventPayload {
 
 #[cfg(test)]
 mod tests {
-    use chrono::Duration;
-
+    use crate::clock::MockClock;
     use crate::create_database_pool;
     use crate::datetime::DateTime;
     use crate::event::{AbstractEvent, ConcreteEvent, Event};
@@ -81,6 +81,7 @@ mod tests {
     async fn create_comment() {
         let pool = create_database_pool().await.unwrap();
         let mut transaction = pool.begin().await.unwrap();
+        let _clock = MockClock::freeze(DateTime::ymd(2023, 5, 1));
 
         let set_thread_state_event = CreateCommentEventPayload::new(16740, 18932, 10, 1);
 
@@ -109,7 +110,7 @@ mod tests {
         } = event
         {
             assert_eq!(uuid_parameters.get("discussion").unwrap(), 16740);
-            assert!(DateTime::now().signed_duration_since(date) < Duration::minutes(1))
+            assert_eq!(date, DateTime::ymd(2023, 5, 1))
         } else {
             panic!("Event does not fulfill assertions: {:?}", event)
         }
@@ -1,11 +1,12 @@
 use crate::database::Executor;
 use crate::event::{Event, EventError, EventPayload, RawEventType};
+use schemars::JsonSchema;
 use serde::Serialize;
 use std::collections::HashMap;
 
 use super::abstract_event::AbstractEvent;
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
+#[derive(Debug, Eq, PartialEq, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateEntityEvent {
     entity_id: i32,
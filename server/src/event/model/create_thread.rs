@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::convert::TryFrom;
 
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use super::abstract_event::AbstractEvent;
@@ -9,7 +10,7 @@ use super::event_type::RawEventType;
 use super::EventError;
 use crate::database::Executor;
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
+#[derive(Debug, Eq, PartialEq, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateThreadEvent {
     object_id: i32,
@@ -73,9 +74,8 @@ impl CreateThreadEventPayload {
 
 #[cfg(test)]
 mod tests {
-    use chrono::Duration;
-
     use super::CreateThreadEventPayload;
+    use crate::clock::MockClock;
     use crate::create_database_pool;
     use crate::datetime::DateTime;
     use crate::event::{AbstractEvent, ConcreteEvent, CreateThreadEvent, Event};
@@ -84,6 +84,7 @@ mod tests {
     async fn create_thread() {
         let pool = create_database_pool().await.unwrap();
         let mut transaction = pool.begin().await.unwrap();
+        let _clock = MockClock::freeze(DateTime::ymd(2023, 5, 1));
 
         let create_thread_event = CreateThreadEventPayload::new(16740, 1292, 10, 1);
 
@@ -112,7 +113,7 @@ mod tests {
         } = event
         {
             assert_eq!(uuid_parameters.get("on").unwrap(), 1292);
-            assert!(DateTime::now().signed_duration_since(date) < Duration::minutes(1))
+            assert_eq!(date, DateTime::ymd(2023, 5, 1))
         } else {
             panic!("Event does not fulfill assertions: {:?}", event)
         }
@@ -1,13 +1,14 @@
 use std::collections::HashMap;
 use std::convert::TryFrom;
 
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use super::abstract_event::AbstractEvent;
 use super::{Event, EventError, EventPayload, RawEventType};
 use crate::database::Executor;
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
+#[derive(Debug, Eq, PartialEq, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct EntityLinkEvent {
     child_id: i32,
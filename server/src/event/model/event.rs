@@ -1,9 +1,10 @@
 use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 
-use futures::TryStreamExt;
+use futures::{Stream, TryStreamExt};
+use schemars::JsonSchema;
 use serde::Serialize;
-use sqlx::MySqlPool;
+use sqlx::{MySqlPool, Row};
 
 use super::super::messages::*;
 use super::abstract_event::AbstractEvent;
@@ -21,13 +22,18 @@ use super::set_uuid_state::SetUuidStateEvent;
 use super::taxonomy_link::TaxonomyLinkEvent;
 use super::taxonomy_term::TaxonomyTermEvent;
 use super::EventError;
-use crate::database::Executor;
+use crate::cache::cache;
+use crate::changes;
+use crate::clock;
+use crate::database::{placeholders, Executor};
 use crate::datetime::DateTime;
 use crate::event::{EventStringParameters, EventUuidParameters};
+use crate::event_stream;
 use crate::instance::Instance;
 use crate::notification::Notifications;
+use crate::outbox;
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
+#[derive(Debug, Eq, PartialEq, Serialize, JsonSchema)]
 pub struct Event {
     #[serde(flatten)]
     pub abstract_event: AbstractEvent,
@@ -35,7 +41,7 @@ pub struct Event {
     pub concrete_event: ConcreteEvent,
 }
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
+#[derive(Debug, Eq, PartialEq, Serialize, JsonSchema)]
 #[serde(untagged)]
 pub enum ConcreteEvent {
     SetThreadState(SetThreadStateEvent),
@@ -147,7 +153,7 @@ impl EventPayload {
             actor_id,
             object_id,
             instance_id,
-            date: DateTime::now(),
+            date: clock::now(),
 
             string_parameters,
             uuid_parameters,
@@ -257,6 +263,14 @@ impl EventPayload {
 
         let event = Event::fetch_via_transaction(event_id, &mut transaction).await?;
         Notifications::create_notifications(&event, &mut transaction).await?;
+        outbox::record(&event, &mut transaction).await?;
+
+        let affected_uuids: Vec<i32> = std::iter::once(self.object_id)
+            .chain(self.uuid_parameters.values().copied())
+            .collect();
+        changes::record_affected_uuids(&affected_uuids);
+        cache().invalidate(&affected_uuids);
+        changes::record_event(event_id);
 
         transaction.commit().await?;
 
@@ -277,7 +291,84 @@ impl Event {
             Some(instance) => Some(Instance::fetch_id(instance, &mut transaction).await?),
             None => None,
         };
-        let mut event_records = sqlx::query!(
+        let mut event_stream = Self::stream_events(payload, instance_id, &mut transaction);
+
+        let mut events: Vec<Event> = Vec::new();
+        let mut has_next_page = false;
+
+        while let Some(event) = event_stream.try_next().await? {
+            if events.len() as i32 == payload.first {
+                has_next_page = true;
+                break;
+            }
+            if let Some(event) = event {
+                events.push(event);
+            }
+        }
+
+        Ok(events_query::Output {
+            events,
+            has_next_page,
+        })
+    }
+
+    /// Returns the ids of the events after `after` (in ascending order) which match `filter`. Like
+    /// `EventsQuery` it skips checkouts of page revisions.
+    pub async fn fetch_ids_after<'a, E>(
+        after: i32,
+        filter: &event_stream::Filter,
+        instance_id: Option<i32>,
+        limit: i32,
+        executor: E,
+    ) -> Result<Vec<i32>, sqlx::Error>
+    where
+        E: Executor<'a>,
+    {
+        sqlx::query(
+            r#"
+                SELECT DISTINCT el.id
+                FROM event_log el
+                    JOIN event e ON e.id = el.event_id
+                    LEFT JOIN uuid u ON u.id = el.uuid_id
+                    LEFT JOIN event_parameter ep ON ep.log_id = el.id
+                    LEFT JOIN event_parameter_uuid epu ON epu.event_parameter_id = ep.id
+                WHERE
+                  el.id > ?
+                  AND NOT (e.name = "entity/revision/checkout" AND u.discriminator = "pageRevision")
+                  AND (? IS NULL OR el.actor_id = ?)
+                  AND (? IS NULL OR el.uuid_id = ? OR epu.uuid_id = ?)
+                  AND (? IS NULL OR el.instance_id = ?)
+                ORDER BY el.id
+                LIMIT ?
+            "#,
+        )
+        .bind(after)
+        .bind(filter.actor_id)
+        .bind(filter.actor_id)
+        .bind(filter.object_id)
+        .bind(filter.object_id)
+        .bind(filter.object_id)
+        .bind(instance_id)
+        .bind(instance_id)
+        .bind(limit)
+        .fetch(executor)
+        .and_then(|row| async move { Ok(row.try_get::<i64, _>("id")? as i32) })
+        .try_collect()
+        .await
+    }
+
+    /// Returns a stream of the events matching `payload` which are mapped while the rows are read
+    /// from the database. It contains `payload.first + 1` rows at most, so that consumers can
+    /// find out whether there is a next page. Rows which cannot be mapped to an event are `None`.
+    pub fn stream_events<'a, E>(
+        payload: &events_query::Payload,
+        instance_id: Option<i32>,
+        executor: E,
+    ) -> impl Stream<Item = Result<Option<Event>, sqlx::Error>> + Send + Unpin + 'a
+    where
+        E: Executor<'a> + 'a,
+    {
+        sqlx::query!(
             r#"
                 SELECT
                     el.id,
@@ -329,74 +420,154 @@ impl Event {
             instance_id,
             payload.first + 1
         )
-        .fetch(&mut transaction);
+        .fetch(executor)
+        .map_ok(|record| {
+            EventRow {
+                id: record.id,
+                instance: record.instance,
+                raw_typename: record.raw_typename,
+                actor_id: record.actor_id,
+                date: record.date,
+                object_id: record.object_id,
+                string_parameters: record.string_parameters,
+                uuid_parameters: record.uuid_parameters,
+            }
+            .into_event()
+        })
+    }
 
-        let mut events: Vec<Event> = Vec::new();
-        let mut has_next_page = false;
+    /// Fetches the events with the given ids in one query (in ascending order). Events which
+    /// cannot be mapped are skipped like in `EventsQuery`.
+    pub async fn fetch_many<'a, E>(ids: &[i32], executor: E) -> Result<Vec<Event>, sqlx::Error>
+    where
+        E: Executor<'a>,
+    {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
 
-        while let Some(record) = event_records.try_next().await? {
-            if events.len() as i32 == payload.first {
-                has_next_page = true;
-                break;
-            }
-            let instance = match record.instance.parse() {
-                Ok(instance) => instance,
-                _ => continue,
-            };
-
-            let raw_typename: RawEventType = match record.raw_typename.parse() {
-                Ok(typename) => typename,
-                _ => continue,
-            };
-
-            let string_parameters = record
-                .string_parameters
-                .and_then(|value| {
-                    value.as_object().map(|object| {
-                        object
-                            .iter()
-                            .filter_map(|(key, value)| {
-                                value.as_str().map(|value| (key.clone(), value.to_string()))
-                            })
-                            .collect()
-                    })
+        let query = format!(
+            r#"
+                SELECT
+                    el.id,
+                    i.subdomain                           AS instance,
+                    e.name                                AS raw_typename,
+                    el.actor_id,
+                    el.date,
+                    el.uuid_id                            AS object_id,
+                    JSON_REMOVE(
+                        JSON_OBJECTAGG(
+                            CASE WHEN epn.name IS NOT NULL THEN epn.name ELSE "__unused_key" END,
+                            eps.value
+                        ),
+                        "$.__unused_key"
+                    )   AS string_parameters,
+                    JSON_REMOVE(
+                        JSON_OBJECTAGG(
+                            CASE WHEN epn.name IS NOT NULL THEN epn.name ELSE "__unused_key" END,
+                            epu.uuid_id
+                        ),
+                        "$.__unused_key"
+                    ) AS uuid_parameters
+                FROM event_log el
+                    JOIN event e ON e.id = el.event_id
+                    JOIN instance i on i.id = el.instance_id
+                    LEFT JOIN event_parameter ep ON ep.log_id = el.id
+                    LEFT JOIN event_parameter_name epn ON epn.id = ep.name_id
+                    LEFT JOIN event_parameter_string eps ON eps.event_parameter_id = ep.id
+                    LEFT JOIN event_parameter_uuid epu ON epu.event_parameter_id = ep.id
+                WHERE el.id IN ({})
+                GROUP BY el.id
+                ORDER BY el.id
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, EventRow>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+
+        query
+            .fetch(executor)
+            .try_filter_map(|row| async move {
+                let id = row.id;
+                let event = row.into_event();
+                if event.is_none() {
+                    tracing::warn!(id, "skipped event which cannot be mapped");
+                }
+                Ok(event)
+            })
+            .try_collect()
+            .await
+    }
+}
+
+/// Row of an event whose parameters are aggregated into JSON objects.
+#[derive(sqlx::FromRow)]
+struct EventRow {
+    id: i64,
+    instance: String,
+    raw_typename: String,
+    actor_id: i64,
+    date: chrono::DateTime<chrono::Utc>,
+    object_id: i64,
+    string_parameters: Option<serde_json::Value>,
+    uuid_parameters: Option<serde_json::Value>,
+}
+
+impl EventRow {
+    /// Returns `None` when the row cannot be mapped to an event.
+    fn into_event(self) -> Option<Event> {
+        let instance = match self.instance.parse() {
+            Ok(instance) => instance,
+            _ => return None,
+        };
+
+        let raw_typename: RawEventType = match self.raw_typename.parse() {
+            Ok(typename) => typename,
+            _ => return None,
+        };
+
+        let string_parameters = self
+            .string_parameters
+            .and_then(|value| {
+                value.as_object().map(|object| {
+                    object
+                        .iter()
+                        .filter_map(|(key, value)| {
+                            value.as_str().map(|value| (key.clone(), value.to_string()))
+                        })
+                        .collect()
                 })
-                .unwrap_or_default();
-
-            let uuid_parameters = record
-                .uuid_parameters
-                .and_then(|value| {
-                    value.as_object().map(|object| {
-                        object
-                            .iter()
-                            .filter_map(|(key, value)| {
-                                value.as_i64().map(|value| (key.clone(), value as i32))
-                            })
-                            .collect()
-                    })
+            })
+            .unwrap_or_default();
+
+        let uuid_parameters = self
+            .uuid_parameters
+            .and_then(|value| {
+                value.as_object().map(|object| {
+                    object
+                        .iter()
+                        .filter_map(|(key, value)| {
+                            value.as_i64().map(|value| (key.clone(), value as i32))
+                        })
+                        .collect()
                 })
-                .unwrap_or_default();
-
-            let abstract_event = AbstractEvent {
-                __typename: raw_typename.clone().into(),
-                id: record.id as i32,
-                instance,
-                actor_id: record.actor_id as i32,
-                object_id: record.object_id as i32,
-                date: record.date.into(),
-                raw_typename,
-                string_parameters: EventStringParameters(string_parameters),
-                uuid_parameters: EventUuidParameters(uuid_parameters),
-            };
-
-            if let Ok(event) = abstract_event.try_into() {
-                events.push(event);
-            }
-        }
+            })
+            .unwrap_or_default();
+
+        let abstract_event = AbstractEvent {
+            __typename: raw_typename.clone().into(),
+            id: self.id as i32,
+            instance,
+            actor_id: self.actor_id as i32,
+            object_id: self.object_id as i32,
+            date: self.date.into(),
+            raw_typename,
+            string_parameters: EventStringParameters(string_parameters),
+            uuid_parameters: EventUuidParameters(uuid_parameters),
+        };
 
-        Ok(events_query::Output {
-            events,
-            has_next_page,
-        })
+        abstract_event.try_into().ok()
     }
 }
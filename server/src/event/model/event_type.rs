@@ -1,10 +1,11 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::database::HasArguments;
 use sqlx::encode::IsNull;
 use sqlx::mysql::MySqlTypeInfo;
 use sqlx::MySql;
 
-#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub enum RawEventType {
     #[serde(rename = "discussion/comment/archive")]
     ArchiveThread,
@@ -44,6 +45,37 @@ pub enum RawEventType {
     TrashUuid,
 }
 
+impl RawEventType {
+    pub const ALL: [RawEventType; 18] = [
+        RawEventType::ArchiveThread,
+        RawEventType::RestoreThread,
+        RawEventType::CreateComment,
+        RawEventType::CreateThread,
+        RawEventType::CreateEntity,
+        RawEventType::SetLicense,
+        RawEventType::CreateEntityLink,
+        RawEventType::RemoveEntityLink,
+        RawEventType::CreateEntityRevision,
+        RawEventType::CheckoutRevision,
+        RawEventType::RejectRevision,
+        RawEventType::CreateTaxonomyLink,
+        RawEventType::RemoveTaxonomyLink,
+        RawEventType::CreateTaxonomyTerm,
+        RawEventType::SetTaxonomyTerm,
+        RawEventType::SetTaxonomyParent,
+        RawEventType::RestoreUuid,
+        RawEventType::TrashUuid,
+    ];
+
+    /// Returns the name of the event type in the table `event`, e.g. `entity/create`.
+    pub fn name(&self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+}
+
 impl std::str::FromStr for RawEventType {
     type Err = serde_json::Error;
 
@@ -66,7 +98,7 @@ impl<'q> sqlx::Encode<'q, MySql> for RawEventType {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub enum EventType {
     #[serde(rename = "SetThreadStateNotificationEvent")]
     SetThreadState,
@@ -2,11 +2,12 @@ use std::collections::HashMap;
 use std::convert::TryFrom;
 
 use crate::database::Executor;
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use super::{AbstractEvent, Event, EventError, EventPayload, RawEventType};
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
+#[derive(Debug, Eq, PartialEq, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct RemoveTaxonomyLinkEvent {
     entity_id: i32,
@@ -1,11 +1,13 @@
 use std::convert::TryFrom;
 
+use schemars::JsonSchema;
 use serde::Serialize;
 
-use super::{AbstractEvent, Event, EventError, EventPayload, RawEventType};
+use super::{AbstractEvent, Event, EventError, RawEventType};
+use crate::repository::{EventRepository, MySqlRepository, NewEvent};
 use crate::{database::Executor, instance::Instance};
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
+#[derive(Debug, Eq, PartialEq, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct RevisionEvent {
     repository_id: i32,
@@ -68,35 +70,32 @@ impl RevisionEventPayload {
         E: Executor<'a>,
     {
         let mut transaction = executor.begin().await?;
-
-        let event = EventPayload::new(
-            self.raw_typename.clone(),
-            self.user_id,
-            self.revision_id,
-            self.instance.fetch_id(&mut transaction).await?,
-            [("reason".to_string(), self.reason.to_string())]
-                .iter()
-                .cloned()
-                .collect(),
-            [("repository".to_string(), self.repository_id)]
-                .iter()
-                .cloned()
-                .collect(),
-        )
-        .save(&mut transaction)
-        .await?;
-
+        let event = MySqlRepository::new(&mut transaction)
+            .save_event(self.into())
+            .await?;
         transaction.commit().await?;
 
         Ok(event)
     }
 }
 
+impl From<&RevisionEventPayload> for NewEvent {
+    fn from(payload: &RevisionEventPayload) -> Self {
+        Self {
+            raw_typename: payload.raw_typename.clone(),
+            actor_id: payload.user_id,
+            object_id: payload.revision_id,
+            instance: payload.instance.clone(),
+            string_parameters: [("reason".to_string(), payload.reason.clone())].into(),
+            uuid_parameters: [("repository".to_string(), payload.repository_id)].into(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use chrono::Duration;
-
     use super::RevisionEventPayload;
+    use crate::clock::MockClock;
     use crate::create_database_pool;
     use crate::datetime::DateTime;
     use crate::event::{AbstractEvent, ConcreteEvent, Event, RevisionEvent};
@@ -106,6 +105,7 @@ mod tests {
     async fn checkout_revision() {
         let pool = create_database_pool().await.unwrap();
         let mut transaction = pool.begin().await.unwrap();
+        let _clock = MockClock::freeze(DateTime::ymd(2023, 5, 1));
 
         let event = RevisionEventPayload::new(
             false,
@@ -146,7 +146,7 @@ mod tests {
             assert_eq!(reason, "Improve explanation".to_string());
             assert_eq!(string_parameters.get("reason").unwrap(), reason);
             assert_eq!(uuid_parameters.get("repository").unwrap(), 1855);
-            assert!(DateTime::now().signed_duration_since(date) < Duration::minutes(1))
+            assert_eq!(date, DateTime::ymd(2023, 5, 1))
         } else {
             panic!("Event does not fulfill assertions: {:?}", event)
         }
@@ -1,12 +1,13 @@
 use crate::database::Executor;
 use crate::event::{Event, EventError, EventPayload};
+use schemars::JsonSchema;
 use serde::Serialize;
 use std::collections::HashMap;
 
 use super::abstract_event::AbstractEvent;
 use super::RawEventType;
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
+#[derive(Debug, Eq, PartialEq, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SetLicenseEvent {
     repository_id: i32,
@@ -3,12 +3,13 @@ use std::convert::TryFrom;
 
 use crate::database::Executor;
 use crate::event::{Event, EventPayload, RawEventType};
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use super::abstract_event::AbstractEvent;
 use super::EventError;
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
+#[derive(Debug, Eq, PartialEq, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SetTaxonomyParentEvent {
     child_id: i32,
@@ -1,11 +1,12 @@
 use std::collections::HashMap;
 
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use super::{AbstractEvent, Event, EventError, EventPayload, RawEventType};
 use crate::database::Executor;
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
+#[derive(Debug, Eq, PartialEq, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SetThreadStateEvent {
     thread_id: i32,
@@ -77,9 +78,8 @@ impl SetThreadStateEventPayload {
 
 #[cfg(test)]
 mod tests {
-    use chrono::Duration;
-
     use super::{SetThreadStateEvent, SetThreadStateEventPayload};
+    use crate::clock::MockClock;
     use crate::create_database_pool;
     use crate::datetime::DateTime;
     use crate::event::{AbstractEvent, ConcreteEvent, Event};
@@ -89,6 +89,7 @@ mod tests {
     async fn archive_thread() {
         let pool = create_database_pool().await.unwrap();
         let mut transaction = pool.begin().await.unwrap();
+        let _clock = MockClock::freeze(DateTime::ymd(2023, 5, 1));
 
         let set_thread_state_event = SetThreadStateEventPayload::new(true, 16462, 17666);
 
@@ -116,7 +117,7 @@ mod tests {
                 }),
         } = event
         {
-            assert!(DateTime::now().signed_duration_since(date) < Duration::minutes(1))
+            assert_eq!(date, DateTime::ymd(2023, 5, 1))
         } else {
             panic!("Event does not fulfill assertions: {:?}", event)
         }
@@ -126,6 +127,7 @@ mod tests {
     async fn restore_thread() {
         let pool = create_database_pool().await.unwrap();
         let mut transaction = pool.begin().await.unwrap();
+        let _clock = MockClock::freeze(DateTime::ymd(2023, 5, 1));
 
         let set_thread_state_event = SetThreadStateEventPayload::new(false, 15478, 17796);
 
@@ -153,7 +155,7 @@ mod tests {
                 }),
         } = event
         {
-            assert!(DateTime::now().signed_duration_since(date) < Duration::minutes(1))
+            assert_eq!(date, DateTime::ymd(2023, 5, 1))
         } else {
             panic!("Event does not fulfill assertions: {:?}", event)
         }
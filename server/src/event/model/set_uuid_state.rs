@@ -1,11 +1,12 @@
 use std::collections::HashMap;
 
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use super::{AbstractEvent, Event, EventError, EventPayload, RawEventType};
 use crate::{database::Executor, instance::Instance};
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
+#[derive(Debug, Eq, PartialEq, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SetUuidStateEvent {
     trashed: bool,
@@ -67,9 +68,8 @@ impl SetUuidStateEventPayload {
 
 #[cfg(test)]
 mod tests {
-    use chrono::Duration;
-
     use super::{SetUuidStateEvent, SetUuidStateEventPayload};
+    use crate::clock::MockClock;
     use crate::create_database_pool;
     use crate::datetime::DateTime;
     use crate::event::{AbstractEvent, ConcreteEvent, Event};
@@ -79,6 +79,7 @@ mod tests {
     async fn trash_event() {
         let pool = create_database_pool().await.unwrap();
         let mut transaction = pool.begin().await.unwrap();
+        let _clock = MockClock::freeze(DateTime::ymd(2023, 5, 1));
 
         let set_uuid_state_event = SetUuidStateEventPayload::new(true, 1, 1855, Instance::De);
 
@@ -101,7 +102,7 @@ mod tests {
             concrete_event: ConcreteEvent::SetUuidState(SetUuidStateEvent { trashed: true }),
         } = event
         {
-            assert!(DateTime::now().signed_duration_since(date) < Duration::minutes(1))
+            assert_eq!(date, DateTime::ymd(2023, 5, 1))
         } else {
             panic!("Event does not fulfill assertions: {:?}", event)
         }
@@ -111,6 +112,7 @@ mod tests {
     async fn restore_event() {
         let pool = create_database_pool().await.unwrap();
         let mut transaction = pool.begin().await.unwrap();
+        let _clock = MockClock::freeze(DateTime::ymd(2023, 5, 1));
 
         let set_uuid_state_event = SetUuidStateEventPayload::new(false, 1, 1855, Instance::De);
 
@@ -133,7 +135,7 @@ mod tests {
             concrete_event: ConcreteEvent::SetUuidState(SetUuidStateEvent { trashed: false }),
         } = event
         {
-            assert!(DateTime::now().signed_duration_since(date) < Duration::minutes(1))
+            assert_eq!(date, DateTime::ymd(2023, 5, 1))
         } else {
             panic!("Event does not fulfill assertions: {:?}", event)
         }
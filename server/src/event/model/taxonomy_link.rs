@@ -1,11 +1,12 @@
 use std::convert::TryFrom;
 
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use super::abstract_event::AbstractEvent;
 use super::EventError;
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
+#[derive(Debug, Eq, PartialEq, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TaxonomyLinkEvent {
     parent_id: i32,
@@ -0,0 +1,415 @@
+//! Server-sent events (SSE) of new events under `GET /events/stream`.
+//!
+//! A single [`EventFeed`] per server polls the table `event_log` for new events and broadcasts
+//! them to all open streams, so the number of clients does not increase the load of the database.
+//! Each event is sent with its id and the same JSON as the output of `EventQuery`:
+//!
+//! ```text
+//! id: 86592
+//! data: {"__typename":"SetTaxonomyTermNotificationEvent","id":86592,...}
+//! ```
+//!
+//! Streams can be restricted with the query parameters `instance`, `actorId` and `objectId` (like
+//! the payload of `EventsQuery`). When a client sends the header `Last-Event-ID` (browsers do so
+//! when they reconnect), the events after this id are read from the database before new events
+//! are sent. Idle streams get a comment line from time to time so that proxies keep them open.
+//!
+//! Ids of events are assigned when they are inserted, so an event might be committed after events
+//! with higher ids. Therefore the feed re-reads the last [`RESCAN_WINDOW`] ids on every poll and
+//! broadcasts the events it has not seen yet. Streams may thus receive events out of order.
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actix_web::web::{Bytes, Data};
+use actix_web::{HttpRequest, HttpResponse};
+use serde::Deserialize;
+use sqlx::MySqlPool;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::config::EventStreamConfig;
+use crate::event::Event;
+use crate::instance::Instance;
+use crate::operation::{self, ErrorCode};
+
+pub const CONTENT_TYPE: &str = "text/event-stream";
+
+/// Number of events which are read from the database at once.
+const BATCH_SIZE: i32 = 100;
+
+/// Number of ids before the latest event which are read again on every poll, so that events
+/// which are committed later than events with higher ids are not missed.
+const RESCAN_WINDOW: i32 = 1000;
+
+/// Number of events a stream may fall behind before it reads the missed events from the
+/// database.
+const BROADCAST_CAPACITY: usize = 1024;
+
+/// Number of messages which are buffered before a stream stops reading further events.
+const BUFFER_SIZE: usize = 64;
+
+/// Restricts the events of a stream (query parameters of `GET /events/stream`).
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct Filter {
+    pub instance: Option<Instance>,
+    pub actor_id: Option<i32>,
+    pub object_id: Option<i32>,
+}
+
+impl Filter {
+    fn matches(&self, event: &Event) -> bool {
+        let event = &event.abstract_event;
+
+        self.instance
+            .iter()
+            .all(|instance| *instance == event.instance)
+            && self.actor_id.iter().all(|id| *id == event.actor_id)
+            && self
+                .object_id
+                .iter()
+                .all(|id| *id == event.object_id || event.uuid_parameters.values().contains(id))
+    }
+}
+
+/// Broadcasts new events to all open streams.
+pub struct EventFeed {
+    sender: broadcast::Sender<Arc<Event>>,
+    /// Events which have been broadcast. It is locked while events are broadcast, so that new
+    /// streams either receive an event or start after it.
+    broadcast_ids: Mutex<RecentIds>,
+    keep_alive: Duration,
+}
+
+impl EventFeed {
+    /// Creates the feed and starts polling for new events in the background.
+    pub async fn start(
+        pool: MySqlPool,
+        config: &EventStreamConfig,
+    ) -> Result<Data<Self>, sqlx::Error> {
+        let latest_id: i64 =
+            sqlx::query_scalar("SELECT CAST(COALESCE(MAX(id), 0) AS SIGNED) FROM event_log")
+                .fetch_one(&pool)
+                .await?;
+        let mut broadcast_ids = RecentIds::new(latest_id as i32);
+        // The existing events of the window must not be broadcast.
+        for id in Event::fetch_ids_after(
+            broadcast_ids.window_start(),
+            &Filter::default(),
+            None,
+            RESCAN_WINDOW,
+            &pool,
+        )
+        .await?
+        {
+            broadcast_ids.insert(id);
+        }
+
+        let (sender, _) = broadcast::channel(BROADCAST_CAPACITY);
+        let feed = Data::new(Self {
+            sender,
+            broadcast_ids: Mutex::new(broadcast_ids),
+            keep_alive: Duration::from_secs(config.keep_alive_seconds),
+        });
+
+        let poll_interval = Duration::from_millis(config.poll_interval_milliseconds);
+        let poller = feed.clone();
+        actix_rt::spawn(async move {
+            loop {
+                match poller.poll(&pool).await {
+                    Ok(true) => continue,
+                    Ok(false) => {}
+                    Err(error) => tracing::error!(%error, "could not poll new events"),
+                }
+                actix_rt::time::sleep(poll_interval).await;
+            }
+        });
+
+        Ok(feed)
+    }
+
+    /// Broadcasts the events of the window and after it which have not been broadcast yet.
+    /// Returns `true` when there might be more.
+    async fn poll(&self, pool: &MySqlPool) -> Result<bool, sqlx::Error> {
+        let window_start = self.broadcast_ids.lock().unwrap().window_start();
+        let limit = RESCAN_WINDOW + BATCH_SIZE;
+        let ids =
+            Event::fetch_ids_after(window_start, &Filter::default(), None, limit, pool).await?;
+        let new_ids: Vec<i32> = {
+            let broadcast_ids = self.broadcast_ids.lock().unwrap();
+            ids.iter()
+                .copied()
+                .filter(|id| broadcast_ids.is_new(*id))
+                .collect()
+        };
+
+        // Without open streams the events do not need to be read.
+        let events = if self.sender.receiver_count() > 0 {
+            Event::fetch_many(&new_ids, pool).await?
+        } else {
+            Vec::new()
+        };
+
+        let mut broadcast_ids = self.broadcast_ids.lock().unwrap();
+        for id in new_ids {
+            broadcast_ids.insert(id);
+        }
+        for event in events {
+            // Sending only fails when all streams have been closed meanwhile.
+            let _ = self.sender.send(Arc::new(event));
+        }
+
+        Ok(ids.len() == limit as usize)
+    }
+
+    fn subscribe(&self) -> (broadcast::Receiver<Arc<Event>>, i32) {
+        let broadcast_ids = self.broadcast_ids.lock().unwrap();
+        (self.sender.subscribe(), broadcast_ids.latest)
+    }
+
+    /// Opens a stream of the events matching `filter`. Without `last_event_id` it starts with the
+    /// next new event.
+    pub async fn respond(
+        &self,
+        pool: MySqlPool,
+        filter: Filter,
+        last_event_id: Option<i32>,
+    ) -> HttpResponse {
+        let instance_id = match filter.instance.as_ref() {
+            Some(instance) => match Instance::fetch_id(instance, &pool).await {
+                Ok(id) => Some(id),
+                Err(error) => {
+                    let (status, body) = operation::Error::from(error).status_and_body();
+                    return HttpResponse::build(status).json(body);
+                }
+            },
+            None => None,
+        };
+        let (receiver, latest_id) = self.subscribe();
+        let (sender, body_receiver) = mpsc::channel(BUFFER_SIZE);
+        let mut subscriber = Subscriber {
+            pool,
+            filter,
+            instance_id,
+            sent_ids: RecentIds::new(last_event_id.unwrap_or(latest_id)),
+            sender,
+        };
+        let keep_alive = self.keep_alive;
+
+        actix_rt::spawn(async move {
+            if let Err(error) = subscriber.run(receiver, last_event_id, keep_alive).await {
+                // Clients reconnect with the id of the last event they received.
+                tracing::error!(%error, "event stream failed");
+            }
+        });
+
+        let body = futures::stream::unfold(body_receiver, |mut receiver| async move {
+            receiver
+                .recv()
+                .await
+                .map(|chunk| (Ok::<_, actix_web::Error>(chunk), receiver))
+        });
+
+        HttpResponse::Ok()
+            .content_type(CONTENT_TYPE)
+            .insert_header(("Cache-Control", "no-cache"))
+            .streaming(body)
+    }
+}
+
+/// Sends the events of a single stream.
+struct Subscriber {
+    pool: MySqlPool,
+    filter: Filter,
+    instance_id: Option<i32>,
+    /// Events which have been sent or skipped. Its latest id is the position of the stream.
+    sent_ids: RecentIds,
+    sender: mpsc::Sender<Bytes>,
+}
+
+impl Subscriber {
+    /// Sends events until the client closes the connection.
+    async fn run(
+        &mut self,
+        mut receiver: broadcast::Receiver<Arc<Event>>,
+        last_event_id: Option<i32>,
+        keep_alive: Duration,
+    ) -> Result<(), sqlx::Error> {
+        if last_event_id.is_some() && !self.catch_up().await? {
+            return Ok(());
+        }
+
+        loop {
+            let connected = match actix_rt::time::timeout(keep_alive, receiver.recv()).await {
+                Ok(Ok(event)) => {
+                    let id = event.abstract_event.id;
+                    if !self.sent_ids.is_new(id) {
+                        continue;
+                    }
+                    self.sent_ids.insert(id);
+                    !self.filter.matches(&event) || self.send(&event).await
+                }
+                Ok(Err(broadcast::error::RecvError::Lagged(_))) => self.catch_up().await?,
+                Ok(Err(broadcast::error::RecvError::Closed)) => false,
+                Err(_) => self
+                    .sender
+                    .send(Bytes::from(": keep-alive\n\n"))
+                    .await
+                    .is_ok(),
+            };
+
+            if !connected {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Sends the events after the position of the stream from the database. Returns `false`
+    /// when the client has closed the connection.
+    async fn catch_up(&mut self) -> Result<bool, sqlx::Error> {
+        loop {
+            let ids = Event::fetch_ids_after(
+                self.sent_ids.latest,
+                &self.filter,
+                self.instance_id,
+                BATCH_SIZE,
+                &self.pool,
+            )
+            .await?;
+
+            for event in Event::fetch_many(&ids, &self.pool).await? {
+                if !self.send(&event).await {
+                    return Ok(false);
+                }
+            }
+            for id in &ids {
+                self.sent_ids.insert(*id);
+            }
+
+            if ids.len() < BATCH_SIZE as usize {
+                return Ok(true);
+            }
+        }
+    }
+
+    async fn send(&self, event: &Event) -> bool {
+        match to_message(event) {
+            Ok(message) => self.sender.send(message).await.is_ok(),
+            Err(error) => {
+                tracing::error!(%error, id = event.abstract_event.id, "could not serialize event");
+                true
+            }
+        }
+    }
+}
+
+/// Returns the value of the header `Last-Event-ID` or the error response for an invalid id.
+pub fn last_event_id(req: &HttpRequest) -> Result<Option<i32>, HttpResponse> {
+    let value = match req.headers().get("Last-Event-ID") {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .map(Some)
+        .ok_or_else(|| {
+            HttpResponse::BadRequest().json(
+                ErrorCode::InvalidParameter.to_body("Last-Event-ID must be the id of an event."),
+            )
+        })
+}
+
+/// Ids of the events of the trailing window before and including the latest event.
+#[derive(Debug)]
+struct RecentIds {
+    latest: i32,
+    ids: BTreeSet<i32>,
+}
+
+impl RecentIds {
+    fn new(latest: i32) -> Self {
+        Self {
+            latest,
+            ids: BTreeSet::new(),
+        }
+    }
+
+    /// Id after which the window starts.
+    fn window_start(&self) -> i32 {
+        self.latest.saturating_sub(RESCAN_WINDOW).max(0)
+    }
+
+    /// Returns whether `id` is inside or after the window and has not been seen yet.
+    fn is_new(&self, id: i32) -> bool {
+        id > self.window_start() && !self.ids.contains(&id)
+    }
+
+    fn insert(&mut self, id: i32) {
+        if id > self.latest {
+            self.latest = id;
+            self.ids = self.ids.split_off(&(self.window_start() + 1));
+        }
+        self.ids.insert(id);
+    }
+}
+
+fn to_message(event: &Event) -> serde_json::Result<Bytes> {
+    let data = serde_json::to_string(event)?;
+    Ok(Bytes::from(format!(
+        "id: {}\ndata: {data}\n\n",
+        event.abstract_event.id
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::{last_event_id, RecentIds, RESCAN_WINDOW};
+
+    #[test]
+    fn reads_last_event_id_from_header() {
+        let req = TestRequest::default()
+            .insert_header(("Last-Event-ID", "86591"))
+            .to_http_request();
+
+        assert_eq!(last_event_id(&req).unwrap(), Some(86591));
+        assert_eq!(
+            last_event_id(&TestRequest::default().to_http_request()).unwrap(),
+            None
+        );
+        assert!(last_event_id(
+            &TestRequest::default()
+                .insert_header(("Last-Event-ID", "latest"))
+                .to_http_request()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn accepts_late_ids_inside_window_once() {
+        let mut ids = RecentIds::new(5000);
+
+        assert!(ids.is_new(5001));
+        assert!(ids.is_new(4990));
+        assert!(!ids.is_new(5000 - RESCAN_WINDOW));
+
+        ids.insert(5001);
+        ids.insert(4990);
+
+        assert!(!ids.is_new(5001));
+        assert!(!ids.is_new(4990));
+        assert!(ids.is_new(4995));
+
+        ids.insert(5000 + RESCAN_WINDOW);
+
+        assert!(!ids.is_new(4995));
+        assert_eq!(
+            ids.ids.into_iter().collect::<Vec<_>>(),
+            vec![5001, 5000 + RESCAN_WINDOW]
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! Readiness check of the server, served via `GET /.well-known/ready`.
+//!
+//! In contrast to `GET /.well-known/health` (which only shows that the process is alive) the
+//! readiness check verifies that messages can be served: the database is reachable, the pool has
+//! free connections and the reference rows which events and instances rely on exist.
+use std::collections::HashSet;
+use std::future::Future;
+use std::time::Duration;
+
+use serde::Serialize;
+use sqlx::{MySqlPool, Row};
+
+use crate::event::RawEventType;
+use crate::instance::Instance;
+
+/// Names of the event parameters used by the events in [`crate::event`].
+pub const EVENT_PARAMETER_NAMES: [&str; 8] = [
+    "discussion",
+    "from",
+    "object",
+    "on",
+    "parent",
+    "reason",
+    "repository",
+    "to",
+];
+
+/// Maximum time for each query of the readiness check.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Readiness {
+    pub ready: bool,
+    pub pool: PoolStatus,
+    pub checks: Vec<Check>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolStatus {
+    pub size: u32,
+    pub idle: usize,
+    pub saturated: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<String>,
+}
+
+impl Check {
+    fn from_result(name: &'static str, result: Result<Vec<String>, String>) -> Self {
+        match result {
+            Ok(missing) => Self {
+                name,
+                ok: missing.is_empty(),
+                error: None,
+                missing,
+            },
+            Err(error) => Self {
+                name,
+                ok: false,
+                error: Some(error),
+                missing: Vec::new(),
+            },
+        }
+    }
+}
+
+/// Checks whether the server is ready to serve messages. `replica_pool` is the pool of the read
+/// replica if there is one.
+pub async fn check_readiness(pool: &MySqlPool, replica_pool: Option<&MySqlPool>) -> Readiness {
+    let (pool_status, database) = ping(pool).await;
+    let mut checks = vec![Check::from_result("database", database)];
+
+    // Without a free connection the other queries would only wait until the acquire timeout.
+    if !pool_status.saturated {
+        if let Some(replica_pool) = replica_pool {
+            let (_, replica_database) = ping(replica_pool).await;
+            checks.push(Check::from_result("replicaDatabase", replica_database));
+        }
+        checks.push(Check::from_result(
+            "instances",
+            missing_rows(
+                pool,
+                "SELECT subdomain AS name FROM instance",
+                Instance::ALL.iter().map(|instance| instance.to_string()),
+            )
+            .await,
+        ));
+        checks.push(Check::from_result(
+            "eventTypes",
+            missing_rows(
+                pool,
+                "SELECT name FROM event",
+                RawEventType::ALL.iter().map(|event_type| event_type.name()),
+            )
+            .await,
+        ));
+        checks.push(Check::from_result(
+            "eventParameterNames",
+            missing_rows(
+                pool,
+                "SELECT name FROM event_parameter_name",
+                EVENT_PARAMETER_NAMES.iter().map(|name| name.to_string()),
+            )
+            .await,
+        ));
+    }
+
+    Readiness {
+        ready: checks.iter().all(|check| check.ok),
+        pool: pool_status,
+        checks,
+    }
+}
+
+/// Runs `SELECT 1` on a connection of the pool. The pool counts as saturated when no connection
+/// becomes free within [`QUERY_TIMEOUT`].
+async fn ping(pool: &MySqlPool) -> (PoolStatus, Result<Vec<String>, String>) {
+    let mut pool_status = PoolStatus {
+        size: pool.size(),
+        idle: pool.num_idle(),
+        saturated: false,
+    };
+
+    let result = match actix_rt::time::timeout(QUERY_TIMEOUT, pool.acquire()).await {
+        Ok(Ok(mut connection)) => with_timeout(sqlx::query("SELECT 1").execute(&mut connection))
+            .await
+            .map(|_| Vec::new()),
+        Ok(Err(error)) => Err(error.to_string()),
+        Err(_) => {
+            pool_status.saturated = true;
+            Err(format!(
+                "no free connection in the pool within {} seconds",
+                QUERY_TIMEOUT.as_secs()
+            ))
+        }
+    };
+
+    (pool_status, result)
+}
+
+/// Returns the expected names which are missing in the column `name` of the query result.
+async fn missing_rows(
+    pool: &MySqlPool,
+    query: &str,
+    expected: impl Iterator<Item = String>,
+) -> Result<Vec<String>, String> {
+    let rows = with_timeout(sqlx::query(query).fetch_all(pool)).await?;
+    let existing = rows
+        .iter()
+        .map(|row| row.try_get::<String, _>("name"))
+        .collect::<Result<HashSet<_>, _>>()
+        .map_err(|error| error.to_string())?;
+
+    Ok(expected.filter(|name| !existing.contains(name)).collect())
+}
+
+async fn with_timeout<T>(
+    future: impl Future<Output = Result<T, sqlx::Error>>,
+) -> Result<T, String> {
+    match actix_rt::time::timeout(QUERY_TIMEOUT, future).await {
+        Ok(result) => result.map_err(|error| error.to_string()),
+        Err(_) => Err(format!(
+            "query took longer than {} seconds",
+            QUERY_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::fs;
+    use std::path::Path;
+
+    use regex::Regex;
+    use schemars::schema::RootSchema;
+    use schemars::schema_for;
+
+    use super::EVENT_PARAMETER_NAMES;
+    use crate::event::RawEventType;
+    use crate::instance::Instance;
+
+    /// Returns the serialized names of all variants of an enum.
+    fn variant_names(schema: RootSchema) -> BTreeSet<String> {
+        serde_json::to_value(schema).unwrap()["enum"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|name| name.as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn checks_all_instances() {
+        let checked: BTreeSet<_> = Instance::ALL.iter().map(Instance::to_string).collect();
+
+        assert_eq!(checked, variant_names(schema_for!(Instance)));
+    }
+
+    #[test]
+    fn checks_all_event_types() {
+        let checked: BTreeSet<_> = RawEventType::ALL.iter().map(RawEventType::name).collect();
+
+        assert_eq!(checked, variant_names(schema_for!(RawEventType)));
+    }
+
+    #[test]
+    fn checks_all_event_parameter_names() {
+        // Events set their parameters like `("repository".to_string(), self.entity_id)`.
+        let parameter = Regex::new(r#"\("([a-z_]+)"\.to_string\(\),"#).unwrap();
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/event/model");
+        let mut used = BTreeSet::new();
+        for file in fs::read_dir(directory).unwrap() {
+            let source = fs::read_to_string(file.unwrap().path()).unwrap();
+            used.extend(
+                parameter
+                    .captures_iter(&source)
+                    .map(|captures| captures[1].to_string()),
+            );
+        }
+
+        let checked: BTreeSet<_> = EVENT_PARAMETER_NAMES
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+
+        assert_eq!(checked, used);
+    }
+}
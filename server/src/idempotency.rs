@@ -0,0 +1,314 @@
+//! Idempotency keys for mutations sent to `POST /`.
+//!
+//! Clients which retry a mutation (e.g. after a timeout) send the same `Idempotency-Key` header
+//! with every attempt. The first request stores the key in the table `idempotency_key` before
+//! the mutation is executed and the response in the same transaction as the mutation. Repeated
+//! requests get the stored response (marked with the header `Idempotency-Replayed: true`)
+//! instead of executing the mutation again. A request with a key whose mutation is still running is rejected with `409`,
+//! so that concurrent duplicates never both execute. Keys expire after
+//! [`IdempotencyConfig::window_seconds`].
+//!
+//! Responses with a server error are not stored and their transaction is rolled back, so that the
+//! mutation can be retried with the same key. The same happens when the request is cancelled
+//! (e.g. because the client disconnected). When the server stops while executing a mutation, the reservation is taken over
+//! by the next request with the key after [`IdempotencyConfig::lease_seconds`].
+//!
+//! The table `idempotency_key` is created by the migrations in `server/migrations`.
+use actix_web::body::to_bytes;
+use actix_web::http::header::HeaderMap;
+use actix_web::http::StatusCode;
+use actix_web::web::Bytes;
+use actix_web::HttpResponse;
+use sqlx::mysql::MySqlDatabaseError;
+use sqlx::{MySql, MySqlPool, Row, Transaction};
+
+use crate::config::IdempotencyConfig;
+use crate::operation::ErrorCode;
+
+pub const HEADER: &str = "Idempotency-Key";
+pub const REPLAYED_HEADER: &str = "Idempotency-Replayed";
+
+const MAX_KEY_LENGTH: usize = 255;
+const DUPLICATE_ENTRY: u16 = 1062;
+/// Number of attempts to reserve a key whose row vanishes in between (e.g. because it expired).
+const RESERVE_ATTEMPTS: usize = 3;
+
+/// Reserves `key` for the mutation identified by `fingerprint`, see the module documentation.
+/// Returns the response to send instead of executing the mutation when the key cannot be
+/// reserved (e.g. because its response is already stored).
+pub async fn reserve_key(
+    pool: &MySqlPool,
+    config: &IdempotencyConfig,
+    key: &str,
+    fingerprint: &str,
+) -> Result<ReservedKey, HttpResponse> {
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidParameter,
+            "Idempotency-Key must have between 1 and 255 characters.",
+        ));
+    }
+
+    match reserve(pool, config, key, fingerprint).await {
+        // Releases the key when it is dropped before the response is stored.
+        Ok(Reservation::Reserved) => Ok(ReservedKey {
+            guard: ReservationGuard {
+                pool: pool.clone(),
+                key: Some(key.to_string()),
+            },
+        }),
+        Ok(Reservation::Stored { status, body }) => Err(replay(status, body)),
+        Ok(Reservation::InProgress) => Err(error_response(
+            StatusCode::CONFLICT,
+            ErrorCode::IdempotencyKeyInUse,
+            "A request with this Idempotency-Key is still being processed.",
+        )),
+        Ok(Reservation::OtherMessage) => Err(error_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::IdempotencyKeyReused,
+            "The Idempotency-Key was already used for another message.",
+        )),
+        Err(error) => Err(database_error(error)),
+    }
+}
+
+/// A key reserved by [`reserve_key`] whose mutation is executed.
+pub struct ReservedKey {
+    guard: ReservationGuard,
+}
+
+impl ReservedKey {
+    /// Stores `response` in `transaction`, which contains the changes of the mutation, and commits
+    /// it, so that either both or none of them are saved. Server errors roll the transaction back
+    /// and release the key instead, so that the mutation can be retried.
+    pub async fn complete(
+        mut self,
+        mut transaction: Transaction<'_, MySql>,
+        response: HttpResponse,
+    ) -> HttpResponse {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = match to_bytes(response.into_body()).await {
+            Ok(body) if !status.is_server_error() => body,
+            result => {
+                self.guard.release().await;
+                return match result {
+                    Ok(body) => build_response(status, &headers, body),
+                    Err(_) => error_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        ErrorCode::InternalServerError,
+                        "Internal server error.",
+                    ),
+                };
+            }
+        };
+
+        let key = self.guard.key.as_deref().unwrap_or_default();
+        let stored = match store(&mut transaction, key, status, &body).await {
+            Ok(()) => transaction.commit().await,
+            Err(error) => Err(error),
+        };
+
+        match stored {
+            Ok(()) => {
+                self.guard.disarm();
+                build_response(status, &headers, body)
+            }
+            Err(error) => {
+                // The transaction is rolled back when it is dropped.
+                tracing::error!(%error, "could not store response of idempotency key");
+                self.guard.release().await;
+                error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ErrorCode::DatabaseError,
+                    "Internal server error.",
+                )
+            }
+        }
+    }
+}
+
+fn build_response(status: StatusCode, headers: &HeaderMap, body: Bytes) -> HttpResponse {
+    let mut builder = HttpResponse::build(status);
+    for (name, value) in headers.iter() {
+        builder.insert_header((name.clone(), value.clone()));
+    }
+    builder.body(body)
+}
+
+enum Reservation {
+    Reserved,
+    Stored { status: u16, body: Vec<u8> },
+    InProgress,
+    OtherMessage,
+}
+
+async fn reserve(
+    pool: &MySqlPool,
+    config: &IdempotencyConfig,
+    key: &str,
+    fingerprint: &str,
+) -> Result<Reservation, sqlx::Error> {
+    sqlx::query(
+        "DELETE FROM idempotency_key WHERE created_at < NOW() - INTERVAL ? SECOND LIMIT 100",
+    )
+    .bind(config.window_seconds)
+    .execute(pool)
+    .await?;
+
+    for _ in 0..RESERVE_ATTEMPTS {
+        let inserted = sqlx::query(
+            r#"
+                INSERT INTO idempotency_key (`key`, fingerprint, created_at)
+                    VALUES (?, ?, NOW())
+            "#,
+        )
+        .bind(key)
+        .bind(fingerprint)
+        .execute(pool)
+        .await;
+
+        match inserted {
+            Ok(_) => return Ok(Reservation::Reserved),
+            Err(sqlx::Error::Database(error))
+                if matches!(
+                    error.try_downcast_ref::<MySqlDatabaseError>(),
+                    Some(error) if error.number() == DUPLICATE_ENTRY
+                ) => {}
+            Err(error) => return Err(error),
+        }
+
+        // Takes over keys which have expired but are not deleted yet as well as reservations
+        // whose request has not stored a response within the lease.
+        let taken_over = sqlx::query(
+            r#"
+                UPDATE idempotency_key
+                    SET fingerprint = ?, status = NULL, response = NULL, created_at = NOW()
+                    WHERE `key` = ? AND (
+                        created_at < NOW() - INTERVAL ? SECOND OR
+                        (status IS NULL AND created_at < NOW() - INTERVAL ? SECOND)
+                    )
+            "#,
+        )
+        .bind(fingerprint)
+        .bind(key)
+        .bind(config.window_seconds)
+        .bind(config.lease_seconds)
+        .execute(pool)
+        .await?;
+
+        if taken_over.rows_affected() > 0 {
+            return Ok(Reservation::Reserved);
+        }
+
+        let row = sqlx::query(
+            r#"
+                SELECT fingerprint, status, response
+                    FROM idempotency_key
+                    WHERE `key` = ? AND created_at >= NOW() - INTERVAL ? SECOND
+            "#,
+        )
+        .bind(key)
+        .bind(config.window_seconds)
+        .fetch_optional(pool)
+        .await?;
+
+        let row = match row {
+            Some(row) => row,
+            // The key has been released or has expired in the meantime.
+            None => continue,
+        };
+
+        if row.try_get::<String, _>("fingerprint")? != fingerprint {
+            return Ok(Reservation::OtherMessage);
+        }
+
+        return match (
+            row.try_get::<Option<u16>, _>("status")?,
+            row.try_get::<Option<Vec<u8>>, _>("response")?,
+        ) {
+            (Some(status), Some(body)) => Ok(Reservation::Stored { status, body }),
+            _ => Ok(Reservation::InProgress),
+        };
+    }
+
+    Ok(Reservation::InProgress)
+}
+
+/// Deletes the reservation of a key unless it is disarmed, also when the request is dropped
+/// while the mutation is running.
+struct ReservationGuard {
+    pool: MySqlPool,
+    key: Option<String>,
+}
+
+impl ReservationGuard {
+    async fn release(&mut self) {
+        if let Some(key) = self.key.take() {
+            release(&self.pool, &key).await;
+        }
+    }
+
+    fn disarm(&mut self) {
+        self.key = None;
+    }
+}
+
+impl Drop for ReservationGuard {
+    fn drop(&mut self) {
+        if let (Some(key), Ok(runtime)) = (self.key.take(), tokio::runtime::Handle::try_current()) {
+            let pool = self.pool.clone();
+            runtime.spawn(async move { release(&pool, &key).await });
+        }
+    }
+}
+
+async fn store(
+    transaction: &mut Transaction<'_, MySql>,
+    key: &str,
+    status: StatusCode,
+    body: &[u8],
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE idempotency_key SET status = ?, response = ? WHERE `key` = ?")
+        .bind(status.as_u16())
+        .bind(body)
+        .bind(key)
+        .execute(transaction)
+        .await?;
+
+    Ok(())
+}
+
+/// Deletes the key so that the mutation can be retried.
+async fn release(pool: &MySqlPool, key: &str) {
+    if let Err(error) = sqlx::query("DELETE FROM idempotency_key WHERE `key` = ?")
+        .bind(key)
+        .execute(pool)
+        .await
+    {
+        tracing::error!(%error, "could not release idempotency key");
+    }
+}
+
+fn replay(status: u16, body: Vec<u8>) -> HttpResponse {
+    HttpResponse::build(StatusCode::from_u16(status).unwrap_or(StatusCode::OK))
+        .content_type("application/json; charset=utf-8")
+        .insert_header((REPLAYED_HEADER, "true"))
+        .body(body)
+}
+
+fn database_error(error: sqlx::Error) -> HttpResponse {
+    tracing::error!(%error, "could not reserve idempotency key");
+    error_response(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        ErrorCode::DatabaseError,
+        "Internal server error.",
+    )
+}
+
+fn error_response(status: StatusCode, code: ErrorCode, message: &str) -> HttpResponse {
+    HttpResponse::build(status)
+        .content_type("application/json; charset=utf-8")
+        .json(code.to_body(message))
+}
@@ -1,5 +1,6 @@
 use std::fmt;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::database::HasArguments;
 use sqlx::encode::IsNull;
@@ -9,7 +10,7 @@ use sqlx::{Error, MySql};
 use crate::database::Executor;
 use std::fmt::Formatter;
 
-#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum Instance {
     De,
@@ -21,6 +22,15 @@ pub enum Instance {
 }
 
 impl Instance {
+    pub const ALL: [Instance; 6] = [
+        Instance::De,
+        Instance::En,
+        Instance::Es,
+        Instance::Fr,
+        Instance::Hi,
+        Instance::Ta,
+    ];
+
     pub async fn fetch_id<'a, E>(&self, executor: E) -> Result<i32, Error>
     where
         E: Executor<'a>,
@@ -32,6 +42,20 @@ impl Instance {
                 .id,
         )
     }
+
+    /// Returns `None` when there is no supported instance with the id.
+    pub async fn fetch_by_id<'a, E>(id: i32, executor: E) -> Result<Option<Self>, Error>
+    where
+        E: Executor<'a>,
+    {
+        let subdomain: Option<String> =
+            sqlx::query_scalar("SELECT subdomain FROM instance WHERE id = ?")
+                .bind(id)
+                .fetch_optional(executor)
+                .await?;
+
+        Ok(subdomain.and_then(|subdomain| subdomain.parse().ok()))
+    }
 }
 
 impl std::str::FromStr for Instance {
@@ -0,0 +1,174 @@
+//! Invariants of the database which the code relies on but the schema does not enforce.
+//!
+//! Each [`Rule`] is a query returning the ids of the rows which violate it. Violations typically
+//! show up as `UuidQuery` responding with 404 (see [`crate::uuid::UuidError`]). The rules are
+//! checked by the binary `dbl-check`.
+use serde::Serialize;
+
+use crate::database::Executor;
+use crate::uuid::Discriminator;
+
+pub struct Rule {
+    /// Stable identifier of the rule, e.g. to select it in `dbl-check --rule`.
+    pub id: &'static str,
+    pub description: &'static str,
+    /// Query selecting the ids of the violations in the column `id`. `{discriminators}` is replaced
+    /// by the list of all supported discriminators (see [`Discriminator::ALL`]).
+    query: &'static str,
+}
+
+/// Violations of a single rule.
+#[derive(Debug, Serialize)]
+pub struct Violations {
+    pub rule: &'static str,
+    pub description: &'static str,
+    pub ids: Vec<i32>,
+}
+
+impl Rule {
+    pub async fn check<'a, E>(&self, executor: E) -> Result<Violations, sqlx::Error>
+    where
+        E: Executor<'a>,
+    {
+        let discriminators = Discriminator::ALL
+            .iter()
+            .map(|discriminator| format!("'{}'", discriminator.name()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let query = format!(
+            "SELECT DISTINCT CAST(id AS SIGNED) FROM ({}) violations ORDER BY 1",
+            self.query.replace("{discriminators}", &discriminators)
+        );
+        let ids: Vec<i64> = sqlx::query_scalar(&query).fetch_all(executor).await?;
+
+        Ok(Violations {
+            rule: self.id,
+            description: self.description,
+            ids: ids.into_iter().map(|id| id as i32).collect(),
+        })
+    }
+}
+
+pub fn find_rule(id: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| rule.id == id)
+}
+
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "uuid-without-row",
+        description: "Every uuid has a row in the table of its discriminator (e.g. `entity`).",
+        query: r#"
+            SELECT u.id FROM uuid u
+            WHERE (u.discriminator = 'attachment'
+                    AND NOT EXISTS (SELECT 1 FROM attachment_container WHERE id = u.id))
+                OR (u.discriminator = 'blogPost'
+                    AND NOT EXISTS (SELECT 1 FROM blog_post WHERE id = u.id))
+                OR (u.discriminator = 'comment'
+                    AND NOT EXISTS (SELECT 1 FROM comment WHERE id = u.id))
+                OR (u.discriminator = 'entity'
+                    AND NOT EXISTS (SELECT 1 FROM entity WHERE id = u.id))
+                OR (u.discriminator = 'entityRevision'
+                    AND NOT EXISTS (SELECT 1 FROM entity_revision WHERE id = u.id))
+                OR (u.discriminator = 'page'
+                    AND NOT EXISTS (SELECT 1 FROM page_repository WHERE id = u.id))
+                OR (u.discriminator = 'pageRevision'
+                    AND NOT EXISTS (SELECT 1 FROM page_revision WHERE id = u.id))
+                OR (u.discriminator = 'taxonomyTerm'
+                    AND NOT EXISTS (SELECT 1 FROM term_taxonomy WHERE id = u.id))
+                OR (u.discriminator = 'user'
+                    AND NOT EXISTS (SELECT 1 FROM user WHERE id = u.id))
+        "#,
+    },
+    Rule {
+        id: "uuid-with-unknown-discriminator",
+        description: "Every uuid has a discriminator which is supported by the database layer.",
+        query: r#"
+            SELECT id FROM uuid
+            WHERE discriminator NOT IN ({discriminators})
+        "#,
+    },
+    Rule {
+        id: "entity-current-revision-of-other-repository",
+        description: "The current revision of an entity is a revision of the same entity.",
+        query: r#"
+            SELECT e.id FROM entity e
+            LEFT JOIN entity_revision r ON r.id = e.current_revision_id
+            WHERE e.current_revision_id IS NOT NULL
+                AND (r.id IS NULL OR r.repository_id != e.id)
+        "#,
+    },
+    Rule {
+        id: "page-current-revision-of-other-repository",
+        description: "The current revision of a page is a revision of the same page.",
+        query: r#"
+            SELECT p.id FROM page_repository p
+            LEFT JOIN page_revision r ON r.id = p.current_revision_id
+            WHERE p.current_revision_id IS NOT NULL
+                AND (r.id IS NULL OR r.page_repository_id != p.id)
+        "#,
+    },
+    Rule {
+        id: "entity-revision-without-repository",
+        description: "Every entity revision belongs to an existing entity.",
+        query: r#"
+            SELECT r.id FROM entity_revision r
+            WHERE NOT EXISTS (SELECT 1 FROM entity WHERE id = r.repository_id)
+        "#,
+    },
+    Rule {
+        id: "entity-without-required-parent",
+        description: "Solutions have an exercise or grouped exercise as parent, course pages and \
+            grouped exercises have a parent.",
+        query: r#"
+            SELECT e.id FROM entity e
+            JOIN type t ON t.id = e.type_id
+            WHERE (t.name = 'text-solution'
+                    AND NOT EXISTS (
+                        SELECT 1 FROM entity_link l
+                        JOIN entity p ON p.id = l.parent_id
+                        JOIN type pt ON pt.id = p.type_id
+                        WHERE l.child_id = e.id
+                            AND pt.name IN ('text-exercise', 'grouped-text-exercise')))
+                OR (t.name IN ('course-page', 'grouped-text-exercise')
+                    AND NOT EXISTS (SELECT 1 FROM entity_link WHERE child_id = e.id))
+        "#,
+    },
+    Rule {
+        id: "taxonomy-term-without-parent-row",
+        description: "The parent of a taxonomy term exists.",
+        query: r#"
+            SELECT t.id FROM term_taxonomy t
+            WHERE t.parent_id IS NOT NULL
+                AND NOT EXISTS (SELECT 1 FROM term_taxonomy WHERE id = t.parent_id)
+        "#,
+    },
+    Rule {
+        id: "taxonomy-term-cycle",
+        description: "Taxonomy terms are no ancestors of themselves.",
+        query: r#"
+            WITH RECURSIVE ancestors (id, ancestor_id, depth) AS (
+                SELECT id, parent_id, 1 FROM term_taxonomy WHERE parent_id IS NOT NULL
+
+                UNION ALL
+
+                SELECT a.id, t.parent_id, a.depth + 1
+                FROM ancestors a
+                JOIN term_taxonomy t ON t.id = a.ancestor_id
+                WHERE t.parent_id IS NOT NULL AND a.ancestor_id != a.id AND a.depth < 100
+            )
+            SELECT id FROM ancestors WHERE ancestor_id = id
+        "#,
+    },
+    Rule {
+        id: "comment-without-object",
+        description: "Threads belong to an existing uuid, replies to an existing thread.",
+        query: r#"
+            SELECT c.id FROM comment c
+            WHERE (c.uuid_id IS NULL AND c.parent_id IS NULL)
+                OR (c.uuid_id IS NOT NULL
+                    AND NOT EXISTS (SELECT 1 FROM uuid WHERE id = c.uuid_id))
+                OR (c.parent_id IS NOT NULL
+                    AND NOT EXISTS (SELECT 1 FROM comment WHERE id = c.parent_id))
+        "#,
+    },
+];
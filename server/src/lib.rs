@@ -1,29 +1,42 @@
-use std::env;
-use std::time::Duration;
-
 use actix_web::web::Data;
 use actix_web::App;
-use dotenv::dotenv;
 use regex::Regex;
-use sqlx::mysql::{MySqlConnectOptions, MySqlPoolOptions};
 use sqlx::pool::Pool;
 use sqlx::{MySql, MySqlPool};
 use thiserror::Error;
 
+use crate::config::{Config, DatabaseConfig};
+
 pub mod alias;
+pub mod auth;
+pub mod cache;
+pub mod changes;
+pub mod cli;
+pub mod clock;
+pub mod config;
 pub mod database;
 pub mod datetime;
 pub mod event;
+pub mod event_stream;
+pub mod health;
+pub mod idempotency;
 pub mod instance;
+pub mod integrity;
 pub mod message;
 pub mod metadata;
+pub mod metrics;
 pub mod navigation;
+pub mod ndjson;
 pub mod notification;
 pub mod operation;
+pub mod outbox;
+pub mod repository;
 pub mod routes;
+pub mod schema;
 pub mod subject;
 pub mod subscription;
 pub mod thread;
+pub mod timeout;
 pub mod user;
 pub mod uuid;
 pub mod vocabulary;
@@ -58,47 +71,74 @@ where
     app.app_data(Data::new(pool)).configure(routes::init)
 }
 
+/// Registers the pool of a read replica besides the pool of the primary database, so that
+/// queries are executed on the replica (see [`database::ReplicaPool`]).
+pub fn configure_app_with_replica<T>(
+    app: App<T>,
+    pool: MySqlPool,
+    replica_pool: MySqlPool,
+) -> App<T>
+where
+    T: actix_service::ServiceFactory<
+        actix_web::dev::ServiceRequest,
+        Config = (),
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+{
+    configure_app(app, pool).app_data(Data::new(database::ReplicaPool(replica_pool)))
+}
+
+/// Creates the pool of the primary database with the configuration from the environment.
 pub async fn create_database_pool() -> Result<Pool<MySql>, ApplicationError> {
-    dotenv().ok();
-
-    let database_max_connections: u32 = env::var("DATABASE_MAX_CONNECTIONS")
-        .expect("DATABASE_MAX_CONNECTIONS is not set.")
-        .parse()
-        .unwrap();
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set.");
-    let re = Regex::new(
-        r"^mysql://(?P<username>.+):(?P<password>.+)@(?P<host>.+):(?P<port>\d+)/(?P<database>.+)$",
-    )
-    .unwrap();
-    let captures = re.captures(&database_url).unwrap();
-    let username = captures.name("username").unwrap().as_str();
-    let password = captures.name("password").unwrap().as_str();
-    let host = captures.name("host").unwrap().as_str();
-    let port: u16 = captures.name("port").unwrap().as_str().parse().unwrap();
-    let database = captures.name("database").unwrap().as_str();
-
-    let options = MySqlConnectOptions::new()
-        .host(host)
-        .port(port)
-        .username(username)
-        .password(password)
-        .database(database)
-        .charset("latin1");
-    let pool = MySqlPoolOptions::new()
-        .max_connections(database_max_connections)
-        .acquire_timeout(Duration::from_secs(10 * 60))
-        .connect_with(options)
-        .await?;
+    connect_database(&Config::load()?.database).await
+}
+
+/// Creates the pool of the read replica when `DATABASE_REPLICA_URL` is set.
+pub async fn create_replica_database_pool() -> Result<Option<Pool<MySql>>, ApplicationError> {
+    connect_replica_database(&Config::load()?.database).await
+}
+
+pub async fn connect_database(config: &DatabaseConfig) -> Result<Pool<MySql>, ApplicationError> {
+    let options = config.connect_options()?;
+    let pool = config.pool_options(&options).connect_with(options).await?;
 
     Ok(pool)
 }
 
+/// Creates the tables of the server which are not part of the Serlo database (see
+/// `server/migrations`).
+pub async fn migrate_database(pool: &Pool<MySql>) -> Result<(), ApplicationError> {
+    sqlx::migrate!().run(pool).await?;
+
+    Ok(())
+}
+
+pub async fn connect_replica_database(
+    config: &DatabaseConfig,
+) -> Result<Option<Pool<MySql>>, ApplicationError> {
+    match config.replica_connect_options()? {
+        Some(options) => Ok(Some(
+            config.pool_options(&options).connect_with(options).await?,
+        )),
+        None => Ok(None),
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum ApplicationError {
     #[error("Database error: {inner:?}.")]
     DatabaseError { inner: sqlx::Error },
+    #[error("Migration error: {inner}.")]
+    MigrationError { inner: sqlx::migrate::MigrateError },
     #[error("Server error: {inner:?}.")]
     ServerError { inner: std::io::Error },
+    #[error("Configuration error: {name} is not set.")]
+    MissingConfig { name: String },
+    #[error("Configuration error: {name} is invalid: {reason}.")]
+    InvalidConfig { name: String, reason: String },
+    #[error("Configuration error: cannot read configuration file {path:?}: {reason}.")]
+    ConfigFileError { path: String, reason: String },
 }
 
 impl From<sqlx::Error> for ApplicationError {
@@ -107,6 +147,12 @@ impl From<sqlx::Error> for ApplicationError {
     }
 }
 
+impl From<sqlx::migrate::MigrateError> for ApplicationError {
+    fn from(inner: sqlx::migrate::MigrateError) -> Self {
+        Self::MigrationError { inner }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::slugify;
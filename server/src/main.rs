@@ -1,8 +1,15 @@
 use std::env;
 
+use actix_web::web::Data;
 use actix_web::{App, HttpServer, Result};
+use tracing_subscriber::EnvFilter;
 
-use server::{configure_app, create_database_pool, ApplicationError};
+use server::config::Config;
+use server::event_stream::EventFeed;
+use server::{
+    cache, configure_app, configure_app_with_replica, connect_database, connect_replica_database,
+    migrate_database, outbox, ApplicationError,
+};
 
 #[actix_web::main]
 async fn main() -> Result<(), ApplicationError> {
@@ -16,17 +23,82 @@ async fn main() -> Result<(), ApplicationError> {
     ));
     env::set_var("RUST_BACKTRACE", "1");
 
-    let pool = create_database_pool().await?;
+    // Logs are written as JSON lines. Besides our own events this includes the statements
+    // logged by sqlx, which end up inside the span of the message executing them.
+    tracing_subscriber::fmt()
+        .json()
+        .with_current_span(true)
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
 
-    println!("🚀 Server ready: http://localhost:8080");
+    let config = Config::load()?;
+    cache::init(&config.cache);
+    let pool = connect_database(&config.database).await?;
+    migrate_database(&pool).await?;
+    let replica_pool = connect_replica_database(&config.database).await?;
 
-    HttpServer::new(move || {
-        let app = App::new().wrap(sentry_actix::Sentry::new());
-        configure_app(app, pool.clone())
+    if replica_pool.is_some() {
+        tracing::info!("Queries are executed on the read replica");
+    }
+
+    if config.auth.is_enabled() {
+        tracing::info!("Messages require a key");
+    }
+
+    outbox::start(pool.clone(), config.outbox.clone());
+    if !config.outbox.webhook_urls.is_empty() {
+        tracing::info!("New events are sent to the webhooks");
+    }
+
+    let event_feed = EventFeed::start(pool.clone(), &config.event_stream).await?;
+
+    let app_pool = pool.clone();
+    let auth = Data::new(config.auth.clone());
+    let idempotency = Data::new(config.idempotency.clone());
+    let timeouts = Data::new(config.timeouts.clone());
+    let batch = Data::new(config.batch.clone());
+    let app_replica_pool = replica_pool.clone();
+    let mut server = HttpServer::new(move || {
+        let app = App::new()
+            .wrap(sentry_actix::Sentry::new())
+            .app_data(auth.clone())
+            .app_data(idempotency.clone())
+            .app_data(timeouts.clone())
+            .app_data(batch.clone())
+            .app_data(event_feed.clone());
+        match &app_replica_pool {
+            Some(replica_pool) => {
+                configure_app_with_replica(app, app_pool.clone(), replica_pool.clone())
+            }
+            None => configure_app(app, app_pool.clone()),
+        }
     })
-    .bind("0.0.0.0:8080")
-    .map_err(|inner| ApplicationError::ServerError { inner })?
-    .run()
-    .await
-    .map_err(|inner| ApplicationError::ServerError { inner })
+    // On SIGTERM the server stops accepting connections and waits for in-flight requests (and
+    // thus their transactions) to finish.
+    .shutdown_timeout(config.shutdown_timeout_seconds);
+
+    if let Some(workers) = config.workers {
+        server = server.workers(workers);
+    }
+
+    let server = server
+        .bind(&config.bind_address)
+        .map_err(|inner| ApplicationError::ServerError { inner })?;
+
+    tracing::info!("🚀 Server ready: http://{}", config.bind_address);
+
+    server
+        .run()
+        .await
+        .map_err(|inner| ApplicationError::ServerError { inner })?;
+
+    tracing::info!("Server stopped, closing database connections");
+    pool.close().await;
+    if let Some(replica_pool) = replica_pool {
+        replica_pool.close().await;
+    }
+
+    Ok(())
 }
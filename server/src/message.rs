@@ -1,13 +1,22 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
 use actix_web::HttpResponse;
 use async_trait::async_trait;
-use serde::{Deserialize, Serialize};
+use schemars::gen::SchemaGenerator;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use thiserror::Error;
 
 use crate::alias::AliasMessage;
+use crate::cache::CacheMessage;
 use crate::database::Connection;
 use crate::event::EventMessage;
 use crate::metadata::MetadataMessage;
 use crate::navigation::NavigationMessage;
 use crate::notification::NotificationMessage;
+use crate::operation::{is_mutation, serialize_fields, ErrorCode};
+use crate::schema::{MessageSchema, MessageSchemas};
 use crate::subject::SubjectsMessage;
 use crate::subscription::SubscriptionMessage;
 use crate::thread::ThreadMessage;
@@ -21,10 +30,16 @@ pub trait MessageResponder {
     async fn handle(&self, connection: Connection<'_, '_>) -> HttpResponse;
 }
 
-#[derive(Deserialize, Serialize)]
+/// A message sent to the database layer, i.e. `{ "type": "UuidQuery", "payload": { "id": 1 } }`.
+///
+/// Messages are deserialized by first reading the field `type` and then deserializing the
+/// payload into the message enum which contains the type (see [`Message::from_value`]). This
+/// way clients get the concrete error of the payload or the list of known types.
+#[derive(Serialize)]
 #[serde(untagged)]
 pub enum Message {
     AliasMessage(AliasMessage),
+    CacheMessage(CacheMessage),
     EntityMessage(EntityMessage),
     EventMessage(EventMessage),
     MetadataMessage(MetadataMessage),
@@ -46,6 +61,7 @@ impl MessageResponder for Message {
     async fn handle(&self, connection: Connection<'_, '_>) -> HttpResponse {
         match self {
             Message::AliasMessage(message) => message.handle(connection).await,
+            Message::CacheMessage(message) => message.handle(connection).await,
             Message::EntityMessage(message) => message.handle(connection).await,
             Message::EventMessage(message) => message.handle(connection).await,
             Message::MetadataMessage(message) => message.handle(connection).await,
@@ -62,3 +78,258 @@ impl MessageResponder for Message {
         }
     }
 }
+
+impl Message {
+    /// Returns the type of the message, e.g. `UuidQuery`. The payload is not serialized.
+    pub fn message_type(&self) -> Option<String> {
+        match serialize_fields(self, |name| name == "type") {
+            Value::Object(mut fields) => match fields.remove("type") {
+                Some(Value::String(message_type)) => Some(message_type),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns whether the message only reads data, i.e. it is no mutation (see
+    /// [`is_mutation`]). Such messages may be executed on a read replica of the database (see
+    /// [`crate::database::ReplicaPool`]).
+    pub fn is_query(&self) -> bool {
+        self.message_type()
+            .is_some_and(|message_type| !is_mutation(&message_type))
+    }
+}
+
+/// Error of a message which cannot be deserialized.
+#[derive(Debug, Error)]
+pub enum MessageError {
+    #[error("message needs the field `type`")]
+    MissingType,
+    #[error("unknown message type `{message_type}`")]
+    UnknownType { message_type: String },
+    #[error("invalid payload of {message_type}: {reason}")]
+    InvalidPayload {
+        message_type: String,
+        reason: String,
+    },
+}
+
+impl MessageError {
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            MessageError::MissingType => ErrorCode::MissingParameter,
+            MessageError::UnknownType { .. } => ErrorCode::UnknownMessageType {
+                types: parsers().keys().map(|name| name.to_string()).collect(),
+            },
+            MessageError::InvalidPayload { .. } => ErrorCode::InvalidPayload,
+        }
+    }
+}
+
+impl Message {
+    pub fn from_value(value: Value) -> Result<Self, MessageError> {
+        let message_type = value
+            .get("type")
+            .and_then(Value::as_str)
+            .ok_or(MessageError::MissingType)?
+            .to_string();
+        let parse = match parsers().get(message_type.as_str()) {
+            Some(parse) => parse,
+            None => return Err(MessageError::UnknownType { message_type }),
+        };
+
+        parse(value).map_err(|error| MessageError::InvalidPayload {
+            message_type,
+            reason: error.to_string(),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Message {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Message::from_value(Value::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+type Parse = fn(Value) -> serde_json::Result<Message>;
+
+/// Returns the functions deserializing a message by its type. The types of a message enum are
+/// taken from its [`MessageSchemas`] implementation.
+fn parsers() -> &'static BTreeMap<&'static str, Parse> {
+    static PARSERS: OnceLock<BTreeMap<&'static str, Parse>> = OnceLock::new();
+
+    PARSERS.get_or_init(|| {
+        let mut parsers = BTreeMap::new();
+        let mut generator = SchemaGenerator::default();
+        let mut add = |message_schemas: fn(&mut SchemaGenerator) -> Vec<MessageSchema>,
+                       parse: Parse| {
+            for schema in message_schemas(&mut generator) {
+                parsers.insert(schema.message_type, parse);
+            }
+        };
+
+        add(AliasMessage::message_schemas, |value| {
+            serde_json::from_value(value).map(Message::AliasMessage)
+        });
+        add(CacheMessage::message_schemas, |value| {
+            serde_json::from_value(value).map(Message::CacheMessage)
+        });
+        add(EntityMessage::message_schemas, |value| {
+            serde_json::from_value(value).map(Message::EntityMessage)
+        });
+        add(EventMessage::message_schemas, |value| {
+            serde_json::from_value(value).map(Message::EventMessage)
+        });
+        add(MetadataMessage::message_schemas, |value| {
+            serde_json::from_value(value).map(Message::MetadataMessage)
+        });
+        add(NavigationMessage::message_schemas, |value| {
+            serde_json::from_value(value).map(Message::NavigationMessage)
+        });
+        add(NotificationMessage::message_schemas, |value| {
+            serde_json::from_value(value).map(Message::NotificationMessage)
+        });
+        add(PageMessage::message_schemas, |value| {
+            serde_json::from_value(value).map(Message::PageMessage)
+        });
+        add(SubjectsMessage::message_schemas, |value| {
+            serde_json::from_value(value).map(Message::SubjectsMessage)
+        });
+        add(SubscriptionMessage::message_schemas, |value| {
+            serde_json::from_value(value).map(Message::SubscriptionMessage)
+        });
+        add(TaxonomyTermMessage::message_schemas, |value| {
+            serde_json::from_value(value).map(Message::TaxonomyTermMessage)
+        });
+        add(ThreadMessage::message_schemas, |value| {
+            serde_json::from_value(value).map(Message::ThreadMessage)
+        });
+        add(UserMessage::message_schemas, |value| {
+            serde_json::from_value(value).map(Message::UserMessage)
+        });
+        add(UuidMessage::message_schemas, |value| {
+            serde_json::from_value(value).map(Message::UuidMessage)
+        });
+        add(VocabularyMessage::message_schemas, |value| {
+            serde_json::from_value(value).map(Message::VocabularyMessage)
+        });
+
+        parsers
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use regex::Regex;
+    use schemars::gen::SchemaGenerator;
+    use serde::de::DeserializeOwned;
+    use serde_json::json;
+
+    use super::*;
+
+    /// Returns the names of all variants of a message enum, taken from the error of serde for an
+    /// unknown variant.
+    fn variant_names<T: DeserializeOwned>() -> BTreeSet<String> {
+        let error = serde_json::from_value::<T>(json!({ "type": "", "payload": null }))
+            .err()
+            .unwrap()
+            .to_string();
+        let expected = &error[error.find("expected").unwrap()..];
+
+        Regex::new(r"`(\w+)`")
+            .unwrap()
+            .captures_iter(expected)
+            .map(|captures| captures[1].to_string())
+            .collect()
+    }
+
+    /// Asserts that the [`MessageSchemas`] of a message enum list all of its variants, since
+    /// messages are routed by them.
+    fn assert_lists_all_variants<T: MessageSchemas + DeserializeOwned>() {
+        let listed: BTreeSet<_> = T::message_schemas(&mut SchemaGenerator::default())
+            .into_iter()
+            .map(|schema| schema.message_type.to_string())
+            .collect();
+
+        assert_eq!(listed, variant_names::<T>());
+    }
+
+    #[test]
+    fn lists_all_message_types() {
+        assert_lists_all_variants::<AliasMessage>();
+        assert_lists_all_variants::<CacheMessage>();
+        assert_lists_all_variants::<EntityMessage>();
+        assert_lists_all_variants::<EventMessage>();
+        assert_lists_all_variants::<MetadataMessage>();
+        assert_lists_all_variants::<NavigationMessage>();
+        assert_lists_all_variants::<NotificationMessage>();
+        assert_lists_all_variants::<PageMessage>();
+        assert_lists_all_variants::<SubjectsMessage>();
+        assert_lists_all_variants::<SubscriptionMessage>();
+        assert_lists_all_variants::<TaxonomyTermMessage>();
+        assert_lists_all_variants::<ThreadMessage>();
+        assert_lists_all_variants::<UserMessage>();
+        assert_lists_all_variants::<UuidMessage>();
+        assert_lists_all_variants::<VocabularyMessage>();
+    }
+
+    #[test]
+    fn queries_are_distinguished_from_mutations() {
+        let query: Message =
+            serde_json::from_value(json!({ "type": "UuidQuery", "payload": { "id": 1 } })).unwrap();
+        let mutation: Message = serde_json::from_value(json!({
+            "type": "UuidSetStateMutation",
+            "payload": { "ids": [1], "userId": 1, "trashed": true }
+        }))
+        .unwrap();
+
+        assert_eq!(query.message_type().as_deref(), Some("UuidQuery"));
+        assert!(query.is_query());
+        assert!(!mutation.is_query());
+    }
+
+    #[test]
+    fn fails_for_unknown_message_type() {
+        let error = Message::from_value(json!({ "type": "UuidQuerry", "payload": { "id": 1 } }))
+            .err()
+            .unwrap();
+
+        assert!(matches!(error, MessageError::UnknownType { .. }));
+        assert_eq!(error.to_string(), "unknown message type `UuidQuerry`");
+    }
+
+    #[test]
+    fn fails_for_unknown_payload_field() {
+        let error = Message::from_value(json!({ "type": "UuidQuery", "payload": { "uuid": 1 } }))
+            .err()
+            .unwrap();
+
+        assert!(matches!(
+            error,
+            MessageError::InvalidPayload { ref message_type, ref reason }
+                if message_type == "UuidQuery" && reason.starts_with("unknown field `uuid`")
+        ));
+    }
+
+    #[test]
+    fn accepts_only_empty_payload_for_cache_stats_query() {
+        for payload in [json!(null), json!({})] {
+            assert!(
+                Message::from_value(json!({ "type": "CacheStatsQuery", "payload": payload }))
+                    .is_ok()
+            );
+        }
+
+        let error =
+            Message::from_value(json!({ "type": "CacheStatsQuery", "payload": { "id": 1 } }))
+                .err()
+                .unwrap();
+
+        assert!(matches!(error, MessageError::InvalidPayload { .. }));
+    }
+}
@@ -1,14 +1,18 @@
 use actix_web::HttpResponse;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::env;
 
+use crate::clock;
 use crate::database::{Connection, Executor};
 use crate::message::MessageResponder;
 use crate::operation::Error;
 use crate::operation::{self, Operation};
+use crate::schema::{MessageSchema, MessageSchemas};
 
 #[derive(Deserialize, Serialize)]
 #[serde(tag = "type", content = "payload")]
@@ -28,14 +32,26 @@ impl MessageResponder for MetadataMessage {
     }
 }
 
+impl MessageSchemas for MetadataMessage {
+    fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema> {
+        vec![MessageSchema::new::<entities_metadata_query::Payload>(
+            "EntitiesMetadataQuery",
+            generator,
+        )]
+    }
+}
+
 pub mod entities_metadata_query {
+    use futures::{Stream, TryStreamExt};
     use itertools::Itertools;
+    use sqlx::MySqlPool;
     use std::collections::{HashMap, HashSet};
 
     use super::*;
+    use crate::ndjson::{Cursor, Lines};
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Clone, Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub first: i32,
         pub after: Option<i32>,
@@ -43,13 +59,13 @@ pub mod entities_metadata_query {
         pub modified_after: Option<DateTime<Utc>>,
     }
 
-    #[derive(Serialize)]
+    #[derive(Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         entities: Vec<EntityMetadata>,
     }
 
-    #[derive(Serialize)]
+    #[derive(Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     struct EntityMetadata {
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -82,7 +98,7 @@ pub mod entities_metadata_query {
         version: Option<LinkedNode>,
     }
 
-    #[derive(Serialize)]
+    #[derive(Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     struct Creator {
         #[serde(rename = "type")]
@@ -92,12 +108,12 @@ pub mod entities_metadata_query {
         affiliation: serde_json::Value,
     }
 
-    #[derive(Serialize)]
+    #[derive(Serialize, JsonSchema)]
     enum CreatorType {
         Person,
     }
 
-    #[derive(Serialize)]
+    #[derive(Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     struct LinkedNode {
         id: String,
@@ -108,27 +124,79 @@ pub mod entities_metadata_query {
         type Output = Output;
 
         async fn execute(&self, connection: Connection<'_, '_>) -> operation::Result<Self::Output> {
+            self.validate()?;
+
+            let entities = match connection {
+                Connection::Pool(pool) => {
+                    let entities = query(self, self.first, pool)?;
+                    entities.map_ok(|(_, entity)| entity).try_collect().await?
+                }
+                Connection::Transaction(transaction) => {
+                    let entities = query(self, self.first, transaction)?;
+                    entities.map_ok(|(_, entity)| entity).try_collect().await?
+                }
+            };
+
+            Ok(Output { entities })
+        }
+    }
+
+    impl Payload {
+        fn validate(&self) -> Result<(), operation::Error> {
             if self.first > 10_000 {
                 return Err(Error::BadRequest {
+                    code: operation::ErrorCode::InvalidParameter,
                     reason: "The 'first' value should be less than or equal 10_000".to_string(),
                 });
             };
 
-            let entities = match connection {
-                Connection::Pool(pool) => query(self, pool).await?,
-                Connection::Transaction(transaction) => query(self, transaction).await?,
-            };
+            Ok(())
+        }
+    }
 
-            Ok(Output { entities })
+    /// Streams the metadata of the entities as NDJSON, see [`crate::ndjson`].
+    pub async fn stream(
+        payload: &Payload,
+        pool: &MySqlPool,
+        lines: &Lines,
+    ) -> Result<Cursor, operation::Error> {
+        payload.validate()?;
+
+        // One more entity is queried to find out whether there is a next page.
+        let mut entities = query(payload, payload.first + 1, pool)?;
+        let mut cursor = Cursor {
+            after: payload.after,
+            has_next_page: false,
+        };
+        let mut count = 0;
+
+        while let Some((id, entity)) = entities.try_next().await? {
+            if count == payload.first {
+                cursor.has_next_page = true;
+                break;
+            }
+            if !lines.send(&entity).await? {
+                break;
+            }
+            count += 1;
+            cursor.after = Some(id);
         }
+
+        Ok(cursor)
     }
 
-    async fn query<'a, E>(
+    /// Returns a stream of at most `limit` entities (together with their ids) which match the
+    /// payload. The rows are mapped while they are read from the database.
+    fn query<'a, E>(
         payload: &Payload,
+        limit: i32,
         executor: E,
-    ) -> Result<Vec<EntityMetadata>, operation::Error>
+    ) -> Result<
+        impl Stream<Item = Result<(i32, EntityMetadata), sqlx::Error>> + Send + Unpin + 'a,
+        operation::Error,
+    >
     where
-        E: Executor<'a>,
+        E: Executor<'a> + 'a,
     {
         // See https://github.com/serlo/private-issues-sso-metadata-wallet/issues/37
         let metadata_api_last_changes_date: DateTime<Utc> = DateTime::parse_from_rfc3339(
@@ -136,6 +204,7 @@ pub mod entities_metadata_query {
                 .expect("METADATA_API_LAST_CHANGES_DATE is not set."),
         )
         .map_err(|_| operation::Error::InternalServerError {
+            code: operation::ErrorCode::InternalServerError,
             error: "Error while parsing METADATA_API_LAST_CHANGES_DATE".into(),
         })?
         .with_timezone(&Utc);
@@ -207,11 +276,9 @@ pub mod entities_metadata_query {
             payload.instance,
             modified_after,
             modified_after,
-            payload.first
-        ).fetch_all(executor)
-            .await?
-            .into_iter()
-            .map(|result| {
+            limit
+        ).fetch(executor)
+            .map_ok(|result| {
                 let identifier = result.id as i32;
                 let title: Option<String> = result.title;
                 let id = get_iri(result.id as i32);
@@ -309,7 +376,7 @@ pub mod entities_metadata_query {
                            .collect()
                     })
                     .unwrap_or(Vec::new());
-                let current_date = Utc::now().to_rfc3339();
+                let current_date = clock::now().to_rfc3339();
                 let subject_ids: Vec<i32> = result.subject_ids.as_ref()
                     .and_then(|value| value.as_array())
                     .map(|ids| {
@@ -324,7 +391,7 @@ pub mod entities_metadata_query {
                 let subject_metadata = Option::from(subject_ids.iter().flat_map(|id| {
                     map_serlo_subjects_to_amb_standard(*id)
                 }).collect::<Vec<SubjectMetadata>>()).filter(|v| !v.is_empty());
-                EntityMetadata {
+                (identifier, EntityMetadata {
                     about: subject_metadata,
                     context: json!([
                         "https://w3id.org/kim/amb/context.jsonld",
@@ -367,9 +434,8 @@ pub mod entities_metadata_query {
                     publisher: vec![get_serlo_organization_metadata()],
                     is_part_of,
                     version: result.version.map(|version| LinkedNode { id: get_iri(version) })
-                }
+                })
             })
-            .collect()
         )
     }
 
@@ -513,7 +579,7 @@ pub mod entities_metadata_query {
         }
     }
 
-    #[derive(Serialize)]
+    #[derive(Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     struct SubjectMetadata {
         r#type: String,
@@ -521,7 +587,7 @@ pub mod entities_metadata_query {
         in_scheme: Scheme,
     }
 
-    #[derive(Serialize)]
+    #[derive(Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     struct Scheme {
         id: String,
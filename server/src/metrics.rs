@@ -0,0 +1,106 @@
+//! Provides the [Prometheus](https://prometheus.io/) metrics of the database layer.
+//!
+//! The metrics are collected in a global registry (see [`metrics`]) and served as text via
+//! `GET /metrics`.
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use actix_web::http::StatusCode;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use sqlx::MySqlPool;
+
+pub struct Metrics {
+    registry: Registry,
+    messages_total: IntCounterVec,
+    message_duration_seconds: HistogramVec,
+    database_pool_connections: IntGaugeVec,
+}
+
+/// Returns the metrics shared by all workers of the server.
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::new)
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+
+        let messages_total = IntCounterVec::new(
+            Opts::new(
+                "messages_total",
+                "Number of handled messages by message type and response status",
+            ),
+            &["operation_type", "status"],
+        )
+        .unwrap();
+        let message_duration_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                "message_duration_seconds",
+                "Time needed to handle a message by message type and response status",
+            )
+            .buckets(vec![
+                0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+            ]),
+            &["operation_type", "status"],
+        )
+        .unwrap();
+        let database_pool_connections = IntGaugeVec::new(
+            Opts::new(
+                "database_pool_connections",
+                "Number of connections in the database pool by state (`idle` or `active`)",
+            ),
+            &["state"],
+        )
+        .unwrap();
+
+        registry.register(Box::new(messages_total.clone())).unwrap();
+        registry
+            .register(Box::new(message_duration_seconds.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(database_pool_connections.clone()))
+            .unwrap();
+
+        Self {
+            registry,
+            messages_total,
+            message_duration_seconds,
+            database_pool_connections,
+        }
+    }
+
+    pub fn observe_message(&self, operation_type: &str, status: StatusCode, duration: Duration) {
+        let status = status.as_str();
+
+        self.messages_total
+            .with_label_values(&[operation_type, status])
+            .inc();
+        self.message_duration_seconds
+            .with_label_values(&[operation_type, status])
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Returns all metrics in the text format of Prometheus. The statistics of the database pool
+    /// are read at the time of the call.
+    pub fn render(&self, pool: &MySqlPool) -> String {
+        let size = pool.size() as i64;
+        let idle = pool.num_idle() as i64;
+
+        self.database_pool_connections
+            .with_label_values(&["idle"])
+            .set(idle);
+        self.database_pool_connections
+            .with_label_values(&["active"])
+            .set(size - idle);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("metrics can be encoded");
+
+        String::from_utf8(buffer).expect("metrics are valid UTF-8")
+    }
+}
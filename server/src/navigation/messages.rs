@@ -1,12 +1,16 @@
 use actix_web::HttpResponse;
 use async_trait::async_trait;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::model::Navigation;
+use crate::cache::CacheKey;
 use crate::database::Connection;
 use crate::instance::Instance;
 use crate::message::MessageResponder;
 use crate::operation::{self, Operation};
+use crate::schema::{MessageSchema, MessageSchemas};
 
 #[derive(Deserialize, Serialize)]
 #[serde(tag = "type", content = "payload")]
@@ -26,11 +30,20 @@ impl MessageResponder for NavigationMessage {
     }
 }
 
+impl MessageSchemas for NavigationMessage {
+    fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema> {
+        vec![MessageSchema::new::<navigation_query::Payload>(
+            "NavigationQuery",
+            generator,
+        )]
+    }
+}
+
 pub mod navigation_query {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub instance: Instance,
     }
@@ -39,6 +52,10 @@ pub mod navigation_query {
     impl Operation for Payload {
         type Output = Navigation;
 
+        fn cache_key(&self) -> Option<CacheKey> {
+            Some(CacheKey::Navigation(self.instance.clone()))
+        }
+
         async fn execute(&self, connection: Connection<'_, '_>) -> operation::Result<Self::Output> {
             let instance = self.instance.clone();
             Ok(match connection {
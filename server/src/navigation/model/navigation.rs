@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::convert::TryInto;
 
+use schemars::JsonSchema;
 use serde::Serialize;
 use sqlx::MySqlPool;
 
@@ -10,7 +11,7 @@ use super::navigation_child::{
 use crate::database::Executor;
 use crate::instance::Instance;
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct Navigation {
     pub data: Vec<NavigationChild>,
     pub instance: Instance,
@@ -3,13 +3,14 @@ use std::convert::TryFrom;
 
 use futures::try_join;
 use regex::Regex;
+use schemars::JsonSchema;
 use serde::Serialize;
 use sqlx::MySqlPool;
 use thiserror::Error;
 
 use crate::database::Executor;
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(untagged)]
 pub enum NavigationChild {
     Uuid(UuidNavigationChild),
@@ -17,7 +18,7 @@ pub enum NavigationChild {
     Container(ContainerNavigationChild),
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct UuidNavigationChild {
     pub label: String,
     pub id: i32,
@@ -25,7 +26,7 @@ pub struct UuidNavigationChild {
     pub children: Option<Vec<NavigationChild>>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct UrlNavigationChild {
     pub label: String,
     pub url: String,
@@ -33,7 +34,7 @@ pub struct UrlNavigationChild {
     pub children: Option<Vec<NavigationChild>>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct ContainerNavigationChild {
     pub label: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -183,8 +184,11 @@ impl TryFrom<(RawNavigationChild, Vec<NavigationChild>)> for NavigationChild {
                     }
                 }
                 route => {
-                    println!("Unhandled route: {route}");
-                    println!("Params: {:?}", raw_navigation_child.parameters);
+                    tracing::warn!(
+                        route,
+                        parameters = ?raw_navigation_child.parameters,
+                        "unhandled route in navigation"
+                    );
                     Err(NavigationChildError::InvalidRoute)
                 }
             }
@@ -0,0 +1,222 @@
+//! Streaming of large paginated queries as newline delimited JSON (NDJSON).
+//!
+//! `EntitiesMetadataQuery` and `EventsQuery` return up to 10,000 items. When a client sends the
+//! header `Accept: application/x-ndjson`, the items are not collected into one JSON document but
+//! written as one line each while the rows are read from the database. The last line contains
+//! the cursor of the next page, i.e. the value of the parameter `after` for the next request:
+//!
+//! ```text
+//! {"id":86592,"__typename":"SetTaxonomyTermNotificationEvent",...}
+//! {"id":86591,"__typename":"SetTaxonomyTermNotificationEvent",...}
+//! {"cursor":{"after":86591,"hasNextPage":true}}
+//! ```
+//!
+//! Errors which occur before the first line are sent as usual error responses. Later errors end
+//! the stream with a line `{"error":{"code":...,"message":...,"details":...}}` instead of the
+//! cursor, so a response without a cursor line is incomplete.
+use std::future::Future;
+use std::time::Instant;
+
+use actix_web::http::header::ACCEPT;
+use actix_web::http::StatusCode;
+use actix_web::web::Bytes;
+use actix_web::{HttpRequest, HttpResponse};
+use futures::StreamExt;
+use serde::Serialize;
+use serde_json::json;
+use sqlx::MySqlPool;
+use tokio::sync::mpsc;
+use tracing::Instrument;
+
+use crate::event::{events_query, EventMessage};
+use crate::message::Message;
+use crate::metadata::{entities_metadata_query, MetadataMessage};
+use crate::metrics::metrics;
+use crate::operation;
+use crate::timeout::{self, ExcludedTime};
+
+pub const CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Number of lines which are buffered before further rows are read from the database.
+const BUFFER_SIZE: usize = 64;
+
+/// Pagination cursor which is sent as the last line.
+#[derive(Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cursor {
+    pub after: Option<i32>,
+    pub has_next_page: bool,
+}
+
+type Chunk = Result<Bytes, operation::Error>;
+
+/// Writes the items of a query as lines to the response.
+pub struct Lines {
+    sender: mpsc::Sender<Chunk>,
+    /// Time spent waiting for the client, which does not count towards the time limit.
+    waiting: ExcludedTime,
+}
+
+impl Lines {
+    /// Writes `item` as a line. Returns `false` when the client has closed the connection, so
+    /// that no further rows need to be read.
+    pub async fn send<T: Serialize>(&self, item: &T) -> operation::Result<bool> {
+        let line = to_line(item)?;
+        Ok(self
+            .waiting
+            .exclude(self.sender.send(Ok(line)))
+            .await
+            .is_ok())
+    }
+}
+
+/// Returns whether the client accepts NDJSON responses.
+pub fn is_accepted(req: &HttpRequest) -> bool {
+    req.headers()
+        .get_all(ACCEPT)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media_type| media_type.split(';').next().map(str::trim) == Some(CONTENT_TYPE))
+}
+
+/// Streams the response of `message` on `pool`. Returns `None` for messages which cannot be
+/// streamed.
+pub async fn respond(message: &Message, pool: &MySqlPool) -> Option<HttpResponse> {
+    let response = match message {
+        Message::MetadataMessage(MetadataMessage::EntitiesMetadataQuery(payload)) => {
+            let payload = payload.clone();
+            stream(
+                "EntitiesMetadataQuery",
+                pool.clone(),
+                |pool, lines| async move {
+                    entities_metadata_query::stream(&payload, &pool, &lines).await
+                },
+            )
+            .await
+        }
+        Message::EventMessage(EventMessage::EventsQuery(payload)) => {
+            let payload = payload.clone();
+            stream("EventsQuery", pool.clone(), |pool, lines| async move {
+                events_query::stream(&payload, &pool, &lines).await
+            })
+            .await
+        }
+        _ => return None,
+    };
+
+    Some(response)
+}
+
+/// Runs `query` in a separate task which sends the lines to the response through a bounded
+/// channel. Thus the rows are only read as fast as the client receives them. The time limit of
+/// the message only applies to reading the rows, not to waiting for a slow client.
+async fn stream<F, Fut>(operation_type: &'static str, pool: MySqlPool, query: F) -> HttpResponse
+where
+    F: FnOnce(MySqlPool, Lines) -> Fut + 'static,
+    Fut: Future<Output = operation::Result<Cursor>> + 'static,
+{
+    let start = Instant::now();
+    let span = tracing::info_span!("message", operation_type, streamed = true);
+    let limit = timeout::limit_for(operation_type);
+    let (sender, mut receiver) = mpsc::channel(BUFFER_SIZE);
+    let waiting = ExcludedTime::default();
+    let lines = Lines {
+        sender: sender.clone(),
+        waiting: waiting.clone(),
+    };
+
+    actix_rt::spawn(
+        async move {
+            let execution = query(pool, lines);
+            let result = match limit {
+                Some(limit) => timeout::with_limit_excluding(limit, &waiting, execution)
+                    .await
+                    .unwrap_or_else(|elapsed| Err(elapsed.into())),
+                None => execution.await,
+            };
+
+            let status = match &result {
+                Ok(_) => StatusCode::OK,
+                Err(error) => {
+                    tracing::error!(%error, "message failed");
+                    error.status_and_body().0
+                }
+            };
+            let duration = start.elapsed();
+            tracing::info!(
+                status = status.as_u16(),
+                duration_ms = duration.as_millis() as u64,
+                "message handled"
+            );
+            metrics().observe_message(operation_type, status, duration);
+
+            let last_line = result.and_then(|cursor| Ok(to_line(&json!({ "cursor": cursor }))?));
+            // The client might have closed the connection already.
+            let _ = sender.send(last_line).await;
+        }
+        .instrument(span),
+    );
+
+    let first_chunk = match receiver.recv().await {
+        Some(Ok(line)) => line,
+        Some(Err(error)) => {
+            let (status, body) = error.status_and_body();
+            return HttpResponse::build(status)
+                .content_type("application/json; charset=utf-8")
+                .json(body);
+        }
+        None => Bytes::new(),
+    };
+
+    let following_chunks = futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|chunk| (chunk, receiver))
+    });
+    let body = futures::stream::once(async { Ok(first_chunk) })
+        .chain(following_chunks)
+        .map(|chunk| Ok::<_, actix_web::Error>(chunk.unwrap_or_else(|error| error_line(&error))));
+
+    HttpResponse::Ok()
+        .content_type(CONTENT_TYPE)
+        .streaming(body)
+}
+
+fn to_line<T: Serialize>(item: &T) -> serde_json::Result<Bytes> {
+    let mut line = serde_json::to_vec(item)?;
+    line.push(b'\n');
+    Ok(Bytes::from(line))
+}
+
+fn error_line(error: &operation::Error) -> Bytes {
+    let (_, body) = error.status_and_body();
+    to_line(&json!({ "error": body })).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::{is_accepted, to_line, Cursor};
+
+    #[test]
+    fn accepts_ndjson_among_other_media_types() {
+        let req = TestRequest::default()
+            .insert_header(("Accept", "application/json, application/x-ndjson; q=0.9"))
+            .to_http_request();
+
+        assert!(is_accepted(&req));
+        assert!(!is_accepted(&TestRequest::default().to_http_request()));
+    }
+
+    #[test]
+    fn writes_cursor_as_line() {
+        let cursor = Cursor {
+            after: Some(42),
+            has_next_page: true,
+        };
+
+        assert_eq!(
+            to_line(&cursor).unwrap(),
+            "{\"after\":42,\"hasNextPage\":true}\n"
+        );
+    }
+}
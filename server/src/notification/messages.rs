@@ -1,11 +1,14 @@
 use actix_web::HttpResponse;
 use async_trait::async_trait;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::model::Notifications;
 use crate::database::Connection;
 use crate::message::MessageResponder;
 use crate::operation::{self, Operation, SuccessOutput};
+use crate::schema::{MessageSchema, MessageSchemas};
 
 #[derive(Deserialize, Serialize)]
 #[serde(tag = "type", content = "payload")]
@@ -31,11 +34,23 @@ impl MessageResponder for NotificationMessage {
     }
 }
 
+impl MessageSchemas for NotificationMessage {
+    fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema> {
+        vec![
+            MessageSchema::new::<notifications_query::Payload>("NotificationsQuery", generator),
+            MessageSchema::new::<set_state_mutation::Payload>(
+                "NotificationSetStateMutation",
+                generator,
+            ),
+        ]
+    }
+}
+
 pub mod notifications_query {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub user_id: i32,
     }
@@ -58,8 +73,8 @@ pub mod notifications_query {
 pub mod set_state_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub ids: Vec<i32>,
         pub user_id: i32,
@@ -1,22 +1,23 @@
 use std::collections::HashSet;
 use std::hash::{Hash, Hasher};
 
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use super::messages::*;
 use crate::database::Executor;
 use crate::event::{AbstractEvent, Event};
-use crate::subscription::Subscriptions;
+use crate::repository::{MySqlRepository, NotificationRepository, SubscriptionRepository};
 use sqlx::MySqlPool;
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Notifications {
     pub user_id: i32,
     pub notifications: Vec<Notification>,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Notification {
     pub id: i32,
@@ -107,7 +108,18 @@ impl Notifications {
         E: Executor<'a>,
     {
         let mut transaction = executor.begin().await?;
+        Self::notify_subscribers(event, &mut MySqlRepository::new(&mut transaction)).await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
 
+    /// Notifies the subscribers of the object and of the uuid parameters of `event` except its
+    /// actor. Every subscriber gets one notification at most.
+    pub async fn notify_subscribers<R>(event: &Event, repository: &mut R) -> Result<(), sqlx::Error>
+    where
+        R: SubscriptionRepository + NotificationRepository,
+    {
         let AbstractEvent {
             actor_id,
             object_id,
@@ -120,9 +132,8 @@ impl Notifications {
         object_ids.extend(event.abstract_event.uuid_parameters.values());
 
         for object_id in object_ids {
-            let subscriptions = Subscriptions::fetch_by_object(object_id, &mut transaction).await?;
+            let subscriptions = repository.fetch_subscriptions_by_object(object_id).await?;
             let subscriptions = subscriptions
-                .0
                 .iter()
                 .filter(|subscription| subscription.user_id != actor_id);
             for subscription in subscriptions {
@@ -134,17 +145,18 @@ impl Notifications {
         }
 
         for subscriber in subscribers {
-            Self::create_notification(event, &subscriber, &mut transaction).await?;
+            repository
+                .create_notification(event, subscriber.user_id, subscriber.send_email)
+                .await?;
         }
 
-        transaction.commit().await?;
-
         Ok(())
     }
 
-    async fn create_notification<'a, E>(
+    pub(crate) async fn insert_notification<'a, E>(
         event: &Event,
-        subscriber: &Subscriber,
+        user_id: i32,
+        send_email: bool,
         executor: E,
     ) -> Result<(), sqlx::Error>
     where
@@ -157,9 +169,9 @@ impl Notifications {
                 INSERT INTO notification (user_id, date, email)
                     VALUES (?, ?, ?)
             "#,
-            subscriber.user_id,
+            user_id,
             event.abstract_event.date,
-            subscriber.send_email
+            send_email
         )
         .execute(&mut transaction)
         .await?;
@@ -188,25 +200,26 @@ impl Notifications {
         E: Executor<'a>,
     {
         let mut transaction = executor.begin().await?;
+        Self::set_notification_state_with(payload, &mut MySqlRepository::new(&mut transaction))
+            .await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
 
+    pub async fn set_notification_state_with<R>(
+        payload: &set_state_mutation::Payload,
+        repository: &mut R,
+    ) -> Result<(), sqlx::Error>
+    where
+        R: NotificationRepository,
+    {
         for id in &payload.ids {
-            let seen = !payload.unread;
-            sqlx::query!(
-                r#"
-                    UPDATE notification
-                        SET seen = ?
-                        WHERE seen != ? AND id = ?
-                "#,
-                seen,
-                seen,
-                id
-            )
-            .execute(&mut transaction)
-            .await?;
+            repository
+                .set_notification_state(*id, payload.unread)
+                .await?;
         }
 
-        transaction.commit().await?;
-
         Ok(())
     }
 }
@@ -365,15 +378,7 @@ mod tests {
             EntityPayloadType::Revision(payload) => payload.save(&mut transaction).await.unwrap(),
         };
 
-        Notifications::create_notification(
-            &event,
-            &Subscriber {
-                user_id: new_user_id,
-                send_email: false,
-            },
-            &mut transaction,
-        )
-        .await?;
+        Notifications::insert_notification(&event, new_user_id, false, &mut transaction).await?;
 
         assert_eq!(
             Notifications::fetch_via_transaction(new_user_id, &mut transaction)
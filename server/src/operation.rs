@@ -1,38 +1,188 @@
+use std::time::Instant;
+
+use crate::cache::{cache, CacheKey, Dependency};
+use crate::changes::{self, Changes};
 use crate::database::Connection;
+use actix_web::http::StatusCode;
+use actix_web::web::Bytes;
 use actix_web::HttpResponse;
 use async_trait::async_trait;
-use serde::Serialize;
-use serde_json::{json, Value};
+use schemars::JsonSchema;
+use serde::{ser, Serialize};
+use serde_json::{json, Map, Value};
 
 use crate::event::EventError;
+use crate::metrics::metrics;
+use crate::timeout::{self, Elapsed};
 use thiserror::Error;
+use tracing::Instrument;
 
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("BadRequest: {reason:?}")]
-    BadRequest { reason: String },
+    BadRequest { code: ErrorCode, reason: String },
     #[error("InternalServerError: {error:?}")]
-    InternalServerError { error: Box<dyn std::error::Error> },
+    InternalServerError {
+        code: ErrorCode,
+        error: Box<dyn std::error::Error>,
+    },
     #[error("Requested value could not be found.")]
-    NotFoundError,
+    NotFoundError { code: ErrorCode },
+    #[error("Timeout: {reason:?}")]
+    Timeout { code: ErrorCode, reason: String },
+}
+
+/// Stable, machine-readable error codes which are sent to clients in the error body.
+///
+/// Clients shall match on these codes instead of on the human readable messages. Variants with
+/// fields send them as `details`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "code", content = "details", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    // Generic errors
+    InternalServerError,
+    DatabaseError,
+    InvalidParameter,
+    MissingParameter,
+    NotFound,
+    MessageTimeout,
+    UnknownMessageType { types: Vec<String> },
+    InvalidPayload,
+
+    // Errors of authentication
+    Unauthorized,
+    Forbidden,
+
+    // Errors of idempotency keys
+    IdempotencyKeyInUse,
+    IdempotencyKeyReused,
+
+    // Errors of uuids and aliases
+    AliasNotFound,
+    UuidNotFound,
+    UuidNotTrashable,
+    UnsupportedDiscriminator { discriminator: String },
+    UnsupportedEntityType { name: String },
+    UnsupportedEntityRevisionType { name: String },
+    EntityMissingParent,
+    InvalidInstance,
+
+    // Errors of events
+    ActorMissing,
+    EventNotFound,
+    InvalidEventType,
+    EventMissingRequiredField,
+
+    // Errors of entities and pages
+    EntityNotFound,
+    PageNotFound,
+    SolutionAlreadyExists,
+    InvalidRevision,
+    InvalidRepository,
+    RevisionAlreadyCheckedOut,
+    RevisionAlreadyRejected,
+    RevisionCurrentlyCheckedOut,
+    InvalidChildren,
+
+    // Errors of taxonomy terms
+    TaxonomyTermNotFound,
+    DuplicateTaxonomyTermName,
+    InvalidTaxonomyLink,
+
+    // Errors of threads and comments
+    ThreadNotFound,
+    ThreadAlreadyArchived,
+    CommentNotFound,
+    NotCommentAuthor,
+    CommentNotEditable,
+    EmptyContent,
+
+    // Errors of users
+    UserNotFound,
+    UserNotDeletable,
+    RoleNotFound,
+}
+
+impl ErrorCode {
+    /// Returns the error body `{ code, message, details }` sent to clients.
+    pub fn to_body(&self, message: &str) -> Value {
+        let mut body = serde_json::to_value(self).unwrap_or_else(|_| json!({}));
+        body["message"] = Value::String(message.to_string());
+        if body.get("details").is_none() {
+            body["details"] = Value::Null;
+        }
+        body
+    }
+}
+
+impl Error {
+    /// Returns the status code and the body of the response sent for this error.
+    pub fn status_and_body(&self) -> (StatusCode, Value) {
+        match self {
+            Error::NotFoundError { code } => (
+                StatusCode::NOT_FOUND,
+                code.to_body("Requested value could not be found."),
+            ),
+            Error::BadRequest { code, reason } => {
+                let mut body = code.to_body(reason);
+                // Kept for clients which have not switched to `code` yet.
+                body["success"] = Value::Bool(false);
+                body["reason"] = Value::String(reason.to_string());
+
+                (StatusCode::BAD_REQUEST, body)
+            }
+            Error::Timeout { code, reason } => (StatusCode::GATEWAY_TIMEOUT, code.to_body(reason)),
+            Error::InternalServerError { code, error: _ } => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                code.to_body("Internal server error."),
+            ),
+        }
+    }
 }
 
 impl From<sqlx::Error> for Error {
     fn from(error: sqlx::Error) -> Self {
         Error::InternalServerError {
+            code: ErrorCode::DatabaseError,
             error: Box::new(error),
         }
     }
 }
 
+impl From<Elapsed> for Error {
+    fn from(elapsed: Elapsed) -> Self {
+        Error::Timeout {
+            code: ErrorCode::MessageTimeout,
+            reason: format!(
+                "message exceeded its time limit of {} ms",
+                elapsed.limit.as_millis()
+            ),
+        }
+    }
+}
+
 impl From<EventError> for Error {
     fn from(error: EventError) -> Self {
         match error {
             EventError::MissingUser => Error::BadRequest {
+                code: ErrorCode::ActorMissing,
                 reason: "acting user does not exist".to_string(),
             },
             EventError::DatabaseError { inner } => inner.into(),
-            _ => Error::InternalServerError {
+            EventError::InvalidType => Error::InternalServerError {
+                code: ErrorCode::InvalidEventType,
+                error: Box::new(error),
+            },
+            EventError::InvalidInstance => Error::InternalServerError {
+                code: ErrorCode::InvalidInstance,
+                error: Box::new(error),
+            },
+            EventError::MissingRequiredField => Error::InternalServerError {
+                code: ErrorCode::EventMissingRequiredField,
+                error: Box::new(error),
+            },
+            EventError::NotFound => Error::InternalServerError {
+                code: ErrorCode::EventNotFound,
                 error: Box::new(error),
             },
         }
@@ -42,6 +192,7 @@ impl From<EventError> for Error {
 impl From<serde_json::Error> for Error {
     fn from(error: serde_json::Error) -> Self {
         Error::InternalServerError {
+            code: ErrorCode::InternalServerError,
             error: Box::new(error),
         }
     }
@@ -55,33 +206,405 @@ pub trait Operation {
 
     async fn execute(&self, connection: Connection<'_, '_>) -> Result<Self::Output>;
 
+    /// Returns the key under which the output is cached, see [`crate::cache`]. Operations
+    /// without a key are not cached.
+    fn cache_key(&self) -> Option<CacheKey> {
+        None
+    }
+
+    /// Returns what the cached output depends on.
+    fn cache_dependencies(&self, _output: &Self::Output) -> Vec<Dependency> {
+        Vec::new()
+    }
+
+    /// Executes the operation and maps its result to a response.
+    ///
+    /// The execution is wrapped in a `message` span containing the message type and the ids of
+    /// the payload, so that the logs of all queries of the message can be related to it.
     #[allow(clippy::async_yields_async)]
-    async fn handle(&self, operation_type: &str, connection: Connection<'_, '_>) -> HttpResponse {
-        match &self.execute(connection).await {
-            Ok(data) => HttpResponse::Ok()
+    async fn handle(&self, operation_type: &str, connection: Connection<'_, '_>) -> HttpResponse
+    where
+        Self: Serialize,
+    {
+        let start = Instant::now();
+        let span = tracing::info_span!("message", operation_type, ids = %payload_ids(self));
+
+        // Reads inside a transaction might see uncommitted changes and are thus not cached.
+        let cache_key = match connection {
+            Connection::Pool(_) => self.cache_key(),
+            Connection::Transaction(_) => None,
+        };
+        let cache_generation = cache().generation();
+        let cached_body = cache_key.as_ref().and_then(|key| cache().get(key));
+        let cache_hit = cached_body.is_some();
+
+        let result = match cached_body {
+            Some(body) => Ok(body),
+            None => {
+                let execution = changes::collect(self.execute(connection)).instrument(span.clone());
+
+                let (result, changes) = match timeout::limit_for(operation_type) {
+                    Some(limit) => timeout::with_limit(limit, execution)
+                        .await
+                        .unwrap_or_else(|elapsed| (Err(elapsed.into()), Changes::default())),
+                    None => execution.await,
+                };
+
+                result.and_then(|data| {
+                    let body = if is_mutation(operation_type) {
+                        serialize_with_changes(&data, changes)?
+                    } else {
+                        Bytes::from(serde_json::to_vec(&data)?)
+                    };
+
+                    if let Some(key) = cache_key {
+                        let dependencies = self.cache_dependencies(&data);
+                        cache().insert(key, body.clone(), dependencies, cache_generation);
+                    }
+
+                    Ok(body)
+                })
+            }
+        };
+
+        let response = match &result {
+            Ok(body) => HttpResponse::Ok()
                 .content_type("application/json; charset=utf-8")
-                .json(data),
+                .body(body.clone()),
 
             Err(error) => {
-                println!("{operation_type}: {error}");
-
                 match error {
-                    Error::NotFoundError => HttpResponse::NotFound()
-                        .content_type("application/json; charset=utf8")
-                        .json(Value::Null),
-                    Error::BadRequest { reason } => HttpResponse::BadRequest()
-                        .content_type("application/json; charset=utf-8")
-                        .json(json!({ "success": false, "reason": reason })),
-                    Error::InternalServerError { error: _ } => {
-                        HttpResponse::InternalServerError().finish()
+                    Error::InternalServerError { .. } | Error::Timeout { .. } => {
+                        tracing::error!(parent: &span, %error, "message failed")
                     }
+                    _ => tracing::warn!(parent: &span, %error, "message failed"),
                 }
+
+                let (status, body) = error.status_and_body();
+
+                HttpResponse::build(status)
+                    .content_type("application/json; charset=utf-8")
+                    .json(body)
             }
+        };
+
+        let duration = start.elapsed();
+        tracing::info!(
+            parent: &span,
+            status = response.status().as_u16(),
+            cache_hit,
+            duration_ms = duration.as_millis() as u64,
+            "message handled"
+        );
+        metrics().observe_message(operation_type, response.status(), duration);
+
+        response
+    }
+}
+
+/// Returns whether a message type is a mutation. All mutations have a name ending with `Mutation`.
+pub fn is_mutation(operation_type: &str) -> bool {
+    operation_type.ends_with("Mutation")
+}
+
+/// Serializes the output of a mutation together with its changes, see [`crate::changes`].
+fn serialize_with_changes<T: Serialize>(output: &T, changes: Changes) -> Result<Bytes> {
+    let mut body = serde_json::to_value(output)?;
+
+    if let (Value::Object(fields), Value::Object(changes)) =
+        (&mut body, serde_json::to_value(changes)?)
+    {
+        fields.extend(changes);
+    }
+
+    Ok(Bytes::from(serde_json::to_vec(&body)?))
+}
+
+/// Returns the ids of a payload (fields like `id`, `ids` or `userId`) as a JSON object. Other
+/// fields are not serialized at all since they might contain large contents or passwords.
+fn payload_ids<P: Serialize + ?Sized>(payload: &P) -> Value {
+    serialize_fields(payload, is_id_field)
+}
+
+/// Serializes only the fields of a struct or map for which `select` returns `true` (without
+/// serializing the other fields). Returns `null` for all other values.
+pub(crate) fn serialize_fields<T: Serialize + ?Sized>(
+    value: &T,
+    select: fn(&str) -> bool,
+) -> Value {
+    value
+        .serialize(SelectedFields(select))
+        .unwrap_or(Value::Null)
+}
+
+fn is_id_field(name: &str) -> bool {
+    name == "id" || name == "ids" || name.ends_with("Id") || name.ends_with("Ids")
+}
+
+/// Serializer which only serializes the selected fields of a struct or map.
+struct SelectedFields(fn(&str) -> bool);
+
+/// Values other than structs and maps have no fields.
+macro_rules! no_fields {
+    ($($method: ident($($argument: ty),*)),* $(,)?) => {
+        $(
+            fn $method(self, $(_: $argument),*) -> std::result::Result<Value, serde_json::Error> {
+                Ok(Value::Null)
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for SelectedFields {
+    type Ok = Value;
+    type Error = serde_json::Error;
+    type SerializeSeq = ser::Impossible<Value, serde_json::Error>;
+    type SerializeTuple = ser::Impossible<Value, serde_json::Error>;
+    type SerializeTupleStruct = ser::Impossible<Value, serde_json::Error>;
+    type SerializeTupleVariant = ser::Impossible<Value, serde_json::Error>;
+    type SerializeMap = SelectedFieldsMap;
+    type SerializeStruct = SelectedFieldsMap;
+    type SerializeStructVariant = ser::Impossible<Value, serde_json::Error>;
+
+    no_fields!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(&'static str),
+        serialize_unit_variant(&'static str, u32, &'static str),
+    );
+
+    fn serialize_some<T: Serialize + ?Sized>(
+        self,
+        value: &T,
+    ) -> std::result::Result<Value, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> std::result::Result<Value, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> std::result::Result<Value, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_seq(
+        self,
+        _len: Option<usize>,
+    ) -> std::result::Result<Self::SerializeSeq, Self::Error> {
+        Err(ser::Error::custom("value has no fields"))
+    }
+
+    fn serialize_tuple(
+        self,
+        _len: usize,
+    ) -> std::result::Result<Self::SerializeTuple, Self::Error> {
+        Err(ser::Error::custom("value has no fields"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> std::result::Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(ser::Error::custom("value has no fields"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> std::result::Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(ser::Error::custom("value has no fields"))
+    }
+
+    fn serialize_map(
+        self,
+        _len: Option<usize>,
+    ) -> std::result::Result<Self::SerializeMap, Self::Error> {
+        Ok(SelectedFieldsMap::new(self.0))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> std::result::Result<Self::SerializeStruct, Self::Error> {
+        Ok(SelectedFieldsMap::new(self.0))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> std::result::Result<Self::SerializeStructVariant, Self::Error> {
+        Err(ser::Error::custom("value has no fields"))
+    }
+}
+
+struct SelectedFieldsMap {
+    select: fn(&str) -> bool,
+    fields: Map<String, Value>,
+    /// Key of the current map entry if it is selected.
+    selected_key: Option<String>,
+}
+
+impl SelectedFieldsMap {
+    fn new(select: fn(&str) -> bool) -> Self {
+        Self {
+            select,
+            fields: Map::new(),
+            selected_key: None,
         }
     }
 }
 
-#[derive(Serialize)]
+impl ser::SerializeStruct for SelectedFieldsMap {
+    type Ok = Value;
+    type Error = serde_json::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> std::result::Result<(), Self::Error> {
+        if (self.select)(key) {
+            self.fields
+                .insert(key.to_string(), serde_json::to_value(value)?);
+        }
+        Ok(())
+    }
+
+    fn end(self) -> std::result::Result<Value, Self::Error> {
+        Ok(self.fields.into())
+    }
+}
+
+impl ser::SerializeMap for SelectedFieldsMap {
+    type Ok = Value;
+    type Error = serde_json::Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(
+        &mut self,
+        key: &T,
+    ) -> std::result::Result<(), Self::Error> {
+        self.selected_key = match serde_json::to_value(key)? {
+            Value::String(key) if (self.select)(&key) => Some(key),
+            _ => None,
+        };
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> std::result::Result<(), Self::Error> {
+        if let Some(key) = self.selected_key.take() {
+            self.fields.insert(key, serde_json::to_value(value)?);
+        }
+        Ok(())
+    }
+
+    fn end(self) -> std::result::Result<Value, Self::Error> {
+        Ok(self.fields.into())
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
 pub struct SuccessOutput {
     pub success: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+    use serde_json::json;
+
+    use super::{payload_ids, serialize_with_changes};
+    use crate::changes::Changes;
+    use crate::operation::SuccessOutput;
+
+    #[test]
+    fn payload_ids_only_contains_id_fields() {
+        let payload = json!({
+            "id": 1,
+            "userId": 2,
+            "childrenIds": [3, 4],
+            "password": "secret",
+            "content": "Hello"
+        });
+
+        assert_eq!(
+            payload_ids(&payload),
+            json!({ "id": 1, "userId": 2, "childrenIds": [3, 4] })
+        );
+    }
+
+    #[test]
+    fn payload_ids_does_not_serialize_other_fields() {
+        struct Content;
+
+        impl Serialize for Content {
+            fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+                panic!("content must not be serialized")
+            }
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Payload {
+            entity_id: i32,
+            content: Content,
+        }
+
+        assert_eq!(
+            payload_ids(&Payload {
+                entity_id: 1,
+                content: Content
+            }),
+            json!({ "entityId": 1 })
+        );
+    }
+
+    #[test]
+    fn adds_changes_to_output_of_mutation() {
+        let changes = Changes {
+            affected_uuids: [1, 2].into(),
+            event_ids: vec![3],
+        };
+        let body = serialize_with_changes(&SuccessOutput { success: true }, changes).unwrap();
+
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            json!({ "success": true, "affectedUuids": [1, 2], "eventIds": [3] })
+        );
+    }
+}
@@ -0,0 +1,389 @@
+//! Transactional outbox which publishes new events to webhooks.
+//!
+//! When webhooks are configured, [`EventPayload::save`] writes every new event into the table
+//! `event_outbox` (created by the migrations in `server/migrations`) in the same transaction as
+//! the event. Thus webhooks get exactly the events which have been committed. The [`Dispatcher`]
+//! copies each new event into one row per webhook of its [`OutboxConfig`] (without webhooks the
+//! event is just removed) and sends the rows as `POST` with the event as body (serialized like
+//! the output of `EventQuery`).
+//!
+//! A row is claimed in a short transaction which leases it for twice the request timeout, so
+//! that several servers can dispatch the same outbox without holding a lock or a connection
+//! during the request. Delivered rows are deleted, failed deliveries are retried with
+//! exponential backoff until [`OutboxConfig::max_attempts`] is reached. Such rows are kept with
+//! their last error for inspection until [`OutboxConfig::failed_retention_seconds`] after the
+//! event.
+//!
+//! Events are delivered at least once: consumers need to ignore events whose id they have seen
+//! already. Since retries are scheduled per row, events might arrive out of order.
+//!
+//! [`EventPayload::save`]: crate::event::EventPayload::save
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use sqlx::types::Json;
+use sqlx::{MySqlPool, Row};
+
+use crate::config::OutboxConfig;
+use crate::database::{placeholders, Executor};
+use crate::event::Event;
+
+/// Maximum number of new events which are copied for the webhooks at once.
+const DISTRIBUTION_BATCH_SIZE: usize = 100;
+
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+/// Lets [`record`] write new events into the outbox when `config` has webhooks. Without webhooks
+/// the events would be removed again anyway.
+pub fn init(config: &OutboxConfig) {
+    RECORDING.store(!config.webhook_urls.is_empty(), Ordering::Relaxed);
+}
+
+/// Starts the [`Dispatcher`] in the background.
+pub fn start(pool: MySqlPool, config: OutboxConfig) {
+    init(&config);
+    let dispatcher = Dispatcher::new(config);
+    actix_rt::spawn(async move { dispatcher.run(&pool).await });
+}
+
+/// Writes `event` into the outbox unless no webhooks are configured (see [`init`]). Called inside
+/// the transaction which creates the event.
+pub async fn record(
+    event: &Event,
+    transaction: &mut sqlx::Transaction<'_, sqlx::MySql>,
+) -> Result<(), sqlx::Error> {
+    if !RECORDING.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    sqlx::query(
+        r#"
+            INSERT INTO event_outbox (event_id, payload, created_at)
+                VALUES (?, ?, NOW())
+        "#,
+    )
+    .bind(event.abstract_event.id)
+    .bind(Json(event))
+    .execute(&mut *transaction)
+    .await?;
+
+    Ok(())
+}
+
+/// Row of the outbox which has been claimed for sending it to its webhook.
+#[derive(Debug)]
+pub struct Delivery {
+    id: u64,
+    pub event_id: i32,
+    pub webhook_url: String,
+    pub payload: serde_json::Value,
+    /// Number of attempts including this one.
+    pub attempts: u32,
+}
+
+/// Sends the events in the outbox to their webhooks.
+pub struct Dispatcher {
+    client: reqwest::Client,
+    config: OutboxConfig,
+}
+
+impl Dispatcher {
+    pub fn new(config: OutboxConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_seconds))
+            .build()
+            .expect("HTTP client can be built");
+
+        Self { client, config }
+    }
+
+    /// Delivers the events in the outbox until the process ends.
+    pub async fn run(&self, pool: &MySqlPool) {
+        let poll_interval = Duration::from_millis(self.config.poll_interval_milliseconds);
+
+        loop {
+            if let Err(error) = self.distribute(pool).await {
+                tracing::error!(%error, "could not distribute new events to webhooks");
+            }
+            match self.deliver_next(pool).await {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(error) => tracing::error!(%error, "could not read event outbox"),
+            }
+            if let Err(error) = self.remove_given_up(pool).await {
+                tracing::error!(%error, "could not remove failed deliveries from event outbox");
+            }
+            actix_rt::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Copies new events into one row per webhook. Returns the number of distributed events.
+    pub async fn distribute<'a, E>(&self, executor: E) -> Result<usize, sqlx::Error>
+    where
+        E: Executor<'a>,
+    {
+        let mut transaction = executor.begin().await?;
+
+        let ids: Vec<u64> = sqlx::query_scalar(
+            r#"
+                SELECT id
+                FROM event_outbox
+                WHERE webhook_url IS NULL
+                ORDER BY id
+                LIMIT ?
+                FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .bind(DISTRIBUTION_BATCH_SIZE as u64)
+        .fetch_all(&mut transaction)
+        .await?;
+
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        for webhook_url in &self.config.webhook_urls {
+            let query = format!(
+                r#"
+                    INSERT INTO event_outbox
+                        (event_id, webhook_url, payload, next_attempt_at, created_at)
+                        SELECT event_id, ?, payload, NOW(), created_at
+                        FROM event_outbox
+                        WHERE id IN ({})
+                        ORDER BY id
+                "#,
+                placeholders(ids.len())
+            );
+            let mut query = sqlx::query(&query).bind(webhook_url);
+            for id in &ids {
+                query = query.bind(id);
+            }
+            query.execute(&mut transaction).await?;
+        }
+
+        let query = format!(
+            "DELETE FROM event_outbox WHERE id IN ({})",
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query(&query);
+        for id in &ids {
+            query = query.bind(id);
+        }
+        query.execute(&mut transaction).await?;
+
+        transaction.commit().await?;
+
+        Ok(ids.len())
+    }
+
+    /// Delivers the oldest due row of the outbox. Returns `false` when no row is due.
+    pub async fn deliver_next(&self, pool: &MySqlPool) -> Result<bool, sqlx::Error> {
+        let delivery = match self.claim_next(pool).await? {
+            Some(delivery) => delivery,
+            None => return Ok(false),
+        };
+        let result = self.send(&delivery).await;
+        self.complete(&delivery, result, pool).await?;
+
+        Ok(true)
+    }
+
+    /// Claims the oldest due row of the outbox. It is not due again until the lease (twice the
+    /// request timeout) has run out, so that it is retried when the server stops during the
+    /// request.
+    pub async fn claim_next<'a, E>(&self, executor: E) -> Result<Option<Delivery>, sqlx::Error>
+    where
+        E: Executor<'a>,
+    {
+        let mut transaction = executor.begin().await?;
+
+        let row = sqlx::query(
+            r#"
+                SELECT id, event_id, webhook_url, payload, attempts
+                FROM event_outbox
+                WHERE next_attempt_at <= NOW()
+                ORDER BY id
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .fetch_optional(&mut transaction)
+        .await?;
+
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        let delivery = Delivery {
+            id: row.try_get("id")?,
+            event_id: row.try_get("event_id")?,
+            webhook_url: row.try_get("webhook_url")?,
+            payload: row.try_get::<Json<serde_json::Value>, _>("payload")?.0,
+            attempts: row.try_get::<u32, _>("attempts")? + 1,
+        };
+
+        sqlx::query(
+            r#"
+                UPDATE event_outbox
+                SET attempts = ?, next_attempt_at = NOW() + INTERVAL ? SECOND
+                WHERE id = ?
+            "#,
+        )
+        .bind(delivery.attempts)
+        .bind(self.config.request_timeout_seconds.saturating_mul(2))
+        .bind(delivery.id)
+        .execute(&mut transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(Some(delivery))
+    }
+
+    /// Sends the event of `delivery` to its webhook.
+    pub async fn send(&self, delivery: &Delivery) -> Result<(), String> {
+        let response = self
+            .client
+            .post(&delivery.webhook_url)
+            .header("Content-Type", "application/json; charset=utf-8")
+            .body(delivery.payload.to_string())
+            .send()
+            .await
+            .map_err(|error| error.to_string())?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("webhook responded with {}", response.status()))
+        }
+    }
+
+    /// Deletes the row of a successful `delivery` or schedules its retry. Nothing is changed when
+    /// the row has been claimed again in the meantime since its lease has run out.
+    pub async fn complete<'a, E>(
+        &self,
+        delivery: &Delivery,
+        result: Result<(), String>,
+        executor: E,
+    ) -> Result<(), sqlx::Error>
+    where
+        E: Executor<'a>,
+    {
+        let Delivery {
+            id,
+            event_id,
+            ref webhook_url,
+            attempts,
+            ..
+        } = *delivery;
+
+        let error = match result {
+            Ok(()) => {
+                sqlx::query("DELETE FROM event_outbox WHERE id = ? AND attempts = ?")
+                    .bind(id)
+                    .bind(attempts)
+                    .execute(executor)
+                    .await?;
+                return Ok(());
+            }
+            Err(error) => error,
+        };
+        let retry = retry_delay(&self.config, attempts);
+
+        match retry {
+            Some(delay) => tracing::warn!(
+                event_id,
+                webhook_url,
+                attempts,
+                %error,
+                retry_in_seconds = delay.as_secs(),
+                "could not deliver event"
+            ),
+            None => tracing::error!(
+                event_id,
+                webhook_url,
+                attempts,
+                %error,
+                "could not deliver event, giving up"
+            ),
+        }
+
+        sqlx::query(
+            r#"
+                UPDATE event_outbox
+                SET last_error = ?,
+                    next_attempt_at = IF(? IS NULL, NULL, NOW() + INTERVAL ? SECOND)
+                WHERE id = ? AND attempts = ?
+            "#,
+        )
+        .bind(error)
+        .bind(retry.map(|delay| delay.as_secs()))
+        .bind(retry.map(|delay| delay.as_secs()))
+        .bind(id)
+        .bind(attempts)
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Deletes the rows whose delivery has been given up once they are older than
+    /// [`OutboxConfig::failed_retention_seconds`].
+    pub async fn remove_given_up<'a, E>(&self, executor: E) -> Result<u64, sqlx::Error>
+    where
+        E: Executor<'a>,
+    {
+        let result = sqlx::query(
+            r#"
+                DELETE FROM event_outbox
+                WHERE webhook_url IS NOT NULL
+                    AND next_attempt_at IS NULL
+                    AND created_at < NOW() - INTERVAL ? SECOND
+            "#,
+        )
+        .bind(self.config.failed_retention_seconds)
+        .execute(executor)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// Returns the time until the next attempt after `attempts` failed attempts, or `None` when the
+/// delivery is given up.
+fn retry_delay(config: &OutboxConfig, attempts: u32) -> Option<Duration> {
+    if attempts >= config.max_attempts {
+        return None;
+    }
+
+    let seconds = config
+        .retry_seconds
+        .saturating_mul(2u64.saturating_pow(attempts - 1))
+        .min(config.max_retry_seconds);
+
+    Some(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::retry_delay;
+    use crate::config::OutboxConfig;
+
+    #[test]
+    fn doubles_retry_delay_up_to_maximum() {
+        let config = OutboxConfig {
+            max_attempts: 5,
+            retry_seconds: 10,
+            max_retry_seconds: 50,
+            ..Default::default()
+        };
+
+        assert_eq!(retry_delay(&config, 1), Some(Duration::from_secs(10)));
+        assert_eq!(retry_delay(&config, 2), Some(Duration::from_secs(20)));
+        assert_eq!(retry_delay(&config, 3), Some(Duration::from_secs(40)));
+        assert_eq!(retry_delay(&config, 4), Some(Duration::from_secs(50)));
+        assert_eq!(retry_delay(&config, 5), None);
+    }
+}
@@ -0,0 +1,373 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+
+use async_trait::async_trait;
+
+use super::{
+    EntityRecord, EntityRepository, EntityRevisionRecord, EventRepository, NewEntity, NewEvent,
+    NotificationRepository, SubscriptionRepository, TaxonomyTermRepository, ThreadRecord,
+    ThreadRepository, UuidRepository,
+};
+use crate::clock;
+use crate::event::{AbstractEvent, Event, EventError, EventStringParameters, EventUuidParameters};
+use crate::instance::Instance;
+use crate::notification::{Notification, Notifications};
+use crate::operation;
+use crate::subscription::Subscription;
+use crate::uuid::{EntityType, UuidError};
+
+/// Repository which keeps all data in memory. Uuids which have not been added are missing.
+#[derive(Debug, Default)]
+pub struct InMemoryRepository {
+    pub entities: HashMap<i32, EntityRecord>,
+    pub entity_revisions: HashMap<i32, EntityRevisionRecord>,
+    /// Fields of the entity revisions created via the repository.
+    pub entity_revision_fields: HashMap<i32, HashMap<String, String>>,
+    /// Types of the entities created via the repository.
+    pub entity_types: HashMap<i32, EntityType>,
+    /// Links between entities as pairs of parent and child id.
+    pub entity_links: Vec<(i32, i32)>,
+    /// Links between entities and taxonomy terms as pairs of entity and taxonomy term id.
+    pub taxonomy_links: Vec<(i32, i32)>,
+    pub taxonomy_terms: HashMap<i32, Instance>,
+    /// Threads and comments (a comment can be commented like a thread).
+    pub threads: HashMap<i32, ThreadRecord>,
+    pub comments: Vec<CommentRecord>,
+    /// Ids of trashed uuids which are no entity revisions.
+    pub trashed_uuids: HashSet<i32>,
+    pub events: Vec<Event>,
+    pub subscriptions: Vec<Subscription>,
+    /// Notifications by the id of their user.
+    pub notifications: HashMap<i32, Vec<Notification>>,
+}
+
+/// A comment created via the repository.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommentRecord {
+    pub id: i32,
+    pub thread_id: i32,
+    pub author_id: i32,
+    pub content: String,
+}
+
+impl InMemoryRepository {
+    pub fn add_entity(&mut self, id: i32, instance: Instance, current_revision_id: Option<i32>) {
+        self.entities.insert(
+            id,
+            EntityRecord {
+                id,
+                instance,
+                current_revision_id,
+            },
+        );
+    }
+
+    pub fn insert_entity_revision(&mut self, id: i32, repository_id: i32) {
+        self.entity_revisions.insert(
+            id,
+            EntityRevisionRecord {
+                id,
+                repository_id,
+                trashed: false,
+            },
+        );
+    }
+
+    pub fn add_taxonomy_term(&mut self, id: i32, instance: Instance) {
+        self.taxonomy_terms.insert(id, instance);
+    }
+
+    pub fn add_thread(&mut self, id: i32, instance: Instance, archived: bool) {
+        self.threads.insert(
+            id,
+            ThreadRecord {
+                id,
+                instance,
+                archived,
+            },
+        );
+    }
+
+    pub fn add_subscription(&mut self, object_id: i32, user_id: i32, send_email: bool) {
+        self.subscriptions.push(Subscription {
+            object_id,
+            user_id,
+            send_email,
+        });
+    }
+
+    pub fn is_trashed(&self, id: i32) -> bool {
+        match self.entity_revisions.get(&id) {
+            Some(revision) => revision.trashed,
+            None => self.trashed_uuids.contains(&id),
+        }
+    }
+
+    fn is_known(&self, id: i32) -> bool {
+        self.entities.contains_key(&id)
+            || self.entity_revisions.contains_key(&id)
+            || self.taxonomy_terms.contains_key(&id)
+            || self.threads.contains_key(&id)
+    }
+
+    /// Returns an id which is higher than the ones of all known uuids.
+    fn next_uuid_id(&self) -> i32 {
+        self.entities
+            .keys()
+            .chain(self.entity_revisions.keys())
+            .chain(self.taxonomy_terms.keys())
+            .chain(self.threads.keys())
+            .chain(self.trashed_uuids.iter())
+            .max()
+            .map_or(1, |id| id + 1)
+    }
+}
+
+#[async_trait]
+impl UuidRepository for InMemoryRepository {
+    async fn set_uuid_state(&mut self, id: i32, trashed: bool) -> Result<(), sqlx::Error> {
+        match self.entity_revisions.get_mut(&id) {
+            Some(revision) => revision.trashed = trashed,
+            None if trashed => {
+                self.trashed_uuids.insert(id);
+            }
+            None => {
+                self.trashed_uuids.remove(&id);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EntityRepository for InMemoryRepository {
+    async fn fetch_entity(&mut self, id: i32) -> Result<Option<EntityRecord>, UuidError> {
+        if !self.is_known(id) {
+            return Err(UuidError::NotFound);
+        }
+
+        Ok(self.entities.get(&id).cloned())
+    }
+
+    async fn fetch_entity_revision(
+        &mut self,
+        id: i32,
+    ) -> Result<Option<EntityRevisionRecord>, UuidError> {
+        if !self.is_known(id) {
+            return Err(UuidError::NotFound);
+        }
+
+        Ok(self.entity_revisions.get(&id).cloned())
+    }
+
+    async fn set_current_revision(
+        &mut self,
+        entity_id: i32,
+        revision_id: i32,
+    ) -> Result<(), sqlx::Error> {
+        if let Some(entity) = self.entities.get_mut(&entity_id) {
+            entity.current_revision_id = Some(revision_id);
+        }
+
+        Ok(())
+    }
+
+    async fn fetch_entity_instance(&mut self, id: i32) -> Result<Option<Instance>, UuidError> {
+        Ok(self.entities.get(&id).map(|entity| entity.instance.clone()))
+    }
+
+    async fn has_untrashed_children(&mut self, parent_id: i32) -> Result<bool, sqlx::Error> {
+        Ok(self
+            .entity_links
+            .iter()
+            .any(|(parent, child)| *parent == parent_id && !self.is_trashed(*child)))
+    }
+
+    async fn create_entity(&mut self, entity: &NewEntity) -> Result<i32, sqlx::Error> {
+        let id = self.next_uuid_id();
+
+        self.add_entity(id, entity.instance.clone(), None);
+        self.entity_types.insert(id, entity.entity_type.clone());
+
+        Ok(id)
+    }
+
+    async fn link_entity(&mut self, parent_id: i32, child_id: i32) -> Result<(), sqlx::Error> {
+        self.entity_links.push((parent_id, child_id));
+
+        Ok(())
+    }
+
+    async fn link_entity_to_taxonomy_term(
+        &mut self,
+        entity_id: i32,
+        taxonomy_term_id: i32,
+    ) -> Result<(), sqlx::Error> {
+        self.taxonomy_links.push((entity_id, taxonomy_term_id));
+
+        Ok(())
+    }
+
+    async fn fetch_latest_untrashed_revision(
+        &mut self,
+        entity_id: i32,
+    ) -> Result<Option<(i32, HashMap<String, String>)>, sqlx::Error> {
+        // Ids grow over time, so the highest id is the one of the latest revision.
+        Ok(self
+            .entity_revisions
+            .values()
+            .filter(|revision| revision.repository_id == entity_id && !revision.trashed)
+            .map(|revision| revision.id)
+            .max()
+            .map(|id| {
+                let fields = self.entity_revision_fields.get(&id).cloned();
+                (id, fields.unwrap_or_default())
+            }))
+    }
+
+    async fn create_entity_revision(
+        &mut self,
+        _author_id: i32,
+        entity_id: i32,
+        fields: HashMap<String, String>,
+    ) -> Result<i32, operation::Error> {
+        let id = self.next_uuid_id();
+
+        self.insert_entity_revision(id, entity_id);
+        self.entity_revision_fields.insert(id, fields);
+
+        Ok(id)
+    }
+}
+
+#[async_trait]
+impl TaxonomyTermRepository for InMemoryRepository {
+    async fn fetch_taxonomy_term_instance(
+        &mut self,
+        id: i32,
+    ) -> Result<Option<Instance>, UuidError> {
+        Ok(self.taxonomy_terms.get(&id).cloned())
+    }
+}
+
+#[async_trait]
+impl ThreadRepository for InMemoryRepository {
+    async fn fetch_thread(&mut self, id: i32) -> Result<Option<ThreadRecord>, UuidError> {
+        Ok(self.threads.get(&id).cloned())
+    }
+
+    async fn create_comment(
+        &mut self,
+        thread: &ThreadRecord,
+        author_id: i32,
+        content: &str,
+    ) -> Result<i32, sqlx::Error> {
+        let id = self.next_uuid_id();
+
+        self.add_thread(id, thread.instance.clone(), false);
+        self.comments.push(CommentRecord {
+            id,
+            thread_id: thread.id,
+            author_id,
+            content: content.to_string(),
+        });
+
+        Ok(id)
+    }
+}
+
+#[async_trait]
+impl EventRepository for InMemoryRepository {
+    async fn save_event(&mut self, event: NewEvent) -> Result<Event, EventError> {
+        let id = self.events.len() as i32 + 1;
+        let date = clock::now();
+        let to_event = |event: NewEvent| {
+            Event::try_from(AbstractEvent {
+                __typename: event.raw_typename.clone().into(),
+                id,
+                instance: event.instance,
+                date: date.clone(),
+                actor_id: event.actor_id,
+                object_id: event.object_id,
+                raw_typename: event.raw_typename,
+                string_parameters: EventStringParameters(event.string_parameters),
+                uuid_parameters: EventUuidParameters(event.uuid_parameters),
+            })
+        };
+
+        let saved_event = to_event(event.clone())?;
+        Notifications::notify_subscribers(&saved_event, self).await?;
+        self.events.push(to_event(event)?);
+
+        Ok(saved_event)
+    }
+}
+
+#[async_trait]
+impl SubscriptionRepository for InMemoryRepository {
+    async fn fetch_subscriptions_by_object(
+        &mut self,
+        object_id: i32,
+    ) -> Result<Vec<Subscription>, sqlx::Error> {
+        Ok(self
+            .subscriptions
+            .iter()
+            .filter(|subscription| subscription.object_id == object_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn save_subscription(&mut self, subscription: &Subscription) -> Result<(), sqlx::Error> {
+        self.remove_subscription(subscription).await?;
+        self.subscriptions.push(subscription.clone());
+
+        Ok(())
+    }
+
+    async fn remove_subscription(
+        &mut self,
+        subscription: &Subscription,
+    ) -> Result<(), sqlx::Error> {
+        self.subscriptions.retain(|other| {
+            other.object_id != subscription.object_id || other.user_id != subscription.user_id
+        });
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl NotificationRepository for InMemoryRepository {
+    async fn create_notification(
+        &mut self,
+        event: &Event,
+        user_id: i32,
+        send_email: bool,
+    ) -> Result<(), sqlx::Error> {
+        let id = self.notifications.values().map(Vec::len).sum::<usize>() as i32 + 1;
+
+        self.notifications
+            .entry(user_id)
+            .or_default()
+            .push(Notification {
+                id,
+                unread: true,
+                email_sent: false,
+                email: send_email,
+                event_id: event.abstract_event.id,
+            });
+
+        Ok(())
+    }
+
+    async fn set_notification_state(&mut self, id: i32, unread: bool) -> Result<(), sqlx::Error> {
+        self.notifications
+            .values_mut()
+            .flatten()
+            .filter(|notification| notification.id == id)
+            .for_each(|notification| notification.unread = unread);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+//! Data access of the aggregates behind traits, so that business rules can be tested without a
+//! database.
+//!
+//! Rules like "a checked-out revision cannot be rejected" are written against the traits of this
+//! module (e.g. [`crate::uuid::Entity::reject_revision_with`]). In production they run on a
+//! [`MySqlRepository`] wrapping the transaction of the message. Tests can use an
+//! [`InMemoryRepository`] instead (also exported by `test-utils`):
+//!
+//! ```ignore
+//! let mut repository = InMemoryRepository::default();
+//! repository.add_entity(1, Instance::De, Some(2));
+//! repository.insert_entity_revision(2, 1);
+//!
+//! let result = Entity::reject_revision_with(&payload, &mut repository).await;
+//! ```
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use crate::event::{Event, EventError, RawEventType};
+use crate::instance::Instance;
+use crate::operation;
+use crate::subscription::Subscription;
+use crate::uuid::{EntityType, UuidError};
+
+pub use memory::InMemoryRepository;
+pub use mysql::MySqlRepository;
+
+mod memory;
+mod mysql;
+
+/// The fields of an entity which business rules need.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EntityRecord {
+    pub id: i32,
+    pub instance: Instance,
+    pub current_revision_id: Option<i32>,
+}
+
+/// The fields of an entity revision which business rules need.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EntityRevisionRecord {
+    pub id: i32,
+    pub repository_id: i32,
+    pub trashed: bool,
+}
+
+/// An entity which shall be created (without revisions).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NewEntity {
+    pub entity_type: EntityType,
+    pub instance: Instance,
+    pub license_id: i32,
+}
+
+/// The fields of a thread (or comment) which business rules need.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ThreadRecord {
+    pub id: i32,
+    pub instance: Instance,
+    pub archived: bool,
+}
+
+/// An event which shall be saved.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NewEvent {
+    pub raw_typename: RawEventType,
+    pub actor_id: i32,
+    pub object_id: i32,
+    pub instance: Instance,
+    pub string_parameters: HashMap<String, String>,
+    pub uuid_parameters: HashMap<String, i32>,
+}
+
+// The errors are the ones of the model functions the MySQL implementation is based on. All of them
+// convert into `operation::Error`.
+
+#[async_trait]
+pub trait UuidRepository {
+    async fn set_uuid_state(&mut self, id: i32, trashed: bool) -> Result<(), sqlx::Error>;
+}
+
+#[async_trait]
+pub trait EntityRepository {
+    /// Returns `None` when the uuid exists but is no entity.
+    async fn fetch_entity(&mut self, id: i32) -> Result<Option<EntityRecord>, UuidError>;
+
+    /// Returns `None` when the uuid exists but is no entity revision.
+    async fn fetch_entity_revision(
+        &mut self,
+        id: i32,
+    ) -> Result<Option<EntityRevisionRecord>, UuidError>;
+
+    async fn set_current_revision(
+        &mut self,
+        entity_id: i32,
+        revision_id: i32,
+    ) -> Result<(), sqlx::Error>;
+
+    /// Returns `None` when there is no entity with the id.
+    async fn fetch_entity_instance(&mut self, id: i32) -> Result<Option<Instance>, UuidError>;
+
+    /// Returns whether the entity has children which are not trashed.
+    async fn has_untrashed_children(&mut self, parent_id: i32) -> Result<bool, sqlx::Error>;
+
+    /// Creates the entity and returns its id.
+    async fn create_entity(&mut self, entity: &NewEntity) -> Result<i32, sqlx::Error>;
+
+    /// Appends the entity `child_id` to the children of the entity `parent_id`.
+    async fn link_entity(&mut self, parent_id: i32, child_id: i32) -> Result<(), sqlx::Error>;
+
+    /// Appends the entity to the entities of the taxonomy term.
+    async fn link_entity_to_taxonomy_term(
+        &mut self,
+        entity_id: i32,
+        taxonomy_term_id: i32,
+    ) -> Result<(), sqlx::Error>;
+
+    /// Returns the id and the fields (by their name in camel case) of the latest revision of the
+    /// entity which is not trashed.
+    async fn fetch_latest_untrashed_revision(
+        &mut self,
+        entity_id: i32,
+    ) -> Result<Option<(i32, HashMap<String, String>)>, sqlx::Error>;
+
+    /// Creates a revision of the entity with the fields (by their name in camel case) and returns
+    /// its id.
+    async fn create_entity_revision(
+        &mut self,
+        author_id: i32,
+        entity_id: i32,
+        fields: HashMap<String, String>,
+    ) -> Result<i32, operation::Error>;
+}
+
+#[async_trait]
+pub trait TaxonomyTermRepository {
+    /// Returns `None` when there is no taxonomy term with the id.
+    async fn fetch_taxonomy_term_instance(
+        &mut self,
+        id: i32,
+    ) -> Result<Option<Instance>, UuidError>;
+}
+
+#[async_trait]
+pub trait ThreadRepository {
+    /// Returns `None` when there is no thread or comment with the id.
+    async fn fetch_thread(&mut self, id: i32) -> Result<Option<ThreadRecord>, UuidError>;
+
+    /// Creates a comment in the thread and returns its id.
+    async fn create_comment(
+        &mut self,
+        thread: &ThreadRecord,
+        author_id: i32,
+        content: &str,
+    ) -> Result<i32, sqlx::Error>;
+}
+
+#[async_trait]
+pub trait EventRepository {
+    /// Saves the event (including the notifications of the subscribers) and returns it.
+    async fn save_event(&mut self, event: NewEvent) -> Result<Event, EventError>;
+}
+
+#[async_trait]
+pub trait SubscriptionRepository {
+    async fn fetch_subscriptions_by_object(
+        &mut self,
+        object_id: i32,
+    ) -> Result<Vec<Subscription>, sqlx::Error>;
+
+    /// Creates the subscription or updates `send_email` of an existing one.
+    async fn save_subscription(&mut self, subscription: &Subscription) -> Result<(), sqlx::Error>;
+
+    async fn remove_subscription(&mut self, subscription: &Subscription)
+        -> Result<(), sqlx::Error>;
+}
+
+#[async_trait]
+pub trait NotificationRepository {
+    async fn create_notification(
+        &mut self,
+        event: &Event,
+        user_id: i32,
+        send_email: bool,
+    ) -> Result<(), sqlx::Error>;
+
+    async fn set_notification_state(&mut self, id: i32, unread: bool) -> Result<(), sqlx::Error>;
+}
@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use convert_case::{Case, Casing};
+
+use super::{
+    EntityRecord, EntityRepository, EntityRevisionRecord, EventRepository, NewEntity, NewEvent,
+    NotificationRepository, SubscriptionRepository, TaxonomyTermRepository, ThreadRecord,
+    ThreadRepository, UuidRepository,
+};
+use crate::clock;
+use crate::event::{Event, EventError, EventPayload};
+use crate::fetch_all_fields;
+use crate::instance::Instance;
+use crate::notification::Notifications;
+use crate::operation;
+use crate::subscription::{Subscription, Subscriptions};
+use crate::uuid::abstract_entity_revision::EntityRevisionPayload;
+use crate::uuid::{ConcreteUuid, Entity, EntityRevision, Uuid, UuidError, UuidFetcher};
+
+/// Repository on a MySQL transaction. Committing or rolling back is up to the owner of the
+/// transaction.
+pub struct MySqlRepository<'t, 'c> {
+    transaction: &'t mut sqlx::Transaction<'c, sqlx::MySql>,
+}
+
+impl<'t, 'c> MySqlRepository<'t, 'c> {
+    pub fn new(transaction: &'t mut sqlx::Transaction<'c, sqlx::MySql>) -> Self {
+        Self { transaction }
+    }
+}
+
+#[async_trait]
+impl UuidRepository for MySqlRepository<'_, '_> {
+    async fn set_uuid_state(&mut self, id: i32, trashed: bool) -> Result<(), sqlx::Error> {
+        Uuid::set_state(id, trashed, &mut *self.transaction).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EntityRepository for MySqlRepository<'_, '_> {
+    async fn fetch_entity(&mut self, id: i32) -> Result<Option<EntityRecord>, UuidError> {
+        let uuid = Entity::fetch_via_transaction(id, &mut *self.transaction).await?;
+
+        Ok(match uuid.concrete_uuid {
+            ConcreteUuid::Entity(entity) => Some(EntityRecord {
+                id,
+                instance: entity.abstract_entity.instance,
+                current_revision_id: entity.abstract_entity.current_revision_id,
+            }),
+            _ => None,
+        })
+    }
+
+    async fn fetch_entity_revision(
+        &mut self,
+        id: i32,
+    ) -> Result<Option<EntityRevisionRecord>, UuidError> {
+        let uuid = EntityRevision::fetch_via_transaction(id, &mut *self.transaction).await?;
+
+        Ok(match uuid.concrete_uuid {
+            ConcreteUuid::EntityRevision(revision) => Some(EntityRevisionRecord {
+                id,
+                repository_id: revision.abstract_entity_revision.repository_id,
+                trashed: uuid.trashed,
+            }),
+            _ => None,
+        })
+    }
+
+    async fn set_current_revision(
+        &mut self,
+        entity_id: i32,
+        revision_id: i32,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+                        UPDATE entity
+                            SET current_revision_id = ?
+                            WHERE id = ?
+                    "#,
+            revision_id,
+            entity_id,
+        )
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn fetch_entity_instance(&mut self, id: i32) -> Result<Option<Instance>, UuidError> {
+        let entity = sqlx::query!("select instance_id from entity where id = ?", id)
+            .fetch_optional(&mut *self.transaction)
+            .await?;
+
+        match entity {
+            Some(entity) => Ok(Some(
+                Instance::fetch_by_id(entity.instance_id, &mut *self.transaction)
+                    .await?
+                    .ok_or(UuidError::InvalidInstance)?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    async fn has_untrashed_children(&mut self, parent_id: i32) -> Result<bool, sqlx::Error> {
+        Ok(sqlx::query!(
+            "SELECT uuid.id 
+                 FROM entity_link JOIN uuid ON uuid.id = entity_link.child_id
+                 WHERE uuid.trashed = 0 AND parent_id = ?",
+            parent_id
+        )
+        .fetch_optional(&mut *self.transaction)
+        .await?
+        .is_some())
+    }
+
+    async fn create_entity(&mut self, entity: &NewEntity) -> Result<i32, sqlx::Error> {
+        sqlx::query!(
+            r#"
+                INSERT INTO uuid (trashed, discriminator)
+                    VALUES (0, "entity")
+            "#,
+        )
+        .execute(&mut *self.transaction)
+        .await?;
+
+        let entity_id = sqlx::query!(r#"SELECT LAST_INSERT_ID() as id"#)
+            .fetch_one(&mut *self.transaction)
+            .await?
+            .id as i32;
+
+        let type_id = sqlx::query!(r#"SELECT id FROM type WHERE name = ?"#, entity.entity_type)
+            .fetch_one(&mut *self.transaction)
+            .await?
+            .id;
+        let instance_id = entity.instance.fetch_id(&mut *self.transaction).await?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO entity (id, type_id, instance_id, license_id, date)
+                    VALUES (?, ?, ?, ?, ?)
+            "#,
+            entity_id,
+            type_id,
+            instance_id,
+            entity.license_id,
+            clock::now(),
+        )
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(entity_id)
+    }
+
+    async fn link_entity(&mut self, parent_id: i32, child_id: i32) -> Result<(), sqlx::Error> {
+        let last_order = sqlx::query!(
+            r#"
+                    SELECT IFNULL(MAX(et.order), 0) AS current_last
+                        FROM entity_link et
+                        WHERE et.parent_id = ?
+                "#,
+            parent_id,
+        )
+        .fetch_one(&mut *self.transaction)
+        .await?
+        .current_last as i32
+            + 1;
+
+        sqlx::query!(
+            r#"
+                    INSERT INTO entity_link (parent_id, child_id, type_id, entity_link.order)
+                    VALUES (?, ?, 9, ?)
+                "#,
+            parent_id,
+            child_id,
+            last_order
+        )
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn link_entity_to_taxonomy_term(
+        &mut self,
+        entity_id: i32,
+        taxonomy_term_id: i32,
+    ) -> Result<(), sqlx::Error> {
+        let last_position = sqlx::query!(
+            r#"
+                    SELECT IFNULL(MAX(position), 0) AS current_last
+                        FROM term_taxonomy_entity
+                        WHERE term_taxonomy_id = ?
+                "#,
+            taxonomy_term_id
+        )
+        .fetch_one(&mut *self.transaction)
+        .await?
+        .current_last as i32
+            + 1;
+
+        sqlx::query!(
+            r#"
+                    INSERT INTO term_taxonomy_entity (entity_id, term_taxonomy_id, position)
+                    VALUES (?, ?, ?)
+                "#,
+            entity_id,
+            taxonomy_term_id,
+            last_position
+        )
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn fetch_latest_untrashed_revision(
+        &mut self,
+        entity_id: i32,
+    ) -> Result<Option<(i32, HashMap<String, String>)>, sqlx::Error> {
+        let revision_id = sqlx::query!(
+            r#"
+            SELECT er.id
+                FROM entity_revision er
+                JOIN uuid ON er.id = uuid.id
+                WHERE repository_id = ?
+                    AND trashed = 0
+                ORDER BY date DESC
+                LIMIT 1
+            "#,
+            entity_id
+        )
+        .fetch_optional(&mut *self.transaction)
+        .await?
+        .map(|x| x.id as i32);
+
+        match revision_id {
+            Some(revision_id) => {
+                let fields = fetch_all_fields!(revision_id, &mut *self.transaction)
+                    .await?
+                    .into_iter()
+                    .map(|field| (field.field.to_case(Case::Camel), field.value))
+                    .collect();
+                Ok(Some((revision_id, fields)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn create_entity_revision(
+        &mut self,
+        author_id: i32,
+        entity_id: i32,
+        fields: HashMap<String, String>,
+    ) -> Result<i32, operation::Error> {
+        Ok(EntityRevisionPayload::new(author_id, entity_id, fields)
+            .save(&mut *self.transaction)
+            .await?
+            .id)
+    }
+}
+
+#[async_trait]
+impl TaxonomyTermRepository for MySqlRepository<'_, '_> {
+    async fn fetch_taxonomy_term_instance(
+        &mut self,
+        id: i32,
+    ) -> Result<Option<Instance>, UuidError> {
+        let term = sqlx::query!(
+            r#"
+                SELECT term.instance_id
+                    FROM term_taxonomy
+                    JOIN term
+                        ON term.id = term_taxonomy.term_id
+                    WHERE term_taxonomy.id = ?
+            "#,
+            id
+        )
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        match term {
+            Some(term) => Ok(Some(
+                Instance::fetch_by_id(term.instance_id, &mut *self.transaction)
+                    .await?
+                    .ok_or(UuidError::InvalidInstance)?,
+            )),
+            None => Ok(None),
+        }
+    }
+}
+
+#[async_trait]
+impl ThreadRepository for MySqlRepository<'_, '_> {
+    async fn fetch_thread(&mut self, id: i32) -> Result<Option<ThreadRecord>, UuidError> {
+        let thread = sqlx::query!(
+            r#"
+                SELECT instance_id, archived
+                    FROM comment
+                    WHERE id = ?
+            "#,
+            id
+        )
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        match thread {
+            Some(thread) => Ok(Some(ThreadRecord {
+                id,
+                instance: Instance::fetch_by_id(thread.instance_id, &mut *self.transaction)
+                    .await?
+                    .ok_or(UuidError::InvalidInstance)?,
+                archived: thread.archived != 0,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    async fn create_comment(
+        &mut self,
+        thread: &ThreadRecord,
+        author_id: i32,
+        content: &str,
+    ) -> Result<i32, sqlx::Error> {
+        let instance_id = thread.instance.fetch_id(&mut *self.transaction).await?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO uuid (trashed, discriminator)
+                    VALUES (0, 'comment')
+            "#
+        )
+        .execute(&mut *self.transaction)
+        .await?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO comment (id, date, archived, title, content, uuid_id, parent_id, author_id, instance_id )
+                    VALUES (LAST_INSERT_ID(), ?, 0, NULL, ?, NULL, ?, ?, ?)
+            "#,
+            clock::now(),
+            content,
+            thread.id,
+            author_id,
+            instance_id
+        )
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(sqlx::query!(r#"SELECT LAST_INSERT_ID() as id"#)
+            .fetch_one(&mut *self.transaction)
+            .await?
+            .id as i32)
+    }
+}
+
+#[async_trait]
+impl EventRepository for MySqlRepository<'_, '_> {
+    async fn save_event(&mut self, event: NewEvent) -> Result<Event, EventError> {
+        let instance_id = event.instance.fetch_id(&mut *self.transaction).await?;
+
+        EventPayload::new(
+            event.raw_typename,
+            event.actor_id,
+            event.object_id,
+            instance_id,
+            event.string_parameters,
+            event.uuid_parameters,
+        )
+        .save(&mut *self.transaction)
+        .await
+    }
+}
+
+#[async_trait]
+impl SubscriptionRepository for MySqlRepository<'_, '_> {
+    async fn fetch_subscriptions_by_object(
+        &mut self,
+        object_id: i32,
+    ) -> Result<Vec<Subscription>, sqlx::Error> {
+        Ok(
+            Subscriptions::fetch_by_object(object_id, &mut *self.transaction)
+                .await?
+                .0,
+        )
+    }
+
+    async fn save_subscription(&mut self, subscription: &Subscription) -> Result<(), sqlx::Error> {
+        subscription.save(&mut *self.transaction).await
+    }
+
+    async fn remove_subscription(
+        &mut self,
+        subscription: &Subscription,
+    ) -> Result<(), sqlx::Error> {
+        subscription.remove(&mut *self.transaction).await
+    }
+}
+
+#[async_trait]
+impl NotificationRepository for MySqlRepository<'_, '_> {
+    async fn create_notification(
+        &mut self,
+        event: &Event,
+        user_id: i32,
+        send_email: bool,
+    ) -> Result<(), sqlx::Error> {
+        Notifications::insert_notification(event, user_id, send_email, &mut *self.transaction).await
+    }
+
+    async fn set_notification_state(&mut self, id: i32, unread: bool) -> Result<(), sqlx::Error> {
+        let seen = !unread;
+        sqlx::query!(
+            r#"
+                    UPDATE notification
+                        SET seen = ?
+                        WHERE seen != ? AND id = ?
+                "#,
+            seen,
+            seen,
+            id
+        )
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,8 +1,19 @@
+use actix_web::body::to_bytes;
 use actix_web::{get, post, web, HttpRequest, HttpResponse};
+use serde::Serialize;
+use serde_json::Value;
 use sqlx::MySqlPool;
 
-use crate::database::Connection;
-use crate::message::{Message, MessageResponder};
+use crate::auth::{authenticate, authorize};
+use crate::cache::{invalidate_after, read_from_replica};
+use crate::config::{AuthConfig, BatchConfig, IdempotencyConfig, TimeoutConfig};
+use crate::database::{Connection, ReplicaPool};
+use crate::event_stream::{self, EventFeed};
+use crate::idempotency;
+use crate::message::{Message, MessageError, MessageResponder};
+use crate::ndjson;
+use crate::operation::ErrorCode;
+use crate::timeout;
 
 #[get("/")]
 async fn index() -> HttpResponse {
@@ -14,36 +25,338 @@ async fn health() -> HttpResponse {
     HttpResponse::Ok().finish()
 }
 
+#[get("/.well-known/ready")]
+async fn ready(
+    db_pool: web::Data<MySqlPool>,
+    replica_pool: Option<web::Data<ReplicaPool>>,
+) -> HttpResponse {
+    let readiness = crate::health::check_readiness(
+        db_pool.get_ref(),
+        replica_pool.as_ref().map(|data| &data.get_ref().0),
+    )
+    .await;
+
+    if readiness.ready {
+        HttpResponse::Ok().json(readiness)
+    } else {
+        HttpResponse::ServiceUnavailable().json(readiness)
+    }
+}
+
+#[get("/metrics")]
+async fn metrics(db_pool: web::Data<MySqlPool>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(crate::metrics::metrics().render(db_pool.get_ref()))
+}
+
+#[get("/schema")]
+async fn schema() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/json; charset=utf-8")
+        .json(crate::schema::openapi())
+}
+
+#[get("/events/stream")]
+async fn stream_events(
+    req: HttpRequest,
+    db_pool: web::Data<MySqlPool>,
+    feed: Option<web::Data<EventFeed>>,
+    auth: Option<web::Data<AuthConfig>>,
+) -> HttpResponse {
+    // The stream only reads events, thus read-only keys suffice.
+    if let Err(response) = authenticate(auth.as_ref().map(|data| data.get_ref()), &req) {
+        return response;
+    }
+
+    let feed = match feed {
+        Some(feed) => feed,
+        None => {
+            return HttpResponse::NotFound()
+                .json(ErrorCode::NotFound.to_body("The event stream is not enabled."))
+        }
+    };
+    let filter = match web::Query::<event_stream::Filter>::from_query(req.query_string()) {
+        Ok(filter) => filter.into_inner(),
+        Err(error) => {
+            return HttpResponse::BadRequest()
+                .json(ErrorCode::InvalidParameter.to_body(&error.to_string()))
+        }
+    };
+    let last_event_id = match event_stream::last_event_id(&req) {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    feed.respond(db_pool.get_ref().clone(), filter, last_event_id)
+        .await
+}
+
 #[post("/")]
 async fn message(
     req: HttpRequest,
-    payload: web::Json<Message>,
+    payload: web::Json<Value>,
     db_pool: web::Data<MySqlPool>,
+    replica_pool: Option<web::Data<ReplicaPool>>,
+    auth: Option<web::Data<AuthConfig>>,
+    idempotency_config: Option<web::Data<IdempotencyConfig>>,
+    timeout_config: Option<web::Data<TimeoutConfig>>,
 ) -> HttpResponse {
-    let rollback = req
+    let rollback = has_header_flag(&req, "Rollback");
+    let streamed = ndjson::is_accepted(&req);
+    let idempotency_key = req
         .headers()
-        .get("Rollback")
-        .map_or(false, |value| matches!(value.to_str(), Ok("true")));
-    let message = payload.into_inner();
+        .get(idempotency::HEADER)
+        .map(|value| value.to_str().unwrap_or_default());
+    let access = match authenticate(auth.as_ref().map(|data| data.get_ref()), &req) {
+        Ok(access) => access,
+        Err(response) => return response,
+    };
+    let message = match Message::from_value(payload.into_inner()) {
+        Ok(message) => message,
+        Err(error) => return invalid_message(&error, &error.to_string()),
+    };
+
+    if let Err(response) = authorize(access, std::slice::from_ref(&message)) {
+        return response;
+    }
+
     let pool = db_pool.get_ref();
 
-    if rollback {
-        let mut transaction = pool.begin().await.expect("Failed to begin transaction.");
-        let connection = Connection::Transaction(&mut transaction);
-        let response = message.handle(connection).await;
-        transaction
-            .rollback()
+    let requested_timeout = timeout::requested_timeout(&req);
+    let timeouts = timeout_config.map_or_else(Default::default, |data| data.into_inner());
+
+    timeout::scope(
+        timeouts,
+        requested_timeout,
+        invalidate_after(async {
+            if rollback {
+                let mut transaction = pool.begin().await.expect("Failed to begin transaction.");
+                let connection = Connection::Transaction(&mut transaction);
+                let response = message.handle(connection).await;
+                transaction
+                    .rollback()
+                    .await
+                    .expect("Failed to roll back transaction.");
+                response
+            } else if let (Some(key), false) = (idempotency_key, message.is_query()) {
+                let config = idempotency_config
+                    .map(|data| data.get_ref().clone())
+                    .unwrap_or_default();
+                let fingerprint = format!(
+                    "{:x}",
+                    md5::compute(serde_json::to_vec(&message).unwrap_or_default())
+                );
+
+                let reserved_key =
+                    match idempotency::reserve_key(pool, &config, key, &fingerprint).await {
+                        Ok(reserved_key) => reserved_key,
+                        Err(response) => return response,
+                    };
+                let mut transaction = pool.begin().await.expect("Failed to begin transaction.");
+                let response = message
+                    .handle(Connection::Transaction(&mut transaction))
+                    .await;
+                reserved_key.complete(transaction, response).await
+            } else {
+                let (routed_pool, replica) = route(
+                    &message,
+                    pool,
+                    replica_pool.as_ref().map(|data| data.get_ref()),
+                );
+                if streamed {
+                    if let Some(response) = ndjson::respond(&message, routed_pool).await {
+                        return response;
+                    }
+                }
+                read_from_replica(replica, message.handle(Connection::Pool(routed_pool))).await
+            }
+        }),
+    )
+    .await
+}
+
+/// Result of a single message inside a batch request.
+#[derive(Serialize)]
+pub struct BatchResult {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl BatchResult {
+    async fn from_response(response: HttpResponse) -> Self {
+        let status = response.status().as_u16();
+        let body = to_bytes(response.into_body())
             .await
-            .expect("Failed to roll back transaction.");
-        response
-    } else {
-        let connection = Connection::Pool(pool);
-        message.handle(connection).await
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or(Value::Null);
+
+        Self { status, body }
     }
+
+    fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Executes a list of messages in order and returns one [`BatchResult`] per message.
+///
+/// With the header `Transaction: true` all messages are executed inside a single transaction
+/// which is only committed when every message succeeded. The batch stops at the first failing
+/// message, so the returned list ends with the result of the failing message in this case.
+/// The header `Rollback: true` behaves like for single messages.
+///
+/// Batches with more than [`BatchConfig::max_messages`] messages are rejected. An
+/// `Idempotency-Key` is not supported for batches and rejected as well, so that clients do not
+/// rely on retried batches being executed only once.
+#[post("/batch")]
+async fn batch(
+    req: HttpRequest,
+    payload: web::Json<Vec<Value>>,
+    db_pool: web::Data<MySqlPool>,
+    replica_pool: Option<web::Data<ReplicaPool>>,
+    auth: Option<web::Data<AuthConfig>>,
+    timeout_config: Option<web::Data<TimeoutConfig>>,
+    batch_config: Option<web::Data<BatchConfig>>,
+) -> HttpResponse {
+    let rollback = has_header_flag(&req, "Rollback");
+    let atomic = has_header_flag(&req, "Transaction");
+    let access = match authenticate(auth.as_ref().map(|data| data.get_ref()), &req) {
+        Ok(access) => access,
+        Err(response) => return response,
+    };
+
+    if req.headers().contains_key(idempotency::HEADER) {
+        return HttpResponse::BadRequest().json(ErrorCode::InvalidParameter.to_body(&format!(
+            "{} is not supported for batches",
+            idempotency::HEADER
+        )));
+    }
+
+    let payload = payload.into_inner();
+    let max_messages = batch_config.map_or_else(
+        || BatchConfig::default().max_messages,
+        |data| data.max_messages,
+    );
+    if payload.len() > max_messages {
+        return HttpResponse::BadRequest().json(ErrorCode::InvalidParameter.to_body(&format!(
+            "a batch may contain at most {max_messages} messages, got {}",
+            payload.len()
+        )));
+    }
+
+    let mut messages = Vec::new();
+    for (position, value) in payload.into_iter().enumerate() {
+        match Message::from_value(value) {
+            Ok(batch_message) => messages.push(batch_message),
+            Err(error) => {
+                return invalid_message(&error, &format!("message {position}: {error}"));
+            }
+        }
+    }
+
+    if let Err(response) = authorize(access, &messages) {
+        return response;
+    }
+
+    let pool = db_pool.get_ref();
+    let mut results = Vec::with_capacity(messages.len());
+
+    // Mutations invalidate the cache again after the transaction has been committed.
+    let requested_timeout = timeout::requested_timeout(&req);
+    let timeouts = timeout_config.map_or_else(Default::default, |data| data.into_inner());
+
+    timeout::scope(
+        timeouts,
+        requested_timeout,
+        invalidate_after(async {
+            if rollback || atomic {
+                let mut transaction = pool.begin().await.expect("Failed to begin transaction.");
+                let mut failed = false;
+
+                for batch_message in messages.iter() {
+                    let connection = Connection::Transaction(&mut transaction);
+                    let result =
+                        BatchResult::from_response(batch_message.handle(connection).await).await;
+                    failed = !result.is_success();
+                    results.push(result);
+
+                    if atomic && failed {
+                        break;
+                    }
+                }
+
+                if rollback || (atomic && failed) {
+                    transaction
+                        .rollback()
+                        .await
+                        .expect("Failed to roll back transaction.");
+                } else {
+                    transaction
+                        .commit()
+                        .await
+                        .expect("Failed to commit transaction.");
+                }
+            } else {
+                for batch_message in messages.iter() {
+                    let (routed_pool, replica) = route(
+                        batch_message,
+                        pool,
+                        replica_pool.as_ref().map(|data| data.get_ref()),
+                    );
+                    let response = read_from_replica(
+                        replica,
+                        batch_message.handle(Connection::Pool(routed_pool)),
+                    )
+                    .await;
+                    results.push(BatchResult::from_response(response).await);
+                }
+            }
+        }),
+    )
+    .await;
+
+    HttpResponse::Ok()
+        .content_type("application/json; charset=utf-8")
+        .json(results)
+}
+
+/// Returns the pool a message shall be executed on outside of a transaction: queries go to the
+/// read replica if there is one, everything else goes to the primary database. The flag tells
+/// whether the replica was chosen.
+fn route<'a>(
+    routed_message: &Message,
+    pool: &'a MySqlPool,
+    replica_pool: Option<&'a ReplicaPool>,
+) -> (&'a MySqlPool, bool) {
+    match replica_pool {
+        Some(ReplicaPool(replica_pool)) if routed_message.is_query() => (replica_pool, true),
+        _ => (pool, false),
+    }
+}
+
+/// Returns the response of `POST /` for a message which cannot be parsed.
+pub fn invalid_message(error: &MessageError, description: &str) -> HttpResponse {
+    tracing::warn!(%error, "rejected invalid message");
+    HttpResponse::BadRequest()
+        .content_type("application/json; charset=utf-8")
+        .json(error.error_code().to_body(description))
+}
+
+fn has_header_flag(req: &HttpRequest, name: &str) -> bool {
+    req.headers()
+        .get(name)
+        .map_or(false, |value| matches!(value.to_str(), Ok("true")))
 }
 
 pub fn init(cfg: &mut web::ServiceConfig) {
     cfg.service(index);
     cfg.service(health);
+    cfg.service(ready);
+    cfg.service(metrics);
+    cfg.service(schema);
+    cfg.service(stream_events);
     cfg.service(message);
+    cfg.service(batch);
 }
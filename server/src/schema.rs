@@ -0,0 +1,202 @@
+//! Generates a description of the wire protocol of the database layer.
+//!
+//! Every message type gets a JSON schema for its payload and for its output. Together they are
+//! published as an [OpenAPI](https://spec.openapis.org/oas/v3.0.3) document describing the
+//! endpoint `/`. The document is served via `GET /schema` and can be printed with
+//! `cargo run --bin schema`.
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::{Schema, SchemaObject, SubschemaValidation};
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+
+use crate::alias::AliasMessage;
+use crate::cache::CacheMessage;
+use crate::changes::Changes;
+use crate::event::EventMessage;
+use crate::metadata::MetadataMessage;
+use crate::navigation::NavigationMessage;
+use crate::notification::NotificationMessage;
+use crate::operation::{is_mutation, ErrorCode, Operation};
+use crate::subject::SubjectsMessage;
+use crate::subscription::SubscriptionMessage;
+use crate::thread::ThreadMessage;
+use crate::user::UserMessage;
+use crate::uuid::{EntityMessage, PageMessage, TaxonomyTermMessage, UuidMessage};
+use crate::vocabulary::VocabularyMessage;
+
+/// Version of the described protocol which is the version of the server.
+pub const SCHEMA_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Schemas of the payload and of the output of a single message type.
+pub struct MessageSchema {
+    pub message_type: &'static str,
+    pub payload: Schema,
+    pub output: Schema,
+}
+
+impl MessageSchema {
+    pub fn new<P>(message_type: &'static str, generator: &mut SchemaGenerator) -> Self
+    where
+        P: Operation + JsonSchema,
+        P::Output: JsonSchema,
+    {
+        let mut output = inline_schema_for::<P::Output>(generator);
+
+        // Mutations additionally list their changes, see `crate::changes`.
+        if is_mutation(message_type) {
+            output = SchemaObject {
+                subschemas: Some(Box::new(SubschemaValidation {
+                    all_of: Some(vec![output, inline_schema_for::<Changes>(generator)]),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            }
+            .into();
+        }
+
+        Self {
+            message_type,
+            payload: inline_schema_for::<P>(generator),
+            output,
+        }
+    }
+}
+
+/// Lists the schemas of all message types of a message enum like [`UuidMessage`].
+pub trait MessageSchemas {
+    fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema>;
+}
+
+pub fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema> {
+    [
+        AliasMessage::message_schemas(generator),
+        CacheMessage::message_schemas(generator),
+        EntityMessage::message_schemas(generator),
+        EventMessage::message_schemas(generator),
+        MetadataMessage::message_schemas(generator),
+        NavigationMessage::message_schemas(generator),
+        NotificationMessage::message_schemas(generator),
+        PageMessage::message_schemas(generator),
+        SubjectsMessage::message_schemas(generator),
+        SubscriptionMessage::message_schemas(generator),
+        TaxonomyTermMessage::message_schemas(generator),
+        ThreadMessage::message_schemas(generator),
+        UserMessage::message_schemas(generator),
+        UuidMessage::message_schemas(generator),
+        VocabularyMessage::message_schemas(generator),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Returns the OpenAPI document of the database layer.
+///
+/// For each message type `T` the components `TPayload`, `TOutput` and `TMessage` (the whole
+/// request body) are defined. Nested types are defined as components with their own name.
+pub fn openapi() -> Value {
+    let mut generator = SchemaSettings::openapi3().into_generator();
+    let messages = message_schemas(&mut generator);
+    let error_response = inline_schema_for::<ErrorResponse>(&mut generator);
+
+    let mut schemas = Map::new();
+    let mut requests = Vec::new();
+    let mut outputs = Vec::new();
+
+    for message in messages {
+        let name = message.message_type;
+
+        schemas.insert(format!("{name}Payload"), to_value(message.payload));
+        schemas.insert(format!("{name}Output"), to_value(message.output));
+        schemas.insert(
+            format!("{name}Message"),
+            json!({
+                "type": "object",
+                "required": ["type", "payload"],
+                "properties": {
+                    "type": { "type": "string", "enum": [name] },
+                    "payload": component_ref(&format!("{name}Payload")),
+                },
+            }),
+        );
+
+        requests.push(component_ref(&format!("{name}Message")));
+        outputs.push(component_ref(&format!("{name}Output")));
+    }
+
+    schemas.insert("ErrorResponse".to_string(), to_value(error_response));
+
+    for (name, schema) in generator.take_definitions() {
+        schemas.insert(name, to_value(schema));
+    }
+
+    let error_content = json!({
+        "application/json": { "schema": component_ref("ErrorResponse") }
+    });
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "serlo.org database layer",
+            "version": SCHEMA_VERSION,
+        },
+        "paths": {
+            "/": {
+                "post": {
+                    "summary": "Executes a single message",
+                    "operationId": "message",
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "oneOf": requests,
+                                    "discriminator": { "propertyName": "type" },
+                                },
+                            },
+                        },
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "The output of the message",
+                            "content": {
+                                "application/json": { "schema": { "oneOf": outputs } },
+                            },
+                        },
+                        "400": { "description": "Bad request", "content": error_content },
+                        "404": { "description": "Not found", "content": error_content },
+                        "500": { "description": "Internal server error", "content": error_content },
+                    },
+                },
+            },
+        },
+        "components": { "schemas": schemas },
+    })
+}
+
+/// Body of all error responses, see [`Operation::handle`].
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+struct ErrorResponse {
+    #[serde(flatten)]
+    code: ErrorCode,
+    message: String,
+}
+
+/// Returns the schema of `T` itself instead of a reference to it. This way types which share
+/// the same name (like all the `Payload` structs) do not override each other's definition.
+fn inline_schema_for<T: ?Sized + JsonSchema>(generator: &mut SchemaGenerator) -> Schema {
+    let mut schema = T::json_schema(generator);
+    for visitor in generator.visitors_mut() {
+        visitor.visit_schema(&mut schema);
+    }
+    schema
+}
+
+fn component_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+fn to_value(schema: Schema) -> Value {
+    serde_json::to_value(schema).expect("JSON schemas can always be serialized")
+}
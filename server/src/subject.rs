@@ -1,10 +1,13 @@
 use actix_web::HttpResponse;
 use async_trait::async_trait;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::database::{Connection, Executor};
 use crate::message::MessageResponder;
 use crate::operation::{self, Operation};
+use crate::schema::{MessageSchema, MessageSchemas};
 
 #[derive(Deserialize, Serialize)]
 #[serde(tag = "type", content = "payload")]
@@ -26,20 +29,29 @@ impl MessageResponder for SubjectsMessage {
     }
 }
 
+impl MessageSchemas for SubjectsMessage {
+    fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema> {
+        vec![MessageSchema::new::<subjects_query::Payload>(
+            "SubjectsQuery",
+            generator,
+        )]
+    }
+}
+
 pub mod subjects_query {
     use super::*;
 
-    #[derive(Deserialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {}
 
-    #[derive(Serialize)]
+    #[derive(Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub subjects: Vec<Subject>,
     }
 
-    #[derive(Serialize)]
+    #[derive(Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Subject {
         pub instance: String,
@@ -1,6 +1,8 @@
 use crate::operation::{self, Operation};
 use actix_web::HttpResponse;
 use async_trait::async_trait;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::model::{
@@ -8,6 +10,7 @@ use super::model::{
 };
 use crate::database::Connection;
 use crate::message::MessageResponder;
+use crate::schema::{MessageSchema, MessageSchemas};
 
 #[derive(Deserialize, Serialize)]
 #[serde(tag = "type", content = "payload")]
@@ -31,22 +34,34 @@ impl MessageResponder for SubscriptionMessage {
     }
 }
 
+impl MessageSchemas for SubscriptionMessage {
+    fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema> {
+        vec![
+            MessageSchema::new::<subscriptions_query::Payload>("SubscriptionsQuery", generator),
+            MessageSchema::new::<subscription_set_mutation::Payload>(
+                "SubscriptionSetMutation",
+                generator,
+            ),
+        ]
+    }
+}
+
 pub mod subscriptions_query {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub user_id: i32,
     }
 
-    #[derive(Serialize)]
+    #[derive(Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub subscriptions: Vec<SubscriptionByUser>,
     }
 
-    #[derive(Serialize)]
+    #[derive(Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct SubscriptionByUser {
         pub object_id: i32,
@@ -71,8 +86,8 @@ pub mod subscriptions_query {
 pub mod subscription_set_mutation {
     use super::*;
 
-    #[derive(Debug, Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub ids: Vec<i32>,
         pub user_id: i32,
@@ -80,7 +95,7 @@ pub mod subscription_set_mutation {
         pub send_email: bool,
     }
 
-    #[derive(Debug, Deserialize, Serialize)]
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         success: bool,
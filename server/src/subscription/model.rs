@@ -1,13 +1,14 @@
 use crate::subscription::messages::{subscription_set_mutation, subscriptions_query};
 use sqlx::MySqlPool;
 
+use crate::clock;
 use crate::database::Executor;
-use crate::datetime::DateTime;
+use crate::repository::{MySqlRepository, SubscriptionRepository};
 
 #[derive(Debug, Eq, PartialEq)]
 pub struct Subscriptions(pub Vec<Subscription>);
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Subscription {
     pub object_id: i32,
     pub user_id: i32,
@@ -84,7 +85,7 @@ impl Subscription {
             self.object_id,
             self.user_id,
             self.send_email,
-            DateTime::now(),
+            clock::now(),
             self.send_email,
         )
         .execute(&mut transaction)
@@ -150,7 +151,20 @@ impl Subscription {
         E: Executor<'a>,
     {
         let mut transaction = executor.begin().await?;
+        Self::change_subscription_with(payload, &mut MySqlRepository::new(&mut transaction))
+            .await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
 
+    pub async fn change_subscription_with<R>(
+        payload: &subscription_set_mutation::Payload,
+        repository: &mut R,
+    ) -> Result<(), sqlx::Error>
+    where
+        R: SubscriptionRepository,
+    {
         for id in &payload.ids {
             let subscription = Subscription {
                 object_id: *id,
@@ -159,12 +173,11 @@ impl Subscription {
             };
 
             if payload.subscribe {
-                subscription.save(&mut transaction).await?;
+                repository.save_subscription(&subscription).await?;
             } else {
-                subscription.remove(&mut transaction).await?;
+                repository.remove_subscription(&subscription).await?;
             }
         }
-        transaction.commit().await?;
 
         Ok(())
     }
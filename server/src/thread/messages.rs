@@ -3,11 +3,14 @@ use crate::operation::{self, Operation};
 use crate::uuid::Uuid;
 use actix_web::HttpResponse;
 use async_trait::async_trait;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::model::Threads;
 use crate::database::Connection;
 use crate::message::MessageResponder;
+use crate::schema::{MessageSchema, MessageSchemas};
 
 #[derive(Deserialize, Serialize)]
 #[serde(tag = "type", content = "payload")]
@@ -55,11 +58,36 @@ impl MessageResponder for ThreadMessage {
     }
 }
 
+impl MessageSchemas for ThreadMessage {
+    fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema> {
+        vec![
+            MessageSchema::new::<all_threads_query::Payload>("AllThreadsQuery", generator),
+            MessageSchema::new::<threads_query::Payload>("ThreadsQuery", generator),
+            MessageSchema::new::<create_thread_mutation::Payload>(
+                "ThreadCreateThreadMutation",
+                generator,
+            ),
+            MessageSchema::new::<create_comment_mutation::Payload>(
+                "ThreadCreateCommentMutation",
+                generator,
+            ),
+            MessageSchema::new::<set_thread_archived_mutation::Payload>(
+                "ThreadSetThreadArchivedMutation",
+                generator,
+            ),
+            MessageSchema::new::<edit_comment_mutation::Payload>(
+                "ThreadEditCommentMutation",
+                generator,
+            ),
+        ]
+    }
+}
+
 pub mod all_threads_query {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub first: i32,
         pub after: Option<String>,
@@ -101,8 +129,8 @@ pub mod all_threads_query {
 pub mod threads_query {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub id: i32,
     }
@@ -125,8 +153,8 @@ pub mod threads_query {
 pub mod create_thread_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub title: String,
         pub content: String,
@@ -153,8 +181,8 @@ pub mod create_thread_mutation {
 
 pub mod create_comment_mutation {
     use super::*;
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub thread_id: i32,
         pub content: String,
@@ -181,8 +209,8 @@ pub mod create_comment_mutation {
 pub mod set_thread_archived_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub ids: Vec<i32>,
         pub user_id: i32,
@@ -191,7 +219,7 @@ pub mod set_thread_archived_mutation {
 
     #[async_trait]
     impl Operation for Payload {
-        type Output = ();
+        type Output = operation::SuccessOutput;
 
         async fn execute(&self, connection: Connection<'_, '_>) -> operation::Result<Self::Output> {
             match connection {
@@ -200,7 +228,7 @@ pub mod set_thread_archived_mutation {
                     Threads::set_archive(self, transaction).await?
                 }
             }
-            Ok(())
+            Ok(operation::SuccessOutput { success: true })
         }
     }
 }
@@ -208,8 +236,8 @@ pub mod set_thread_archived_mutation {
 pub mod edit_comment_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub user_id: u32,
         pub comment_id: u32,
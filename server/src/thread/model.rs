@@ -1,21 +1,28 @@
+use std::collections::HashMap;
+
 use super::messages::{
     create_comment_mutation, create_thread_mutation, edit_comment_mutation,
     set_thread_archived_mutation,
 };
+use schemars::JsonSchema;
 use serde::Serialize;
 use sqlx::{MySqlPool, Row};
 
+use crate::cache::cache;
+use crate::changes;
+use crate::clock;
 use crate::database::Executor;
 use crate::datetime::DateTime;
-use crate::event::{
-    CreateCommentEventPayload, CreateThreadEventPayload, SetThreadStateEventPayload,
-};
+use crate::event::{CreateThreadEventPayload, RawEventType, SetThreadStateEventPayload};
 use crate::instance::Instance;
 use crate::operation;
+use crate::repository::{
+    EventRepository, MySqlRepository, NewEvent, SubscriptionRepository, ThreadRepository,
+};
 use crate::subscription::Subscription;
 use crate::uuid::{Uuid, UuidFetcher};
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Threads {
     pub first_comment_ids: Vec<i32>,
@@ -41,7 +48,7 @@ impl Threads {
 
         let after_parsed = match after.as_ref() {
             Some(date) => DateTime::parse_from_rfc3339(date)?,
-            None => DateTime::now(),
+            None => clock::now(),
         };
 
         // TODO: use alias for MAX(GREATEST(...)) when sqlx supports it
@@ -156,6 +163,7 @@ impl Threads {
         let comments = query.fetch_all(&mut transaction).await?;
         if comments.len() < number_comments {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::CommentNotFound,
                 reason: "not all given ids are comments".to_string(),
             });
         }
@@ -181,11 +189,16 @@ impl Threads {
                     .save(&mut transaction)
                     .await
                     .map_err(|error| operation::Error::InternalServerError {
+                        code: operation::ErrorCode::InternalServerError,
                         error: Box::new(error),
                     })?;
             }
         }
 
+        changes::record_affected_uuids(&payload.ids);
+
+        cache().invalidate(&payload.ids);
+
         transaction.commit().await?;
 
         Ok(())
@@ -197,78 +210,70 @@ impl Threads {
     ) -> Result<Uuid, operation::Error>
     where
         E: Executor<'a>,
+    {
+        let mut transaction = executor.begin().await?;
+        let comment_id =
+            Self::comment_thread_with(payload, &mut MySqlRepository::new(&mut transaction)).await?;
+
+        let comment = Uuid::fetch_via_transaction(comment_id, &mut transaction)
+            .await
+            .map_err(|error| operation::Error::InternalServerError {
+                code: operation::ErrorCode::InternalServerError,
+                error: Box::new(error),
+            })?;
+
+        transaction.commit().await?;
+
+        Ok(comment)
+    }
+
+    /// Adds a comment to a thread which is not archived. Returns the id of the comment.
+    pub async fn comment_thread_with<R>(
+        payload: &create_comment_mutation::Payload,
+        repository: &mut R,
+    ) -> Result<i32, operation::Error>
+    where
+        R: ThreadRepository + EventRepository + SubscriptionRepository,
     {
         if payload.content.is_empty() {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::EmptyContent,
                 reason: "content is empty".to_string(),
             });
         };
 
-        let mut transaction = executor.begin().await?;
-
-        let thread = sqlx::query!(
-            r#"
-                SELECT instance_id, archived
-                    FROM comment
-                    WHERE id = ?
-            "#,
-            payload.thread_id
-        )
-        .fetch_one(&mut transaction)
-        .await
-        .map_err(|error| match error {
-            sqlx::Error::RowNotFound => operation::Error::BadRequest {
+        let thread = repository.fetch_thread(payload.thread_id).await?.ok_or(
+            operation::Error::BadRequest {
+                code: operation::ErrorCode::ThreadNotFound,
                 reason: "thread does not exist".to_string(),
             },
-            error => error.into(),
-        })?;
+        )?;
 
-        if thread.archived != 0 {
-            // TODO: test is missing
+        if thread.archived {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::ThreadAlreadyArchived,
                 reason: "thread is already archived".to_string(),
             });
         }
 
-        sqlx::query!(
-            r#"
-                INSERT INTO uuid (trashed, discriminator)
-                    VALUES (0, 'comment')
-            "#
-        )
-        .execute(&mut transaction)
-        .await?;
-
-        sqlx::query!(
-            r#"
-                INSERT INTO comment (id, date, archived, title, content, uuid_id, parent_id, author_id, instance_id )
-                    VALUES (LAST_INSERT_ID(), ?, 0, NULL, ?, NULL, ?, ?, ?)
-            "#,
-            DateTime::now(),
-            payload.content,
-            payload.thread_id,
-            payload.user_id,
-            thread.instance_id
-        )
-        .execute(&mut transaction)
-        .await?;
-
-        let value = sqlx::query!(r#"SELECT LAST_INSERT_ID() as id"#)
-            .fetch_one(&mut transaction)
+        let comment_id = repository
+            .create_comment(&thread, payload.user_id, &payload.content)
             .await?;
-        let comment_id = value.id as i32;
 
-        CreateCommentEventPayload::new(
-            payload.thread_id,
-            comment_id,
-            payload.user_id,
-            thread.instance_id,
-        )
-        .save(&mut transaction)
-        .await
-        .map_err(|error| operation::Error::InternalServerError {
-            error: Box::new(error),
-        })?;
+        repository
+            .save_event(NewEvent {
+                raw_typename: RawEventType::CreateComment,
+                actor_id: payload.user_id,
+                object_id: comment_id,
+                instance: thread.instance,
+                string_parameters: HashMap::new(),
+                uuid_parameters: [("discussion".to_string(), payload.thread_id)].into(),
+            })
+            .await
+            .map_err(|error| operation::Error::InternalServerError {
+                code: operation::ErrorCode::InternalServerError,
+                error: Box::new(error),
+            })?;
 
         if payload.subscribe {
             for object_id in [payload.thread_id, comment_id].iter() {
@@ -277,19 +282,15 @@ impl Threads {
                     user_id: payload.user_id,
                     send_email: payload.send_email,
                 };
-                subscription.save(&mut transaction).await?;
+                repository.save_subscription(&subscription).await?;
             }
         }
 
-        let comment = Uuid::fetch_via_transaction(comment_id, &mut transaction)
-            .await
-            .map_err(|error| operation::Error::InternalServerError {
-                error: Box::new(error),
-            })?;
+        changes::record_affected_uuids(&[payload.thread_id]);
 
-        transaction.commit().await?;
+        cache().invalidate(&[payload.thread_id]);
 
-        Ok(comment)
+        Ok(comment_id)
     }
 
     pub async fn start_thread<'a, E>(
@@ -301,6 +302,7 @@ impl Threads {
     {
         if payload.content.is_empty() {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::EmptyContent,
                 reason: "content is empty".to_string(),
             });
         }
@@ -337,6 +339,7 @@ impl Threads {
         .fetch_one(&mut transaction)
         .await.map_err(|error| match error {
             sqlx::Error::RowNotFound => operation::Error::BadRequest{
+                code: operation::ErrorCode::UuidNotFound,
                 reason: "UUID not found".to_string(),
             },
             error => error.into(),})?
@@ -356,7 +359,7 @@ impl Threads {
                 INSERT INTO comment ( id , date , archived , title , content , uuid_id , parent_id , author_id , instance_id )
                     VALUES (LAST_INSERT_ID(), ?, 0, ?, ?, ?, NULL, ?, ?)
             "#,
-            DateTime::now(),
+            clock::now(),
             payload.title,
             payload.content,
             payload.object_id,
@@ -375,6 +378,7 @@ impl Threads {
             .save(&mut transaction)
             .await
             .map_err(|error| operation::Error::InternalServerError {
+                code: operation::ErrorCode::InternalServerError,
                 error: Box::new(error),
             })?;
 
@@ -390,6 +394,7 @@ impl Threads {
         let comment = Uuid::fetch_via_transaction(thread_id, &mut transaction)
             .await
             .map_err(|error| operation::Error::InternalServerError {
+                code: operation::ErrorCode::InternalServerError,
                 error: Box::new(error),
             })?;
 
@@ -407,6 +412,7 @@ impl Threads {
     {
         if payload.content.is_empty() {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::EmptyContent,
                 reason: "content is empty".to_string(),
             });
         }
@@ -425,6 +431,7 @@ impl Threads {
         .await
         .map_err(|error| match error {
             sqlx::Error::RowNotFound => operation::Error::BadRequest {
+                code: operation::ErrorCode::CommentNotFound,
                 reason: "no comment with given ID".to_string(),
             },
             error => error.into(),
@@ -432,18 +439,21 @@ impl Threads {
 
         if payload.user_id as i64 != comment.author_id {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::NotCommentAuthor,
                 reason: "given user is not author of the comment".to_string(),
             });
         }
 
         if comment.archived != 0 {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::CommentNotEditable,
                 reason: "archived comment cannot be edited".to_string(),
             });
         }
 
         if comment.trashed != 0 {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::CommentNotEditable,
                 reason: "trashed comment cannot be edited".to_string(),
             });
         }
@@ -456,13 +466,17 @@ impl Threads {
                     UPDATE comment SET content = ? WHERE id = ?
                 "#,
                 payload.content,
-                // DateTime::now(),
+                // clock::now(),
                 payload.comment_id,
             )
             .execute(&mut transaction)
             .await?;
         }
 
+        changes::record_affected_uuids(&[payload.comment_id as i32]);
+
+        cache().invalidate(&[payload.comment_id as i32]);
+
         transaction.commit().await?;
 
         Ok(operation::SuccessOutput { success: true })
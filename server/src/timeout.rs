@@ -0,0 +1,368 @@
+//! Timeouts of messages.
+//!
+//! Every message gets a time limit (see [`TimeoutConfig`]) which clients may shorten with the
+//! header `Timeout: <seconds>`. [`Operation::handle`]
+//! cancels the execution when the limit is exceeded and responds with the error code
+//! `MESSAGE_TIMEOUT`.
+//!
+//! Cancelling the future alone would leave the query running in MySQL and keep its connection
+//! busy. Therefore the pool remembers which connections a request acquires (see
+//! [`ConnectionWatcher`], which is registered in [`DatabaseConfig::pool_options`]). When a
+//! message exceeds its limit, its running queries are aborted with `KILL QUERY` on a separate
+//! connection before the future is dropped. In contrast to `max_execution_time` this aborts
+//! mutations as well.
+//!
+//! [`Operation::handle`]: crate::operation::Operation::handle
+//! [`DatabaseConfig::pool_options`]: crate::config::DatabaseConfig::pool_options
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::HttpRequest;
+use sqlx::mysql::MySqlConnectOptions;
+use sqlx::{Connection, Executor, MySqlConnection};
+
+use crate::config::TimeoutConfig;
+
+pub const HEADER: &str = "Timeout";
+
+struct RequestTimeouts {
+    config: Arc<TimeoutConfig>,
+    requested: Option<Duration>,
+}
+
+tokio::task_local! {
+    static REQUEST_TIMEOUTS: RequestTimeouts;
+    static ACQUISITIONS: Arc<Acquisitions>;
+}
+
+/// Error of a message which exceeded its time limit.
+#[derive(Debug)]
+pub struct Elapsed {
+    pub limit: Duration,
+}
+
+/// Returns the time limit a client requested with the header `Timeout`.
+pub fn requested_timeout(req: &HttpRequest) -> Option<Duration> {
+    req.headers()
+        .get(HEADER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|seconds| *seconds > 0.0)
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+}
+
+/// Runs `future` (the handling of a request) so that its messages are limited by `config` and
+/// the time limit the client requested.
+pub async fn scope<F: Future>(
+    config: Arc<TimeoutConfig>,
+    requested: Option<Duration>,
+    future: F,
+) -> F::Output {
+    REQUEST_TIMEOUTS
+        .scope(
+            RequestTimeouts { config, requested },
+            ACQUISITIONS.scope(Arc::default(), future),
+        )
+        .await
+}
+
+/// Returns the time limit of a message of type `message_type`. Outside of [`scope`] messages have
+/// no time limit.
+pub fn limit_for(message_type: &str) -> Option<Duration> {
+    REQUEST_TIMEOUTS
+        .try_with(|timeouts| timeouts.config.limit(message_type, timeouts.requested))
+        .ok()
+}
+
+/// Runs `future` and cancels it when it takes longer than `limit`. The queries still running on
+/// the connections acquired inside the current request (or inside `future` when it runs in a
+/// separate task) are aborted.
+pub async fn with_limit<F: Future>(limit: Duration, future: F) -> Result<F::Output, Elapsed> {
+    with_limit_excluding(limit, &ExcludedTime::default(), future).await
+}
+
+/// Like [`with_limit`], but the time `future` spends in [`ExcludedTime::exclude`] does not count
+/// towards `limit`.
+pub async fn with_limit_excluding<F: Future>(
+    limit: Duration,
+    excluded: &ExcludedTime,
+    future: F,
+) -> Result<F::Output, Elapsed> {
+    let start = Instant::now();
+    let acquisitions = ACQUISITIONS.try_with(Arc::clone).unwrap_or_default();
+    let future = ACQUISITIONS.scope(acquisitions.clone(), future);
+    futures::pin_mut!(future);
+
+    loop {
+        let remaining = (limit + excluded.elapsed()).saturating_sub(start.elapsed());
+
+        if remaining.is_zero() {
+            break;
+        }
+        if let Ok(output) = actix_rt::time::timeout(remaining, &mut future).await {
+            return Ok(output);
+        }
+    }
+
+    // Done before `future` is dropped, so that its connections are not returned to the pool yet.
+    acquisitions.kill_queries().await;
+    Err(Elapsed { limit })
+}
+
+/// Time which does not count towards the time limit of a message, e.g. while a streamed response
+/// waits for the client.
+#[derive(Clone, Debug, Default)]
+pub struct ExcludedTime(Arc<Mutex<ExcludedTimeState>>);
+
+#[derive(Debug, Default)]
+struct ExcludedTimeState {
+    total: Duration,
+    since: Option<Instant>,
+}
+
+impl ExcludedTime {
+    /// Runs `future` without counting its duration towards the time limit.
+    pub async fn exclude<F: Future>(&self, future: F) -> F::Output {
+        self.0.lock().unwrap().since = Some(Instant::now());
+        let output = future.await;
+        let mut state = self.0.lock().unwrap();
+        if let Some(since) = state.since.take() {
+            state.total += since.elapsed();
+        }
+        output
+    }
+
+    /// Returns the excluded time so far, including the time of a running [`Self::exclude`].
+    fn elapsed(&self) -> Duration {
+        let state = self.0.lock().unwrap();
+        state.total + state.since.map_or(Duration::ZERO, |since| since.elapsed())
+    }
+}
+
+/// Tracks which connections of a pool have been acquired by which request, so that the queries
+/// of a message exceeding its time limit can be aborted.
+pub struct ConnectionWatcher {
+    /// Options to open the separate connection sending `KILL QUERY`.
+    options: MySqlConnectOptions,
+    state: Mutex<WatcherState>,
+    acquisition_count: AtomicU64,
+}
+
+#[derive(Default)]
+struct WatcherState {
+    /// Number of the latest acquisition of each connection (by `CONNECTION_ID()`).
+    latest_acquisitions: HashMap<u64, u64>,
+    /// Connections whose queries are being killed.
+    killing: HashSet<u64>,
+}
+
+impl ConnectionWatcher {
+    pub fn new(options: MySqlConnectOptions) -> Arc<Self> {
+        Arc::new(Self {
+            options,
+            state: Default::default(),
+            acquisition_count: AtomicU64::new(0),
+        })
+    }
+
+    /// Remembers that the current request acquired `connection`. Outside of a request nothing is
+    /// remembered. Returns `false` when the queries of the connection are being killed, so that
+    /// the pool closes it instead of handing it out.
+    ///
+    /// sqlx cannot attach data to a connection, so its id is looked up with every acquisition.
+    /// The lookup replaces the ping of `test_before_acquire` (see
+    /// [`DatabaseConfig::pool_options`](crate::config::DatabaseConfig::pool_options)).
+    pub async fn watch(
+        self: &Arc<Self>,
+        connection: &mut MySqlConnection,
+    ) -> Result<bool, sqlx::Error> {
+        let connection_id: u64 = sqlx::query_scalar("SELECT CONNECTION_ID()")
+            .fetch_one(connection)
+            .await?;
+
+        let Ok(acquisitions) = ACQUISITIONS.try_with(Arc::clone) else {
+            return Ok(true);
+        };
+        let number = self.acquisition_count.fetch_add(1, Ordering::Relaxed);
+
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.killing.contains(&connection_id) {
+                return Ok(false);
+            }
+            state.latest_acquisitions.insert(connection_id, number);
+        }
+        acquisitions.0.lock().unwrap().push(Acquisition {
+            watcher: self.clone(),
+            connection_id,
+            number,
+        });
+
+        Ok(true)
+    }
+
+    async fn kill_query(&self, connection_id: u64) -> Result<(), sqlx::Error> {
+        let mut connection = MySqlConnection::connect_with(&self.options).await?;
+        connection
+            .execute(format!("KILL QUERY {connection_id}").as_str())
+            .await?;
+        connection.close().await
+    }
+}
+
+#[derive(Clone)]
+struct Acquisition {
+    watcher: Arc<ConnectionWatcher>,
+    connection_id: u64,
+    number: u64,
+}
+
+/// Connections acquired inside a request.
+#[derive(Default)]
+struct Acquisitions(Mutex<Vec<Acquisition>>);
+
+impl Acquisitions {
+    /// Aborts the queries of all connections which have not been acquired by another request
+    /// since. Queries of idle connections are not affected.
+    async fn kill_queries(&self) {
+        let acquisitions = self.0.lock().unwrap().clone();
+
+        // Marked as being killed, so that the connections are not handed out to another request
+        // until their queries are killed.
+        let killed: Vec<_> = acquisitions
+            .into_iter()
+            .filter(|acquisition| {
+                let mut state = acquisition.watcher.state.lock().unwrap();
+                let latest = state.latest_acquisitions.get(&acquisition.connection_id);
+                latest == Some(&acquisition.number)
+                    && state.killing.insert(acquisition.connection_id)
+            })
+            .collect();
+
+        for acquisition in killed {
+            if let Err(error) = acquisition
+                .watcher
+                .kill_query(acquisition.connection_id)
+                .await
+            {
+                tracing::warn!(%error, "could not kill query of message exceeding its limit");
+            }
+            acquisition
+                .watcher
+                .state
+                .lock()
+                .unwrap()
+                .killing
+                .remove(&acquisition.connection_id);
+        }
+    }
+}
+
+impl Drop for Acquisitions {
+    /// Forgets the acquisitions of the finished request.
+    fn drop(&mut self) {
+        for acquisition in self.0.get_mut().unwrap().drain(..) {
+            let mut state = acquisition.watcher.state.lock().unwrap();
+
+            if state.latest_acquisitions.get(&acquisition.connection_id)
+                == Some(&acquisition.number)
+            {
+                state.latest_acquisitions.remove(&acquisition.connection_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use actix_web::test::TestRequest;
+
+    use super::{
+        limit_for, requested_timeout, scope, with_limit, with_limit_excluding, ExcludedTime, HEADER,
+    };
+    use crate::config::TimeoutConfig;
+
+    #[test]
+    fn parses_requested_timeout() {
+        let requested = |value: &str| {
+            requested_timeout(
+                &TestRequest::default()
+                    .insert_header((HEADER, value))
+                    .to_http_request(),
+            )
+        };
+
+        assert_eq!(requested("5"), Some(Duration::from_secs(5)));
+        assert_eq!(requested(" 0.5 "), Some(Duration::from_millis(500)));
+        assert_eq!(requested("0"), None);
+        assert_eq!(requested("-1"), None);
+        assert_eq!(requested("inf"), None);
+        assert_eq!(requested("NaN"), None);
+        assert_eq!(requested("1e30"), None);
+        assert_eq!(requested("soon"), None);
+    }
+
+    #[actix_rt::test]
+    async fn limits_messages_inside_scope() {
+        let config = TimeoutConfig {
+            default_seconds: 60,
+            max_seconds: 120,
+            messages: [("AllThreadsQuery".to_string(), 10)].into(),
+        };
+
+        assert_eq!(limit_for("UuidQuery"), None);
+
+        let config = Arc::new(config);
+
+        scope(config.clone(), None, async {
+            assert_eq!(limit_for("UuidQuery"), Some(Duration::from_secs(60)));
+            assert_eq!(limit_for("AllThreadsQuery"), Some(Duration::from_secs(10)));
+        })
+        .await;
+
+        scope(config.clone(), Some(Duration::from_secs(300)), async {
+            assert_eq!(limit_for("UuidQuery"), Some(Duration::from_secs(60)));
+            assert_eq!(limit_for("AllThreadsQuery"), Some(Duration::from_secs(10)));
+        })
+        .await;
+
+        scope(config, Some(Duration::from_secs(5)), async {
+            assert_eq!(limit_for("UuidQuery"), Some(Duration::from_secs(5)));
+            assert_eq!(limit_for("AllThreadsQuery"), Some(Duration::from_secs(5)));
+        })
+        .await;
+    }
+
+    #[actix_rt::test]
+    async fn cancels_future_after_limit() {
+        let result = with_limit(Duration::from_millis(10), async {
+            actix_rt::time::sleep(Duration::from_secs(10)).await;
+        })
+        .await;
+
+        assert!(result.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn does_not_count_excluded_time_towards_limit() {
+        let excluded = ExcludedTime::default();
+        let result = with_limit_excluding(Duration::from_millis(50), &excluded, async {
+            excluded
+                .exclude(actix_rt::time::sleep(Duration::from_millis(100)))
+                .await;
+            actix_rt::time::sleep(Duration::from_millis(10)).await;
+        })
+        .await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -1,11 +1,14 @@
 use actix_web::HttpResponse;
 use async_trait::async_trait;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::model::User;
 use crate::database::Connection;
 use crate::message::MessageResponder;
 use crate::operation::{self, Operation};
+use crate::schema::{MessageSchema, MessageSchemas};
 
 #[derive(Deserialize, Serialize)]
 #[serde(tag = "type", content = "payload")]
@@ -85,11 +88,58 @@ impl MessageResponder for UserMessage {
     }
 }
 
+impl MessageSchemas for UserMessage {
+    fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema> {
+        vec![
+            MessageSchema::new::<active_authors_query::Payload>("ActiveAuthorsQuery", generator),
+            MessageSchema::new::<active_reviewers_query::Payload>(
+                "ActiveReviewersQuery",
+                generator,
+            ),
+            MessageSchema::new::<user_activity_by_type_query::Payload>(
+                "ActivityByTypeQuery",
+                generator,
+            ),
+            MessageSchema::new::<user_activity_by_type_query::Payload>(
+                "UserActivityByTypeQuery",
+                generator,
+            ),
+            MessageSchema::new::<user_add_role_mutation::Payload>("UserAddRoleMutation", generator),
+            MessageSchema::new::<user_create_mutation::Payload>("UserCreateMutation", generator),
+            MessageSchema::new::<user_delete_bots_mutation::Payload>(
+                "UserDeleteBotsMutation",
+                generator,
+            ),
+            MessageSchema::new::<user_delete_regular_users_mutation::Payload>(
+                "UserDeleteRegularUsersMutation",
+                generator,
+            ),
+            MessageSchema::new::<potential_spam_users_query::Payload>(
+                "UserPotentialSpamUsersQuery",
+                generator,
+            ),
+            MessageSchema::new::<user_remove_role_mutation::Payload>(
+                "UserRemoveRoleMutation",
+                generator,
+            ),
+            MessageSchema::new::<users_by_role_query::Payload>("UsersByRoleQuery", generator),
+            MessageSchema::new::<user_set_description_mutation::Payload>(
+                "UserSetDescriptionMutation",
+                generator,
+            ),
+            MessageSchema::new::<user_set_email_mutation::Payload>(
+                "UserSetEmailMutation",
+                generator,
+            ),
+        ]
+    }
+}
+
 pub mod active_authors_query {
     use super::*;
 
-    #[derive(Debug, Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {}
 
     #[async_trait]
@@ -110,8 +160,8 @@ pub mod active_authors_query {
 pub mod active_reviewers_query {
     use super::*;
 
-    #[derive(Debug, Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {}
 
     #[async_trait]
@@ -132,13 +182,13 @@ pub mod active_reviewers_query {
 pub mod user_activity_by_type_query {
     use super::*;
 
-    #[derive(Debug, Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         user_id: i32,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
     pub struct Output {
         pub edits: i32,
         pub reviews: i32,
@@ -164,14 +214,14 @@ pub mod user_activity_by_type_query {
 pub mod user_add_role_mutation {
     use super::*;
 
-    #[derive(Debug, Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub username: String,
         pub role_name: String,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub success: bool,
@@ -194,15 +244,15 @@ pub mod user_add_role_mutation {
 pub mod user_create_mutation {
     use super::*;
 
-    #[derive(Debug, Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub username: String,
         pub email: String,
         pub password: String,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub success: bool,
@@ -229,13 +279,13 @@ pub mod user_create_mutation {
 pub mod user_delete_bots_mutation {
     use super::*;
 
-    #[derive(Debug, Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub bot_ids: Vec<i32>,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub success: bool,
@@ -262,13 +312,13 @@ pub mod user_delete_bots_mutation {
 pub mod user_delete_regular_users_mutation {
     use super::*;
 
-    #[derive(Debug, Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub user_id: i32,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub success: bool,
@@ -294,14 +344,14 @@ pub mod user_delete_regular_users_mutation {
 pub mod potential_spam_users_query {
     use super::*;
 
-    #[derive(Debug, Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub first: i32,
         pub after: Option<i32>,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub user_ids: Vec<i32>,
@@ -314,6 +364,7 @@ pub mod potential_spam_users_query {
         async fn execute(&self, connection: Connection<'_, '_>) -> operation::Result<Self::Output> {
             if self.first > 10_000 {
                 return Err(operation::Error::BadRequest {
+                    code: operation::ErrorCode::InvalidParameter,
                     reason: "parameter `first` is too high".to_string(),
                 });
             };
@@ -332,14 +383,14 @@ pub mod potential_spam_users_query {
 pub mod user_remove_role_mutation {
     use super::*;
 
-    #[derive(Debug, Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub username: String,
         pub role_name: String,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub success: bool,
@@ -364,15 +415,15 @@ pub mod user_remove_role_mutation {
 pub mod users_by_role_query {
     use super::*;
 
-    #[derive(Debug, Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub role_name: String,
         pub first: i32,
         pub after: Option<i32>,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub users_by_role: Vec<i32>,
@@ -398,14 +449,14 @@ pub mod users_by_role_query {
 pub mod user_set_description_mutation {
     use super::*;
 
-    #[derive(Debug, Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub user_id: i32,
         pub description: String,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub success: bool,
@@ -430,14 +481,14 @@ pub mod user_set_description_mutation {
 pub mod user_set_email_mutation {
     use super::*;
 
-    #[derive(Debug, Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub user_id: i32,
         pub email: String,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub success: bool,
@@ -1,3 +1,6 @@
+use crate::cache::cache;
+use crate::changes;
+use crate::clock;
 use crate::database::Executor;
 use crate::datetime::DateTime;
 use crate::operation;
@@ -122,6 +125,7 @@ impl User {
         .fetch_optional(&mut transaction)
         .await?
         .ok_or(operation::Error::BadRequest {
+            code: operation::ErrorCode::UserNotFound,
             reason: "This user does not exist.".to_string(),
         })?
         .id;
@@ -152,6 +156,8 @@ impl User {
         )
         .execute(&mut transaction)
         .await?;
+        changes::record_affected_uuids(&[user_id as i32]);
+        cache().invalidate(&[user_id as i32]);
         transaction.commit().await?;
         Ok(())
     }
@@ -167,24 +173,28 @@ impl User {
 
         if payload.username.len() > 32 {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::InvalidParameter,
                 reason: "Username can\'t be longer than 32 characters.".to_string(),
             });
         }
 
         if payload.email.len() > 254 {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::InvalidParameter,
                 reason: "Email can\'t be longer than 254 characters.".to_string(),
             });
         }
 
         if payload.username.trim().is_empty() {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::InvalidParameter,
                 reason: "Username can\'t be empty.".to_string(),
             });
         }
 
         if payload.password.len() > 50 {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::InvalidParameter,
                 reason: "Password can\'t be longer than 50 characters.".to_string(),
             });
         }
@@ -225,7 +235,7 @@ impl User {
             payload.email,
             payload.username,
             payload.password,
-            DateTime::now(),
+            clock::now(),
             token.to_lowercase(),
         )
         .execute(&mut transaction)
@@ -274,6 +284,10 @@ impl User {
             .await?;
         }
 
+        changes::record_affected_uuids(&payload.bot_ids);
+
+        cache().invalidate(&payload.bot_ids);
+
         transaction.commit().await?;
 
         Ok(email_hashes)
@@ -290,6 +304,7 @@ impl User {
 
         if payload.user_id == deleted_user_id {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::UserNotDeletable,
                 reason: "You cannot delete the user Deleted.".to_string(),
             });
         }
@@ -300,9 +315,29 @@ impl User {
             .fetch_optional(&mut transaction)
             .await?
             .ok_or(operation::Error::BadRequest {
+                code: operation::ErrorCode::UserNotFound,
                 reason: "The requested user does not exist.".to_string(),
             })?;
 
+        // Uuids whose author is replaced by the user Deleted
+        let reassigned_ids: Vec<i32> = sqlx::query_scalar(
+            r#"
+                SELECT id FROM blog_post WHERE author_id = ?
+                UNION ALL
+                SELECT id FROM comment WHERE author_id = ?
+                UNION ALL
+                SELECT id FROM entity_revision WHERE author_id = ?
+                UNION ALL
+                SELECT id FROM page_revision WHERE author_id = ?
+            "#,
+        )
+        .bind(payload.user_id)
+        .bind(payload.user_id)
+        .bind(payload.user_id)
+        .bind(payload.user_id)
+        .fetch_all(&mut transaction)
+        .await?;
+
         sqlx::query!(
             r#"update ad set author_id = ? where author_id = ?"#,
             deleted_user_id,
@@ -397,6 +432,11 @@ impl User {
         .execute(&mut transaction)
         .await?;
 
+        changes::record_affected_uuids(&[payload.user_id]);
+        changes::record_affected_uuids(&reassigned_ids);
+        // All uuids authored by the user have changed
+        cache().invalidate_all();
+
         transaction.commit().await?;
 
         Ok(())
@@ -466,6 +506,7 @@ impl User {
         .fetch_optional(&mut transaction)
         .await?
         .ok_or(operation::Error::BadRequest {
+            code: operation::ErrorCode::UserNotFound,
             reason: "This user does not exist.".to_string(),
         })?
         .id;
@@ -481,6 +522,10 @@ impl User {
         .execute(&mut transaction)
         .await?;
 
+        changes::record_affected_uuids(&[user_id as i32]);
+
+        cache().invalidate(&[user_id as i32]);
+
         transaction.commit().await?;
         Ok(())
     }
@@ -494,6 +539,7 @@ impl User {
     {
         if payload.first > 10000 {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::InvalidParameter,
                 reason: "The parameter first is not allowed to be greater than 10,000.".to_string(),
             });
         }
@@ -529,6 +575,7 @@ impl User {
     {
         if payload.description.len() >= 64 * 1024 {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::InvalidParameter,
                 reason: "description is too long".to_string(),
             });
         }
@@ -540,6 +587,8 @@ impl User {
         )
         .execute(executor)
         .await?;
+        changes::record_affected_uuids(&[payload.user_id]);
+        cache().invalidate(&[payload.user_id]);
         Ok(())
     }
 
@@ -563,6 +612,8 @@ impl User {
         )
         .execute(&mut transaction)
         .await?;
+        changes::record_affected_uuids(&[payload.user_id]);
+        cache().invalidate(&[payload.user_id]);
         transaction.commit().await?;
         Ok(username)
     }
@@ -582,18 +633,19 @@ impl User {
         .fetch_optional(transaction)
         .await?
         .ok_or(operation::Error::BadRequest {
+            code: operation::ErrorCode::RoleNotFound,
             reason: "This role does not exist.".to_string(),
         })?
         .id)
     }
 
     fn now() -> DateTime {
-        // In the development database there are no recent edits so we use an old timestamp.
-        // In production, we use the current time.
+        // In the development database there are no recent edits so we use an old timestamp
+        // (unless a test has set the clock). In production, we use the current time.
         let environment = env::var("ENV").unwrap();
         match environment.as_str() {
-            "development" => DateTime::ymd(2014, 1, 1),
-            _ => DateTime::now(),
+            "development" if !clock::is_mocked() => DateTime::ymd(2014, 1, 1),
+            _ => clock::now(),
         }
     }
 }
@@ -1,16 +1,23 @@
+use std::collections::HashMap;
+
+use crate::cache::{CacheKey, Dependency};
 use crate::operation::{self, Operation};
 use actix_web::HttpResponse;
 use async_trait::async_trait;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use super::model::{Uuid, UuidFetcher};
+use super::model::{ConcreteUuid, Uuid, UuidFetcher};
 use crate::database::Connection;
 use crate::message::MessageResponder;
+use crate::schema::{MessageSchema, MessageSchemas};
 
 #[derive(Deserialize, Serialize)]
 #[serde(tag = "type", content = "payload")]
 pub enum UuidMessage {
     UuidQuery(uuid_query::Payload),
+    UuidsQuery(uuids_query::Payload),
     UuidSetStateMutation(uuid_set_state_mutation::Payload),
 }
 
@@ -20,6 +27,7 @@ impl MessageResponder for UuidMessage {
     async fn handle(&self, connection: Connection<'_, '_>) -> HttpResponse {
         match self {
             UuidMessage::UuidQuery(message) => message.handle("UuidQuery", connection).await,
+            UuidMessage::UuidsQuery(message) => message.handle("UuidsQuery", connection).await,
             UuidMessage::UuidSetStateMutation(message) => {
                 message.handle("UuidSetStateMutation", connection).await
             }
@@ -27,11 +35,24 @@ impl MessageResponder for UuidMessage {
     }
 }
 
+impl MessageSchemas for UuidMessage {
+    fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema> {
+        vec![
+            MessageSchema::new::<uuid_query::Payload>("UuidQuery", generator),
+            MessageSchema::new::<uuids_query::Payload>("UuidsQuery", generator),
+            MessageSchema::new::<uuid_set_state_mutation::Payload>(
+                "UuidSetStateMutation",
+                generator,
+            ),
+        ]
+    }
+}
+
 pub mod uuid_query {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub id: i32,
     }
@@ -40,6 +61,22 @@ pub mod uuid_query {
     impl Operation for Payload {
         type Output = Uuid;
 
+        fn cache_key(&self) -> Option<CacheKey> {
+            Some(CacheKey::Uuid(self.id))
+        }
+
+        fn cache_dependencies(&self, output: &Self::Output) -> Vec<Dependency> {
+            match output.concrete_uuid {
+                // Their alias and canonical subject are derived from the taxonomy.
+                ConcreteUuid::Entity(_)
+                | ConcreteUuid::EntityRevision(_)
+                | ConcreteUuid::TaxonomyTerm(_) => {
+                    vec![Dependency::Uuid(self.id), Dependency::Taxonomy]
+                }
+                _ => vec![Dependency::Uuid(self.id)],
+            }
+        }
+
         async fn execute(&self, connection: Connection<'_, '_>) -> operation::Result<Self::Output> {
             Ok(match connection {
                 Connection::Pool(pool) => Uuid::fetch(self.id, pool).await?,
@@ -51,18 +88,49 @@ pub mod uuid_query {
     }
 }
 
+pub mod uuids_query {
+    use super::*;
+
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
+    pub struct Payload {
+        pub ids: Vec<i32>,
+    }
+
+    #[async_trait]
+    impl Operation for Payload {
+        type Output = HashMap<i32, Option<Uuid>>;
+
+        async fn execute(&self, connection: Connection<'_, '_>) -> operation::Result<Self::Output> {
+            if self.ids.len() > 1_000 {
+                return Err(operation::Error::BadRequest {
+                    code: operation::ErrorCode::InvalidParameter,
+                    reason: "parameter `ids` must not contain more than 1000 ids".to_string(),
+                });
+            }
+
+            Ok(match connection {
+                Connection::Pool(pool) => Uuid::fetch_many(&self.ids, pool).await?,
+                Connection::Transaction(transaction) => {
+                    Uuid::fetch_many(&self.ids, transaction).await?
+                }
+            })
+        }
+    }
+}
+
 pub mod uuid_set_state_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub ids: Vec<i32>,
         pub user_id: i32,
         pub trashed: bool,
     }
 
-    #[derive(Deserialize, Serialize)]
+    #[derive(Deserialize, Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         success: bool,
@@ -83,56 +151,3 @@ pub mod uuid_set_state_mutation {
         }
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use crate::create_database_pool;
-    use crate::uuid::model::{Uuid, UuidError, UuidFetcher};
-
-    #[actix_rt::test]
-    async fn solution_is_null_for_math_puzzle() {
-        let pool = create_database_pool().await.unwrap();
-        let mut transaction = pool.begin().await.unwrap();
-
-        // Add solution with id 100000 and math-puzzle with id 100001
-        sqlx::query(
-            r#"
-                INSERT INTO uuid (id, trashed, discriminator)
-                VALUES (100000, 0, 'entity'),
-                       (100001, 0, 'entity')
-            "#,
-        )
-        .execute(&mut transaction)
-        .await
-        .unwrap();
-        sqlx::query(
-            r#"
-                INSERT INTO entity (id, type_id, instance_id, license_id, date, current_revision_id)
-                VALUES (100000, 2, 1, 1, CURDATE(), 1),
-                       (100001, 39, 1, 1, CURDATE(), 2)
-            "#,
-        )
-        .execute(&mut transaction)
-        .await
-        .unwrap();
-
-        // Link solution and math-puzzle
-        sqlx::query(
-            r#"
-                INSERT INTO entity_link (parent_id, child_id, type_id)
-                VALUES (100001, 100000, 9)
-            "#,
-        )
-        .execute(&mut transaction)
-        .await
-        .unwrap();
-
-        let result = Uuid::fetch_via_transaction(100000, &mut transaction).await;
-
-        assert!(result.is_err());
-        match result.as_ref().err().unwrap() {
-            UuidError::EntityMissingRequiredParent => (),
-            _ => panic!("Error does not fulfill assertions {:?}", result.err()),
-        }
-    }
-}
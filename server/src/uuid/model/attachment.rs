@@ -1,8 +1,10 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use sqlx::MySqlPool;
 
 use super::{ConcreteUuid, Uuid, UuidError, UuidFetcher};
-use crate::database::Executor;
+use crate::database::{placeholders, Executor};
 use crate::format_alias;
 
 pub struct Attachment {}
@@ -47,6 +49,38 @@ impl UuidFetcher for Attachment {
 }
 
 impl Attachment {
+    pub async fn fetch_many<'a, E>(ids: &[i32], executor: E) -> Result<Vec<Uuid>, UuidError>
+    where
+        E: Executor<'a>,
+    {
+        let query = format!(
+            r#"
+                SELECT c.id, u.trashed, f.name
+                    FROM attachment_file f
+                    JOIN attachment_container c ON c.id = f.attachment_id
+                    JOIN uuid u ON u.id = c.id
+                    WHERE c.id IN ({})
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, (i32, i8, String)>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+
+        let mut attachments = HashMap::new();
+        for (id, trashed, name) in query.fetch_all(executor).await? {
+            attachments.entry(id).or_insert_with(|| Uuid {
+                id,
+                trashed: trashed != 0,
+                alias: format_alias(Self::get_context().as_deref(), id, Some(name.as_str())),
+                concrete_uuid: ConcreteUuid::Attachment,
+            });
+        }
+
+        Ok(attachments.into_values().collect())
+    }
+
     pub fn get_context() -> Option<String> {
         Some("attachment".to_string())
     }
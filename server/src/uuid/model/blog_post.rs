@@ -2,7 +2,7 @@ use async_trait::async_trait;
 use sqlx::MySqlPool;
 
 use super::{ConcreteUuid, Uuid, UuidError, UuidFetcher};
-use crate::database::Executor;
+use crate::database::{placeholders, Executor};
 use crate::format_alias;
 
 pub struct BlogPost {}
@@ -46,6 +46,37 @@ impl UuidFetcher for BlogPost {
 }
 
 impl BlogPost {
+    pub async fn fetch_many<'a, E>(ids: &[i32], executor: E) -> Result<Vec<Uuid>, UuidError>
+    where
+        E: Executor<'a>,
+    {
+        let query = format!(
+            r#"
+                SELECT b.id, u.trashed, b.title
+                    FROM blog_post b
+                    JOIN uuid u ON u.id = b.id
+                    WHERE b.id IN ({})
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, (i32, i8, String)>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+
+        Ok(query
+            .fetch_all(executor)
+            .await?
+            .into_iter()
+            .map(|(id, trashed, title)| Uuid {
+                id,
+                trashed: trashed != 0,
+                alias: format_alias(Self::get_context().as_deref(), id, Some(title.as_str())),
+                concrete_uuid: ConcreteUuid::BlogPost,
+            })
+            .collect())
+    }
+
     pub fn get_context() -> Option<String> {
         Some("blog".to_string())
     }
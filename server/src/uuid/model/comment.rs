@@ -1,14 +1,19 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
+use chrono::Utc;
 use futures::join;
+use schemars::JsonSchema;
 use serde::Serialize;
 use sqlx::MySqlPool;
 
+use super::uuid::skip_unfetchable;
 use super::{ConcreteUuid, Uuid, UuidError, UuidFetcher};
-use crate::database::Executor;
+use crate::database::{placeholders, Executor};
 use crate::datetime::DateTime;
 use crate::format_alias;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Comment {
     #[serde(rename(serialize = "__typename"))]
@@ -117,7 +122,104 @@ impl UuidFetcher for Comment {
     }
 }
 
+#[derive(sqlx::FromRow)]
+struct CommentRow {
+    id: i32,
+    trashed: i8,
+    author_id: i32,
+    title: Option<String>,
+    date: chrono::DateTime<Utc>,
+    archived: i8,
+    content: Option<String>,
+    parent_id: Option<i32>,
+    uuid_id: Option<i32>,
+    parent_title: Option<String>,
+    object_id: Option<i32>,
+}
+
+#[derive(sqlx::FromRow)]
+struct CommentChildRow {
+    id: i32,
+    parent_id: i32,
+}
+
 impl Comment {
+    pub async fn fetch_many<'a, E>(ids: &[i32], executor: E) -> Result<Vec<Uuid>, UuidError>
+    where
+        E: Executor<'a>,
+    {
+        let mut transaction = executor.begin().await?;
+
+        let query = format!(
+            r#"
+                SELECT c.id, u.trashed, c.author_id, c.title, c.date, c.archived, c.content, c.parent_id, c.uuid_id, p.title as parent_title, COALESCE(c.uuid_id, p.uuid_id) as object_id
+                    FROM comment c
+                    LEFT JOIN comment p ON p.id = c.parent_id
+                    JOIN uuid u ON u.id = c.id
+                    WHERE c.id IN ({})
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, CommentRow>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+        let comments = query.fetch_all(&mut transaction).await?;
+
+        let query = format!(
+            r#"
+                SELECT id, parent_id
+                    FROM comment
+                    WHERE parent_id IN ({})
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, CommentChildRow>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+        let mut children: HashMap<i32, Vec<CommentChildRow>> = HashMap::new();
+        for child in query.fetch_all(&mut transaction).await? {
+            children.entry(child.parent_id).or_default().push(child);
+        }
+
+        let object_ids: Vec<i32> = comments
+            .iter()
+            .filter_map(|comment| comment.object_id)
+            .collect();
+        let contexts = Uuid::fetch_contexts(&object_ids, &mut transaction).await?;
+
+        transaction.commit().await?;
+
+        let mut uuids = Vec::with_capacity(comments.len());
+        for comment in comments {
+            let children = children.remove(&comment.id).unwrap_or_default();
+            // Like in `Comment::fetch_context()` a comment without object is not found.
+            let context = comment
+                .object_id
+                .and_then(|object_id| contexts.get(&object_id).cloned())
+                .ok_or(UuidError::NotFound);
+            let id = comment.id;
+            if let Some(uuid) = skip_unfetchable(id, Self::from_rows(comment, children, context))? {
+                uuids.push(uuid);
+            }
+        }
+        Ok(uuids)
+    }
+
+    fn from_rows(
+        comment: CommentRow,
+        children: Vec<CommentChildRow>,
+        context: Result<Option<String>, UuidError>,
+    ) -> Result<Uuid, UuidError> {
+        let id = comment.id;
+        to_comment!(
+            id,
+            Ok::<_, sqlx::Error>(comment),
+            Ok::<_, UuidError>(children),
+            context
+        )
+    }
     pub async fn fetch_context(id: i32, pool: &MySqlPool) -> Result<Option<String>, UuidError> {
         let object = sqlx::query!(
             r#"
@@ -1,10 +1,11 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::database::HasArguments;
 use sqlx::encode::IsNull;
 use sqlx::mysql::MySqlTypeInfo;
 use sqlx::MySql;
 
-#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum Discriminator {
     Attachment,
@@ -18,6 +19,29 @@ pub enum Discriminator {
     User,
 }
 
+impl Discriminator {
+    pub const ALL: [Discriminator; 9] = [
+        Discriminator::Attachment,
+        Discriminator::BlogPost,
+        Discriminator::Comment,
+        Discriminator::Entity,
+        Discriminator::EntityRevision,
+        Discriminator::Page,
+        Discriminator::PageRevision,
+        Discriminator::TaxonomyTerm,
+        Discriminator::User,
+    ];
+
+    /// Returns the name which is stored in the column `discriminator` of `uuid`.
+    pub fn name(&self) -> String {
+        serde_json::to_value(self)
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+}
+
 impl std::str::FromStr for Discriminator {
     type Err = serde_json::Error;
 
@@ -33,9 +57,28 @@ impl sqlx::Type<MySql> for Discriminator {
 }
 impl<'q> sqlx::Encode<'q, MySql> for Discriminator {
     fn encode_by_ref(&self, buf: &mut <MySql as HasArguments<'q>>::ArgumentBuffer) -> IsNull {
-        <&str as sqlx::Encode<'_, MySql>>::encode_by_ref(
-            &serde_json::to_value(self).unwrap().as_str().unwrap(),
-            buf,
-        )
+        <&str as sqlx::Encode<'_, MySql>>::encode_by_ref(&self.name().as_str(), buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::schema_for;
+    use std::collections::BTreeSet;
+
+    use super::Discriminator;
+
+    #[test]
+    fn all_contains_every_variant() {
+        let schema = serde_json::to_value(schema_for!(Discriminator)).unwrap();
+        let variants: BTreeSet<_> = schema["enum"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|name| name.as_str().unwrap().to_string())
+            .collect();
+        let names: BTreeSet<_> = Discriminator::ALL.iter().map(Discriminator::name).collect();
+
+        assert_eq!(names, variants);
     }
 }
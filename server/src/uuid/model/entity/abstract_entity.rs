@@ -1,10 +1,11 @@
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use super::entity_type::EntityType;
 use crate::datetime::DateTime;
 use crate::instance::Instance;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AbstractEntity {
     #[serde(rename(serialize = "__typename"))]
@@ -1,3 +1,4 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::encode::IsNull;
 use sqlx::mysql::MySqlTypeInfo;
@@ -5,7 +6,7 @@ use sqlx::MySql;
 
 use super::UuidError;
 
-#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 enum RawEntityType {
     Applet,
@@ -24,7 +25,7 @@ enum RawEntityType {
     Video,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub enum EntityType {
     Applet,
     Article,
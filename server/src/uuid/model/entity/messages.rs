@@ -1,5 +1,7 @@
 use actix_web::HttpResponse;
 use async_trait::async_trait;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::Entity;
@@ -7,6 +9,7 @@ use crate::database::Connection;
 use crate::instance::Instance;
 use crate::message::MessageResponder;
 use crate::operation::{self, Operation, SuccessOutput};
+use crate::schema::{MessageSchema, MessageSchemas};
 use crate::uuid::abstract_entity_revision::EntityRevisionType;
 use crate::uuid::{EntityType, Uuid};
 use std::collections::HashMap;
@@ -63,11 +66,48 @@ impl MessageResponder for EntityMessage {
     }
 }
 
+impl MessageSchemas for EntityMessage {
+    fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema> {
+        vec![
+            MessageSchema::new::<entity_add_revision_mutation::Payload>(
+                "EntityAddRevisionMutation",
+                generator,
+            ),
+            MessageSchema::new::<checkout_revision_mutation::Payload>(
+                "EntityCheckoutRevisionMutation",
+                generator,
+            ),
+            MessageSchema::new::<entity_create_mutation::Payload>(
+                "EntityCreateMutation",
+                generator,
+            ),
+            MessageSchema::new::<reject_revision_mutation::Payload>(
+                "EntityRejectRevisionMutation",
+                generator,
+            ),
+            MessageSchema::new::<unrevised_entities_query::Payload>(
+                "UnrevisedEntitiesQuery",
+                generator,
+            ),
+            MessageSchema::new::<deleted_entities_query::Payload>(
+                "DeletedEntitiesQuery",
+                generator,
+            ),
+            MessageSchema::new::<entity_set_license_mutation::Payload>(
+                "EntitySetLicenseMutation",
+                generator,
+            ),
+            MessageSchema::new::<entity_sort_mutation::Payload>("EntitySortMutation", generator),
+        ]
+    }
+}
+
 pub mod entity_add_revision_mutation {
     use super::*;
 
-    #[derive(Debug, Serialize, Deserialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
+    #[schemars(rename = "EntityAddRevisionInput")]
     pub struct Input {
         pub changes: String,
         pub entity_id: i32,
@@ -77,15 +117,15 @@ pub mod entity_add_revision_mutation {
         pub fields: HashMap<String, String>,
     }
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub input: Input,
         pub revision_type: EntityRevisionType,
         pub user_id: i32,
     }
 
-    #[derive(Deserialize, Serialize)]
+    #[derive(Deserialize, Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub success: bool,
@@ -117,8 +157,8 @@ pub mod entity_add_revision_mutation {
 pub mod checkout_revision_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub revision_id: i32,
         pub user_id: i32,
@@ -144,8 +184,9 @@ pub mod checkout_revision_mutation {
 pub mod entity_create_mutation {
     use super::*;
 
-    #[derive(Debug, Serialize, Deserialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
+    #[schemars(rename = "EntityCreateInput")]
     pub struct Input {
         pub changes: String,
         pub license_id: i32,
@@ -157,8 +198,8 @@ pub mod entity_create_mutation {
         pub taxonomy_term_id: Option<i32>,
     }
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub input: Input,
         pub entity_type: EntityType,
@@ -181,8 +222,8 @@ pub mod entity_create_mutation {
 pub mod reject_revision_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub revision_id: i32,
         pub user_id: i32,
@@ -208,11 +249,11 @@ pub mod reject_revision_mutation {
 pub mod unrevised_entities_query {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {}
 
-    #[derive(Debug, Deserialize, Serialize)]
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub unrevised_entity_ids: Vec<i32>,
@@ -238,22 +279,22 @@ pub mod unrevised_entities_query {
 pub mod deleted_entities_query {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub first: i32,
         pub after: Option<String>,
         pub instance: Option<Instance>,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct DeletedEntity {
         pub date_of_deletion: String,
         pub id: i32,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         success: bool,
@@ -282,15 +323,15 @@ pub mod deleted_entities_query {
 pub mod entity_set_license_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub entity_id: i32,
         pub license_id: i32,
         pub user_id: i32,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         success: bool,
@@ -315,8 +356,8 @@ pub mod entity_set_license_mutation {
 pub mod entity_sort_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub children_ids: Vec<i32>,
         pub entity_id: i32,
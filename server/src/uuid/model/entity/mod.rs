@@ -1,7 +1,8 @@
 use crate::uuid::Subject;
 use async_trait::async_trait;
-use convert_case::{Case, Casing};
+use chrono::Utc;
 use futures::try_join;
+use schemars::JsonSchema;
 use serde::Serialize;
 use sqlx::MySqlPool;
 use sqlx::Row;
@@ -10,19 +11,23 @@ use std::collections::{HashMap, HashSet};
 use abstract_entity::AbstractEntity;
 pub use entity_type::EntityType;
 
-use super::entity_revision::abstract_entity_revision::EntityRevisionPayload;
 use super::taxonomy_term::TaxonomyTerm;
+use super::uuid::skip_unfetchable;
 use super::{ConcreteUuid, EntityRevision, Uuid, UuidError, UuidFetcher};
-use crate::database::Executor;
-use crate::event::{
-    CreateEntityEventPayload, CreateEntityRevisionEventPayload, CreateSetLicenseEventPayload,
-    CreateTaxonomyLinkEventPayload, EntityLinkEventPayload, RevisionEventPayload,
-};
+use crate::cache::cache;
+use crate::changes;
+use crate::clock;
+use crate::database::{placeholders, Executor};
+use crate::event::{CreateSetLicenseEventPayload, RawEventType, RevisionEventPayload};
 
-use crate::{fetch_all_fields, format_alias};
+use crate::format_alias;
 
 use crate::datetime::DateTime;
 use crate::operation;
+use crate::repository::{
+    EntityRepository, EventRepository, MySqlRepository, NewEntity, NewEvent,
+    SubscriptionRepository, TaxonomyTermRepository, UuidRepository,
+};
 use crate::subscription::Subscription;
 use crate::uuid::abstract_entity_revision::EntityRevisionType;
 pub use messages::*;
@@ -33,7 +38,7 @@ mod abstract_entity;
 mod entity_type;
 mod messages;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct Entity {
     #[serde(flatten)]
     pub abstract_entity: AbstractEntity,
@@ -41,7 +46,7 @@ pub struct Entity {
     pub concrete_entity: ConcreteEntity,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(untagged)]
 pub enum ConcreteEntity {
     Generic,
@@ -53,38 +58,38 @@ pub enum ConcreteEntity {
     Solution(Solution),
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Course {
     page_ids: Vec<i32>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CoursePage {
     parent_id: i32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ExerciseGroup {
     exercise_ids: Vec<i32>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Exercise {
     solution_id: Option<i32>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GroupedExercise {
     parent_id: i32,
     solution_id: Option<i32>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Solution {
     parent_id: i32,
@@ -302,7 +307,331 @@ impl UuidFetcher for Entity {
     }
 }
 
+#[derive(sqlx::FromRow)]
+struct EntityRow {
+    id: i32,
+    name: String,
+    trashed: i8,
+    subdomain: String,
+    date: chrono::DateTime<Utc>,
+    current_revision_id: Option<i32>,
+    license_id: i32,
+    title: Option<String>,
+    fallback_title: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct RepositoryChildRow {
+    id: i32,
+    repository_id: i32,
+}
+
+#[derive(sqlx::FromRow)]
+struct EntityLinkRow {
+    entity_id: i32,
+    id: i32,
+    trashed: Option<i8>,
+    name: Option<String>,
+}
+
+impl EntityLinkRow {
+    fn entity_type(&self) -> Option<EntityType> {
+        self.name.as_deref().and_then(|name| name.parse().ok())
+    }
+}
+
 impl Entity {
+    pub async fn fetch_many<'a, E>(ids: &[i32], executor: E) -> Result<Vec<Uuid>, UuidError>
+    where
+        E: Executor<'a>,
+    {
+        let mut transaction = executor.begin().await?;
+
+        let query = format!(
+            r#"
+                SELECT e.id, t.name, u.trashed, i.subdomain, e.date, e.current_revision_id, e.license_id, f1.value as title, f2.value as fallback_title
+                    FROM entity e
+                    JOIN uuid u ON u.id = e.id
+                    JOIN instance i ON i.id = e.instance_id
+                    JOIN type t ON t.id = e.type_id
+                    LEFT JOIN entity_revision_field f1 ON f1.entity_revision_id = e.current_revision_id AND f1.field = 'title'
+                    LEFT JOIN entity_revision_field f2 on f2.entity_revision_id = (SELECT id FROM entity_revision WHERE repository_id = e.id LIMIT 1) AND f2.field = 'title'
+                    WHERE e.id IN ({})
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, EntityRow>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+        let entities = query.fetch_all(&mut transaction).await?;
+
+        let query = format!(
+            r#"
+                SELECT id, repository_id
+                    FROM entity_revision
+                    WHERE repository_id IN ({})
+                    ORDER BY id
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, RepositoryChildRow>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+        let mut revisions: HashMap<i32, Vec<RepositoryChildRow>> = HashMap::new();
+        for revision in query.fetch_all(&mut transaction).await? {
+            revisions
+                .entry(revision.repository_id)
+                .or_default()
+                .push(revision);
+        }
+
+        let query = format!(
+            r#"
+                SELECT term_taxonomy_id as id, entity_id as repository_id
+                    FROM term_taxonomy_entity
+                    WHERE entity_id IN ({})
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, RepositoryChildRow>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+        let mut taxonomy_terms: HashMap<i32, Vec<RepositoryChildRow>> = HashMap::new();
+        for taxonomy_term in query.fetch_all(&mut transaction).await? {
+            taxonomy_terms
+                .entry(taxonomy_term.repository_id)
+                .or_default()
+                .push(taxonomy_term);
+        }
+
+        let parents = Self::fetch_links(
+            r#"
+                SELECT l.child_id as entity_id, l.parent_id as id, u.trashed, t.name
+                    FROM entity_link l
+                    LEFT JOIN entity p on p.id = l.parent_id
+                    LEFT JOIN uuid u on u.id = p.id
+                    LEFT JOIN type t ON t.id = p.type_id
+                    WHERE l.child_id IN ({})
+            "#,
+            ids,
+            &mut transaction,
+        )
+        .await?;
+        let children = Self::fetch_links(
+            r#"
+                SELECT l.parent_id as entity_id, c.id, u.trashed, t.name
+                    FROM entity_link l
+                    JOIN entity c on c.id = l.child_id
+                    JOIN uuid u on u.id = c.id
+                    JOIN type t ON t.id = c.type_id
+                    WHERE l.parent_id IN ({})
+                    ORDER BY l.order ASC
+            "#,
+            ids,
+            &mut transaction,
+        )
+        .await?;
+        let mut subjects = Self::fetch_canonical_subjects(ids, &mut transaction).await?;
+
+        transaction.commit().await?;
+
+        let mut uuids = Vec::with_capacity(entities.len());
+        for entity in entities {
+            let id = entity.id;
+            let result = Self::from_rows(
+                entity,
+                revisions.remove(&id).unwrap_or_default(),
+                taxonomy_terms.remove(&id).unwrap_or_default(),
+                subjects.remove(&id),
+                parents.get(&id).map(Vec::as_slice).unwrap_or_default(),
+                children.get(&id).map(Vec::as_slice).unwrap_or_default(),
+            );
+            if let Some(uuid) = skip_unfetchable(id, result)? {
+                uuids.push(uuid);
+            }
+        }
+
+        Ok(uuids)
+    }
+
+    /// Fetches the linked entities of many entities with `query` which selects them grouped by
+    /// the column `entity_id`.
+    async fn fetch_links<'a, E>(
+        query: &str,
+        ids: &[i32],
+        executor: E,
+    ) -> Result<HashMap<i32, Vec<EntityLinkRow>>, sqlx::Error>
+    where
+        E: Executor<'a>,
+    {
+        let query = query.replace("{}", &placeholders(ids.len()));
+        let mut query = sqlx::query_as::<_, EntityLinkRow>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+
+        let mut links: HashMap<i32, Vec<EntityLinkRow>> = HashMap::new();
+        for link in query.fetch_all(executor).await? {
+            links.entry(link.entity_id).or_default().push(link);
+        }
+        Ok(links)
+    }
+
+    fn from_rows(
+        entity: EntityRow,
+        revisions: Vec<RepositoryChildRow>,
+        taxonomy_terms: Vec<RepositoryChildRow>,
+        subject: Option<Subject>,
+        parents: &[EntityLinkRow],
+        children: &[EntityLinkRow],
+    ) -> Result<Uuid, UuidError> {
+        let id = entity.id;
+        let entity_type = entity.name.parse::<EntityType>()?;
+
+        // Same as in `to_entity!` but with the links fetched beforehand.
+        let children_of_type = |child_type: EntityType| {
+            children
+                .iter()
+                .filter(move |child| child.entity_type().as_ref() == Some(&child_type))
+        };
+        let solution_id = || {
+            children_of_type(EntityType::Solution)
+                .find(|child| child.trashed == Some(0))
+                .map(|child| child.id)
+        };
+        let parent_id = |parent_types: &[EntityType]| {
+            parents
+                .iter()
+                .find(|parent| {
+                    parent_types.is_empty()
+                        || parent
+                            .entity_type()
+                            .is_some_and(|parent_type| parent_types.contains(&parent_type))
+                })
+                .map(|parent| parent.id)
+                .ok_or(UuidError::EntityMissingRequiredParent)
+        };
+
+        let concrete_entity = match entity_type {
+            EntityType::Course => ConcreteEntity::Course(Course {
+                page_ids: children_of_type(EntityType::CoursePage)
+                    .map(|child| child.id)
+                    .collect(),
+            }),
+            EntityType::CoursePage => ConcreteEntity::CoursePage(CoursePage {
+                parent_id: parent_id(&[])?,
+            }),
+            EntityType::ExerciseGroup => ConcreteEntity::ExerciseGroup(ExerciseGroup {
+                exercise_ids: children_of_type(EntityType::GroupedExercise)
+                    .map(|child| child.id)
+                    .collect(),
+            }),
+            EntityType::Exercise => ConcreteEntity::Exercise(Exercise {
+                solution_id: solution_id(),
+            }),
+            EntityType::GroupedExercise => ConcreteEntity::GroupedExercise(GroupedExercise {
+                parent_id: parent_id(&[])?,
+                solution_id: solution_id(),
+            }),
+            EntityType::Solution => ConcreteEntity::Solution(Solution {
+                parent_id: parent_id(&[EntityType::Exercise, EntityType::GroupedExercise])?,
+            }),
+            _ => ConcreteEntity::Generic,
+        };
+
+        let abstract_entity = AbstractEntity {
+            __typename: entity_type,
+            instance: entity
+                .subdomain
+                .parse()
+                .map_err(|_| UuidError::InvalidInstance)?,
+            date: entity.date.into(),
+            license_id: entity.license_id,
+            taxonomy_term_ids: taxonomy_terms.iter().map(|term| term.id).collect(),
+            canonical_subject_id: subject.as_ref().map(|subject| subject.taxonomy_term_id),
+            current_revision_id: entity.current_revision_id,
+            revision_ids: revisions.iter().rev().map(|revision| revision.id).collect(),
+        };
+
+        Ok(Uuid {
+            id,
+            trashed: entity.trashed != 0,
+            alias: format_alias(
+                subject.map(|subject| subject.name).as_deref(),
+                id,
+                Some(
+                    entity
+                        .title
+                        .or(entity.fallback_title)
+                        .unwrap_or(format!("{id}"))
+                        .as_str(),
+                ),
+            ),
+            concrete_uuid: ConcreteUuid::Entity(Entity {
+                abstract_entity,
+                concrete_entity,
+            }),
+        })
+    }
+
+    /// Batched version of [`Entity::fetch_canonical_subject`].
+    pub async fn fetch_canonical_subjects<'a, E>(
+        ids: &[i32],
+        executor: E,
+    ) -> Result<HashMap<i32, Subject>, sqlx::Error>
+    where
+        E: Executor<'a>,
+    {
+        let mut transaction = executor.begin().await?;
+
+        let query = format!(
+            r#"
+                SELECT entity_id, term_taxonomy_id
+                    FROM (
+                        SELECT term_taxonomy_id, entity_id FROM term_taxonomy_entity
+                        UNION ALL
+                        SELECT t.term_taxonomy_id, l.child_id as entity_id
+                            FROM term_taxonomy_entity t
+                            JOIN entity_link l ON t.entity_id = l.parent_id
+                        UNION ALL
+                        SELECT t.term_taxonomy_id, l2.child_id as entity_id
+                            FROM term_taxonomy_entity t
+                            JOIN entity_link l1 ON t.entity_id = l1.parent_id
+                            JOIN entity_link l2 ON l2.parent_id = l1.child_id
+                    ) u
+                    WHERE entity_id IN ({})
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, (i32, i32)>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+
+        // Like in `fetch_canonical_subject!` the first taxonomy term determines the subject.
+        let mut taxonomy_terms: HashMap<i32, i32> = HashMap::new();
+        for (entity_id, taxonomy_term_id) in query.fetch_all(&mut transaction).await? {
+            taxonomy_terms.entry(entity_id).or_insert(taxonomy_term_id);
+        }
+
+        let term_ids: Vec<i32> = taxonomy_terms.values().copied().collect();
+        let subjects = TaxonomyTerm::fetch_canonical_subjects(&term_ids, &mut transaction).await?;
+
+        transaction.commit().await?;
+
+        Ok(taxonomy_terms
+            .into_iter()
+            .filter_map(|(entity_id, term_id)| {
+                subjects
+                    .get(&term_id)
+                    .map(|subject| (entity_id, subject.clone()))
+            })
+            .collect())
+    }
+
     pub async fn fetch_entity_type<'a, E>(
         id: i32,
         executor: E,
@@ -458,35 +787,40 @@ impl Entity {
         E: Executor<'a>,
     {
         let mut transaction = executor.begin().await?;
+        let revision_id =
+            Self::add_revision_with(payload, &mut MySqlRepository::new(&mut transaction)).await?;
+        let revision = EntityRevision::fetch_via_transaction(revision_id, &mut transaction).await?;
+        transaction.commit().await?;
 
-        Self::assert_entity_exists(payload.input.entity_id, &mut transaction).await?;
+        Ok(revision)
+    }
 
-        let last_not_trashed_revision = sqlx::query!(
-            r#"
-            SELECT er.id
-                FROM entity_revision er
-                JOIN uuid ON er.id = uuid.id
-                WHERE repository_id = ?
-                    AND trashed = 0
-                ORDER BY date DESC
-                LIMIT 1
-            "#,
-            payload.input.entity_id
-        )
-        .fetch_optional(&mut transaction)
-        .await?
-        .map(|x| x.id as i32);
+    /// Adds a revision to the entity and checks it out unless it needs a review. When the fields
+    /// equal the ones of the latest revision which is not trashed, no revision is added and the id
+    /// of the latest revision is returned.
+    pub async fn add_revision_with<R>(
+        payload: &entity_add_revision_mutation::Payload,
+        repository: &mut R,
+    ) -> Result<i32, operation::Error>
+    where
+        R: EntityRepository + UuidRepository + EventRepository + SubscriptionRepository,
+    {
+        let entity_id = payload.input.entity_id;
+        let instance = repository
+            .fetch_entity_instance(entity_id)
+            .await?
+            .ok_or_else(|| operation::Error::BadRequest {
+                code: operation::ErrorCode::EntityNotFound,
+                reason: format!("Entity with id {entity_id} does not exist"),
+            })?;
 
         let mut fields = payload.input.fields.clone();
 
-        if let Some(revision_id) = last_not_trashed_revision {
-            let mut last_revision_fields: HashMap<String, String> =
-                fetch_all_fields!(revision_id, &mut transaction)
-                    .await?
-                    .into_iter()
-                    .filter(|field| field.field != "changes")
-                    .map(|field| (field.field.to_case(Case::Camel), field.value))
-                    .collect();
+        if let Some((revision_id, mut last_revision_fields)) = repository
+            .fetch_latest_untrashed_revision(entity_id)
+            .await?
+        {
+            last_revision_fields.remove("changes");
 
             // FIXME: This is bad design -> let's have a DB migration?!
             if payload.revision_type == EntityRevisionType::ExerciseGroup
@@ -504,70 +838,58 @@ impl Entity {
             }
 
             if last_revision_fields == fields {
-                return Ok(
-                    EntityRevision::fetch_via_transaction(revision_id, &mut transaction).await?,
-                );
+                return Ok(revision_id);
             }
         }
 
         fields.insert("changes".to_string(), payload.input.changes.clone());
 
-        let entity_revision =
-            EntityRevisionPayload::new(payload.user_id, payload.input.entity_id, fields)
-                .save(&mut transaction)
-                .await?;
-
-        let instance_id = sqlx::query!(
-            r#"
-                SELECT instance_id
-                    FROM entity
-                    WHERE id = ?
-            "#,
-            payload.input.entity_id
-        )
-        .fetch_one(&mut transaction)
-        .await?
-        .instance_id;
+        let revision_id = repository
+            .create_entity_revision(payload.user_id, entity_id, fields)
+            .await?;
 
-        CreateEntityRevisionEventPayload::new(
-            payload.input.entity_id,
-            entity_revision.id,
-            payload.user_id,
-            instance_id,
-        )
-        .save(&mut transaction)
-        .await?;
+        repository
+            .save_event(NewEvent {
+                raw_typename: RawEventType::CreateEntityRevision,
+                actor_id: payload.user_id,
+                object_id: revision_id,
+                instance,
+                string_parameters: HashMap::new(),
+                uuid_parameters: [("repository".to_string(), entity_id)].into(),
+            })
+            .await?;
 
         if !payload.input.needs_review {
-            Entity::checkout_revision(
+            Entity::checkout_revision_with(
                 &checkout_revision_mutation::Payload {
-                    revision_id: entity_revision.id,
+                    revision_id,
                     user_id: payload.user_id,
                     reason: "".to_string(),
                 },
-                &mut transaction,
+                repository,
             )
             .await
             .map_err(|error| operation::Error::InternalServerError {
+                code: operation::ErrorCode::InternalServerError,
                 error: Box::new(error),
             })?;
         }
 
         if payload.input.subscribe_this {
-            Subscription::save(
-                &Subscription {
-                    object_id: payload.input.entity_id,
+            repository
+                .save_subscription(&Subscription {
+                    object_id: entity_id,
                     user_id: payload.user_id,
                     send_email: payload.input.subscribe_this_by_email,
-                },
-                &mut transaction,
-            )
-            .await?;
+                })
+                .await?;
         }
 
-        transaction.commit().await?;
+        changes::record_affected_uuids(&[entity_id]);
 
-        Ok(entity_revision)
+        cache().invalidate(&[entity_id]);
+
+        Ok(revision_id)
     }
 
     pub async fn assert_entity_exists<'a, E>(id: i32, executor: E) -> Result<(), operation::Error>
@@ -579,11 +901,10 @@ impl Entity {
             .await
             .map_err(|error| match error {
                 sqlx::Error::RowNotFound => operation::Error::BadRequest {
+                    code: operation::ErrorCode::EntityNotFound,
                     reason: format!("Entity with id {id} does not exist"),
                 },
-                _ => operation::Error::InternalServerError {
-                    error: Box::new(error),
-                },
+                _ => error.into(),
             })?;
 
         Ok(())
@@ -597,160 +918,119 @@ impl Entity {
         E: Executor<'a>,
     {
         let mut transaction = executor.begin().await?;
+        let entity_id =
+            Self::create_with(payload, &mut MySqlRepository::new(&mut transaction)).await?;
+        let entity = Entity::fetch_via_transaction(entity_id, &mut transaction).await?;
+        transaction.commit().await?;
 
-        if payload.entity_type == EntityType::Solution
-            && sqlx::query!(
-                "SELECT uuid.id 
-                 FROM entity_link JOIN uuid ON uuid.id = entity_link.child_id
-                 WHERE uuid.trashed = 0 AND parent_id = ?",
-                payload
-                    .input
-                    .parent_id
-                    .ok_or(operation::Error::BadRequest {
-                        reason: "parent_id needs to be provided".to_string(),
-                    })?
-            )
-            .fetch_one(&mut transaction)
-            .await
-            .ok()
-            .is_some()
-        {
-            return Err(operation::Error::BadRequest {
-                reason: "solution already exists".to_string(),
-            });
-        }
-
-        sqlx::query!(
-            r#"
-                INSERT INTO uuid (trashed, discriminator)
-                    VALUES (0, "entity")
-            "#,
-        )
-        .execute(&mut transaction)
-        .await?;
-
-        let entity_id = sqlx::query!(r#"SELECT LAST_INSERT_ID() as id"#)
-            .fetch_one(&mut transaction)
-            .await?
-            .id as i32;
-
-        let type_id = sqlx::query!(r#"SELECT id FROM type WHERE name = ?"#, payload.entity_type)
-            .fetch_one(&mut transaction)
-            .await?
-            .id;
-
-        let parent_id: i32;
-        let instance_id: i32;
+        Ok(entity)
+    }
 
-        if let EntityType::CoursePage | EntityType::GroupedExercise | EntityType::Solution =
-            payload.entity_type
-        {
-            parent_id = payload
+    /// Creates the entity with its first revision below its parent (an entity for course pages,
+    /// grouped exercises and solutions, a taxonomy term otherwise). Returns the id of the entity.
+    pub async fn create_with<R>(
+        payload: &entity_create_mutation::Payload,
+        repository: &mut R,
+    ) -> Result<i32, operation::Error>
+    where
+        R: EntityRepository
+            + TaxonomyTermRepository
+            + EventRepository
+            + UuidRepository
+            + SubscriptionRepository,
+    {
+        let has_parent_entity = matches!(
+            payload.entity_type,
+            EntityType::CoursePage | EntityType::GroupedExercise | EntityType::Solution
+        );
+        let parent_id = if has_parent_entity {
+            payload
                 .input
                 .parent_id
                 .ok_or(operation::Error::BadRequest {
+                    code: operation::ErrorCode::MissingParameter,
                     reason: "parent_id needs to be provided".to_string(),
-                })?;
-
-            instance_id = sqlx::query!("select instance_id from entity where id = ?", parent_id)
-                .fetch_optional(&mut transaction)
-                .await?
-                .ok_or(operation::Error::BadRequest {
-                    reason: format!("parent entity with id {parent_id} does not exist"),
                 })?
-                .instance_id;
         } else {
-            parent_id = payload
+            payload
                 .input
                 .taxonomy_term_id
                 .ok_or(operation::Error::BadRequest {
+                    code: operation::ErrorCode::MissingParameter,
                     reason: "taxonomy_term_id needs to be provided".to_string(),
-                })?;
-
-            instance_id = TaxonomyTerm::get_instance_id(parent_id, &mut transaction).await?;
-        }
-
-        sqlx::query!(
-            r#"
-                INSERT INTO entity (id, type_id, instance_id, license_id, date)
-                    VALUES (?, ?, ?, ?, ?)
-            "#,
-            entity_id,
-            type_id,
-            instance_id,
-            payload.input.license_id,
-            DateTime::now(),
-        )
-        .execute(&mut transaction)
-        .await?;
+                })?
+        };
 
-        if let EntityType::CoursePage | EntityType::GroupedExercise | EntityType::Solution =
-            payload.entity_type
+        if payload.entity_type == EntityType::Solution
+            && repository.has_untrashed_children(parent_id).await?
         {
-            let last_order = sqlx::query!(
-                r#"
-                    SELECT IFNULL(MAX(et.order), 0) AS current_last
-                        FROM entity_link et
-                        WHERE et.parent_id = ?
-                "#,
-                parent_id,
-            )
-            .fetch_one(&mut transaction)
-            .await?
-            .current_last as i32
-                + 1;
-
-            sqlx::query!(
-                r#"
-                    INSERT INTO entity_link (parent_id, child_id, type_id, entity_link.order)
-                    VALUES (?, ?, 9, ?)
-                "#,
-                parent_id,
-                entity_id,
-                last_order
-            )
-            .execute(&mut transaction)
-            .await?;
+            return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::SolutionAlreadyExists,
+                reason: "solution already exists".to_string(),
+            });
+        }
 
-            EntityLinkEventPayload::new(entity_id, parent_id, payload.user_id, instance_id)
-                .save(&mut transaction)
-                .await?;
+        let instance = if has_parent_entity {
+            repository.fetch_entity_instance(parent_id).await?.ok_or(
+                operation::Error::BadRequest {
+                    code: operation::ErrorCode::EntityNotFound,
+                    reason: format!("parent entity with id {parent_id} does not exist"),
+                },
+            )?
         } else {
-            let last_position = sqlx::query!(
-                r#"
-                    SELECT IFNULL(MAX(position), 0) AS current_last
-                        FROM term_taxonomy_entity
-                        WHERE term_taxonomy_id = ?
-                "#,
-                parent_id
-            )
-            .fetch_one(&mut transaction)
-            .await?
-            .current_last as i32
-                + 1;
+            repository
+                .fetch_taxonomy_term_instance(parent_id)
+                .await?
+                .ok_or(operation::Error::BadRequest {
+                    code: operation::ErrorCode::TaxonomyTermNotFound,
+                    reason: format!("Taxonomy term with id {parent_id} does not exist"),
+                })?
+        };
 
-            sqlx::query!(
-                r#"
-                    INSERT INTO term_taxonomy_entity (entity_id, term_taxonomy_id, position)
-                    VALUES (?, ?, ?)
-                "#,
-                entity_id,
-                parent_id,
-                last_position
-            )
-            .execute(&mut transaction)
+        let entity_id = repository
+            .create_entity(&NewEntity {
+                entity_type: payload.entity_type.clone(),
+                instance: instance.clone(),
+                license_id: payload.input.license_id,
+            })
             .await?;
 
-            CreateTaxonomyLinkEventPayload::new(entity_id, parent_id, payload.user_id, instance_id)
-                .save(&mut transaction)
+        let link_event = if has_parent_entity {
+            repository.link_entity(parent_id, entity_id).await?;
+            NewEvent {
+                raw_typename: RawEventType::CreateEntityLink,
+                actor_id: payload.user_id,
+                object_id: entity_id,
+                instance: instance.clone(),
+                string_parameters: HashMap::new(),
+                uuid_parameters: [("parent".to_string(), parent_id)].into(),
+            }
+        } else {
+            repository
+                .link_entity_to_taxonomy_term(entity_id, parent_id)
                 .await?;
-        }
-
-        CreateEntityEventPayload::new(entity_id, payload.user_id, instance_id)
-            .save(&mut transaction)
+            NewEvent {
+                raw_typename: RawEventType::CreateTaxonomyLink,
+                actor_id: payload.user_id,
+                object_id: parent_id,
+                instance: instance.clone(),
+                string_parameters: HashMap::new(),
+                uuid_parameters: [("object".to_string(), entity_id)].into(),
+            }
+        };
+        repository.save_event(link_event).await?;
+        repository
+            .save_event(NewEvent {
+                raw_typename: RawEventType::CreateEntity,
+                actor_id: payload.user_id,
+                object_id: entity_id,
+                instance,
+                string_parameters: HashMap::new(),
+                uuid_parameters: HashMap::new(),
+            })
             .await?;
 
-        Entity::add_revision(
+        Self::add_revision_with(
             &entity_add_revision_mutation::Payload {
                 input: entity_add_revision_mutation::Input {
                     changes: payload.input.changes.clone(),
@@ -763,15 +1043,15 @@ impl Entity {
                 revision_type: EntityRevisionType::from(payload.entity_type.clone()),
                 user_id: payload.user_id,
             },
-            &mut transaction,
+            repository,
         )
         .await?;
 
-        let entity = Entity::fetch_via_transaction(entity_id, &mut transaction).await?;
+        changes::record_affected_uuids(&[parent_id]);
 
-        transaction.commit().await?;
+        cache().invalidate(&[parent_id]);
 
-        Ok(entity)
+        Ok(entity_id)
     }
 }
 
@@ -784,67 +1064,62 @@ impl Entity {
         E: Executor<'a>,
     {
         let mut transaction = executor.begin().await?;
+        Self::checkout_revision_with(payload, &mut MySqlRepository::new(&mut transaction)).await?;
+        transaction.commit().await?;
 
-        let revision_id = payload.revision_id;
-        let revision = EntityRevision::fetch_via_transaction(revision_id, &mut transaction).await?;
+        Ok(())
+    }
 
-        if let ConcreteUuid::EntityRevision(EntityRevision {
-            abstract_entity_revision,
-            ..
-        }) = revision.concrete_uuid
-        {
-            let repository_id = abstract_entity_revision.repository_id;
+    pub async fn checkout_revision_with<R>(
+        payload: &checkout_revision_mutation::Payload,
+        repository: &mut R,
+    ) -> Result<(), operation::Error>
+    where
+        R: EntityRepository + UuidRepository + EventRepository,
+    {
+        let revision_id = payload.revision_id;
+        let revision = repository
+            .fetch_entity_revision(revision_id)
+            .await?
+            .ok_or_else(|| operation::Error::BadRequest {
+                code: operation::ErrorCode::InvalidRevision,
+                reason: "revision invalid".to_string(),
+            })?;
+        let repository_id = revision.repository_id;
+        let entity = repository
+            .fetch_entity(repository_id)
+            .await?
+            .ok_or_else(|| operation::Error::BadRequest {
+                code: operation::ErrorCode::InvalidRepository,
+                reason: "repository  invalid".to_string(),
+            })?;
 
-            let repository = Entity::fetch_via_transaction(repository_id, &mut transaction).await?;
+        if entity.current_revision_id == Some(revision_id) {
+            return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::RevisionAlreadyCheckedOut,
+                reason: "revision is already checked out".to_string(),
+            });
+        }
 
-            if let ConcreteUuid::Entity(Entity {
-                abstract_entity, ..
-            }) = repository.concrete_uuid
-            {
-                if abstract_entity.current_revision_id == Some(revision_id) {
-                    return Err(operation::Error::BadRequest {
-                        reason: "revision is already checked out".to_string(),
-                    });
-                }
-
-                Uuid::set_state(revision_id, false, &mut transaction).await?;
-
-                sqlx::query!(
-                    r#"
-                        UPDATE entity
-                            SET current_revision_id = ?
-                            WHERE id = ?
-                    "#,
-                    revision_id,
-                    repository_id,
-                )
-                .execute(&mut transaction)
-                .await?;
+        repository.set_uuid_state(revision_id, false).await?;
+        repository
+            .set_current_revision(repository_id, revision_id)
+            .await?;
+        let event = RevisionEventPayload::new(
+            false,
+            payload.user_id,
+            repository_id,
+            revision_id,
+            payload.reason.clone(),
+            entity.instance,
+        );
+        repository.save_event(NewEvent::from(&event)).await?;
 
-                RevisionEventPayload::new(
-                    false,
-                    payload.user_id,
-                    repository_id,
-                    payload.revision_id,
-                    payload.reason.clone(),
-                    abstract_entity.instance,
-                )
-                .save(&mut transaction)
-                .await?;
+        changes::record_affected_uuids(&[repository_id]);
 
-                transaction.commit().await?;
+        cache().invalidate(&[repository_id]);
 
-                Ok(())
-            } else {
-                Err(operation::Error::BadRequest {
-                    reason: "repository  invalid".to_string(),
-                })
-            }
-        } else {
-            Err(operation::Error::BadRequest {
-                reason: "revision invalid".to_string(),
-            })
-        }
+        Ok(())
     }
 }
 
@@ -857,61 +1132,67 @@ impl Entity {
         E: Executor<'a>,
     {
         let mut transaction = executor.begin().await?;
+        Self::reject_revision_with(payload, &mut MySqlRepository::new(&mut transaction)).await?;
+        transaction.commit().await?;
 
+        Ok(())
+    }
+
+    pub async fn reject_revision_with<R>(
+        payload: &reject_revision_mutation::Payload,
+        repository: &mut R,
+    ) -> Result<(), operation::Error>
+    where
+        R: EntityRepository + UuidRepository + EventRepository,
+    {
         let revision_id = payload.revision_id;
-        let revision = EntityRevision::fetch_via_transaction(revision_id, &mut transaction).await?;
+        let revision = repository
+            .fetch_entity_revision(revision_id)
+            .await?
+            .ok_or_else(|| operation::Error::BadRequest {
+                code: operation::ErrorCode::InvalidRevision,
+                reason: "revision invalid".to_string(),
+            })?;
 
-        if let ConcreteUuid::EntityRevision(EntityRevision {
-            abstract_entity_revision,
-            ..
-        }) = revision.concrete_uuid
-        {
-            if revision.trashed {
-                return Err(operation::Error::BadRequest {
-                    reason: "revision is already rejected".to_string(),
-                });
-            }
+        if revision.trashed {
+            return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::RevisionAlreadyRejected,
+                reason: "revision is already rejected".to_string(),
+            });
+        }
 
-            let repository_id = abstract_entity_revision.repository_id;
+        let repository_id = revision.repository_id;
+        let entity = repository
+            .fetch_entity(repository_id)
+            .await?
+            .ok_or_else(|| operation::Error::BadRequest {
+                code: operation::ErrorCode::InvalidRepository,
+                reason: "repository invalid".to_string(),
+            })?;
 
-            let repository = Entity::fetch_via_transaction(repository_id, &mut transaction).await?;
+        if entity.current_revision_id == Some(revision_id) {
+            return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::RevisionCurrentlyCheckedOut,
+                reason: "revision is checked out currently".to_string(),
+            });
+        }
 
-            if let ConcreteUuid::Entity(Entity {
-                abstract_entity, ..
-            }) = repository.concrete_uuid
-            {
-                if abstract_entity.current_revision_id == Some(revision_id) {
-                    return Err(operation::Error::BadRequest {
-                        reason: "revision is checked out currently".to_string(),
-                    });
-                }
-
-                Uuid::set_state(revision_id, true, &mut transaction).await?;
-
-                RevisionEventPayload::new(
-                    true,
-                    payload.user_id,
-                    abstract_entity_revision.repository_id,
-                    payload.revision_id,
-                    payload.reason.clone(),
-                    abstract_entity.instance,
-                )
-                .save(&mut transaction)
-                .await?;
+        repository.set_uuid_state(revision_id, true).await?;
+        let event = RevisionEventPayload::new(
+            true,
+            payload.user_id,
+            repository_id,
+            revision_id,
+            payload.reason.clone(),
+            entity.instance,
+        );
+        repository.save_event(NewEvent::from(&event)).await?;
 
-                transaction.commit().await?;
+        changes::record_affected_uuids(&[repository_id]);
 
-                Ok(())
-            } else {
-                Err(operation::Error::BadRequest {
-                    reason: "repository invalid".to_string(),
-                })
-            }
-        } else {
-            Err(operation::Error::BadRequest {
-                reason: "revision invalid".to_string(),
-            })
-        }
+        cache().invalidate(&[repository_id]);
+
+        Ok(())
     }
 }
 
@@ -954,7 +1235,7 @@ impl Entity {
     {
         let after_db_time = match payload.after.as_ref() {
             Some(date) => DateTime::parse_from_rfc3339(date)?,
-            None => DateTime::now(),
+            None => clock::now(),
         };
 
         Ok(sqlx::query!(
@@ -1016,6 +1297,7 @@ impl Entity {
 
         if children_ids.is_empty() {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::EntityNotFound,
                 reason: "entity does not exist or has no children".to_string(),
             });
         }
@@ -1024,6 +1306,7 @@ impl Entity {
             .is_subset(&HashSet::<i32>::from_iter(children_ids.clone()))
         {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::InvalidChildren,
                 reason: "children_ids have to be a subset of linked children entities".to_string(),
             });
         }
@@ -1043,6 +1326,10 @@ impl Entity {
             .await?;
         }
 
+        changes::record_affected_uuids(&[payload.entity_id]);
+
+        cache().invalidate(&[payload.entity_id]);
+
         transaction.commit().await?;
 
         Ok(())
@@ -1068,6 +1355,7 @@ impl Entity {
         .fetch_optional(&mut transaction)
         .await?
         .ok_or(operation::Error::BadRequest {
+            code: operation::ErrorCode::UserNotFound,
             reason: format!("An user with id {} does not exist.", payload.user_id),
         })?;
 
@@ -1080,6 +1368,7 @@ impl Entity {
         .fetch_optional(&mut transaction)
         .await?
         .ok_or(operation::Error::BadRequest {
+            code: operation::ErrorCode::EntityNotFound,
             reason: format!("An entity with id {} does not exist.", payload.entity_id),
         })?;
 
@@ -1101,6 +1390,10 @@ impl Entity {
             .save(&mut transaction)
             .await?;
 
+        changes::record_affected_uuids(&[payload.entity_id]);
+
+        cache().invalidate(&[payload.entity_id]);
+
         transaction.commit().await?;
         Ok(())
     }
@@ -1126,7 +1419,10 @@ mod tests {
 
         match Entity::assert_entity_exists(1, &mut transaction).await {
             Err(error) => match error {
-                operation::Error::BadRequest { reason: _ } => {}
+                operation::Error::BadRequest {
+                    code: operation::ErrorCode::EntityNotFound,
+                    ..
+                } => {}
                 _ => panic!("check_entity_exists didn't throw expected error"),
             },
             _ => panic!("check_entity_exists didn't throw expected error"),
@@ -2,14 +2,16 @@ use convert_case::{Case, Casing};
 use std::collections::HashMap;
 
 use crate::database::Executor;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::UuidError;
+use crate::clock;
 use crate::datetime::DateTime;
 use crate::operation;
 use crate::uuid::{EntityRevision, EntityType, Uuid, UuidFetcher};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AbstractEntityRevision {
     #[serde(rename(serialize = "__typename"))]
@@ -23,7 +25,7 @@ pub struct AbstractEntityRevision {
     pub fields: EntityRevisionFields,
 }
 
-#[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
 #[serde(rename_all(deserialize = "kebab-case"))]
 pub enum EntityRevisionType {
     #[serde(rename = "AppletRevision")]
@@ -129,7 +131,7 @@ impl EntityRevisionPayload {
             entity_revision_id,
             self.author_id,
             self.repository_id,
-            DateTime::now(),
+            clock::now(),
         )
         .execute(&mut transaction)
         .await?;
@@ -1,6 +1,10 @@
+use std::collections::HashMap;
+
 use crate::uuid::Subject;
 use async_trait::async_trait;
+use chrono::Utc;
 use futures::try_join;
+use schemars::JsonSchema;
 use serde::Serialize;
 use sqlx::MySqlPool;
 
@@ -15,8 +19,9 @@ use self::exercise_group_revision::ExerciseGroupRevision;
 use self::generic_entity_revision::GenericRevision;
 use self::video_revision::VideoRevision;
 use super::entity::Entity;
+use super::uuid::skip_unfetchable;
 use super::{ConcreteUuid, Uuid, UuidError, UuidFetcher};
-use crate::database::Executor;
+use crate::database::{placeholders, Executor};
 
 pub mod abstract_entity_revision;
 mod applet_revision;
@@ -28,7 +33,7 @@ mod exercise_group_revision;
 mod generic_entity_revision;
 mod video_revision;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct EntityRevision {
     #[serde(flatten)]
     pub abstract_entity_revision: AbstractEntityRevision,
@@ -36,7 +41,7 @@ pub struct EntityRevision {
     pub concrete_entity_revision: ConcreteEntityRevision,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(untagged)]
 pub enum ConcreteEntityRevision {
     Generic(GenericRevision),
@@ -169,7 +174,119 @@ impl UuidFetcher for EntityRevision {
     }
 }
 
+#[derive(sqlx::FromRow)]
+struct RevisionRow {
+    id: i32,
+    name: String,
+    trashed: i8,
+    date: chrono::DateTime<Utc>,
+    author_id: i32,
+    repository_id: i32,
+}
+
+#[derive(sqlx::FromRow)]
+struct FieldRow {
+    entity_revision_id: i32,
+    field: String,
+    value: String,
+}
+
 impl EntityRevision {
+    pub async fn fetch_many<'a, E>(ids: &[i32], executor: E) -> Result<Vec<Uuid>, UuidError>
+    where
+        E: Executor<'a>,
+    {
+        let mut transaction = executor.begin().await?;
+
+        let query = format!(
+            r#"
+                SELECT r.id, t.name, u.trashed, r.date, r.author_id, r.repository_id
+                    FROM entity_revision r
+                    JOIN uuid u ON u.id = r.id
+                    JOIN entity e ON e.id = r.repository_id
+                    JOIN type t ON t.id = e.type_id
+                    WHERE r.id IN ({})
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, RevisionRow>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+        let revisions = query.fetch_all(&mut transaction).await?;
+
+        let query = format!(
+            r#"
+                SELECT entity_revision_id, field, value
+                    FROM entity_revision_field
+                    WHERE entity_revision_id IN ({})
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, FieldRow>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+        let mut fields: HashMap<i32, Vec<FieldRow>> = HashMap::new();
+        for field in query.fetch_all(&mut transaction).await? {
+            fields
+                .entry(field.entity_revision_id)
+                .or_default()
+                .push(field);
+        }
+
+        transaction.commit().await?;
+
+        let mut uuids = Vec::with_capacity(revisions.len());
+        for revision in revisions {
+            let id = revision.id;
+            let fields = fields.remove(&id).unwrap_or_default();
+            if let Some(uuid) = skip_unfetchable(id, Self::from_rows(revision, fields))? {
+                uuids.push(uuid);
+            }
+        }
+        Ok(uuids)
+    }
+
+    fn from_rows(revision: RevisionRow, fields: Vec<FieldRow>) -> Result<Uuid, UuidError> {
+        to_entity_revisions!(revision.id, revision, fields)
+    }
+
+    /// Batched version of [`EntityRevision::fetch_canonical_subject`].
+    pub async fn fetch_canonical_subjects<'a, E>(
+        ids: &[i32],
+        executor: E,
+    ) -> Result<HashMap<i32, Subject>, sqlx::Error>
+    where
+        E: Executor<'a>,
+    {
+        let mut transaction = executor.begin().await?;
+
+        let query = format!(
+            "SELECT id, repository_id FROM entity_revision WHERE id IN ({})",
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, (i32, i32)>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+        let revisions = query.fetch_all(&mut transaction).await?;
+
+        let repository_ids: Vec<i32> = revisions.iter().map(|(_, id)| *id).collect();
+        let subjects = Entity::fetch_canonical_subjects(&repository_ids, &mut transaction).await?;
+
+        transaction.commit().await?;
+
+        Ok(revisions
+            .into_iter()
+            .filter_map(|(id, repository_id)| {
+                subjects
+                    .get(&repository_id)
+                    .map(|subject| (id, subject.clone()))
+            })
+            .collect())
+    }
+
     pub async fn fetch_canonical_subject(
         id: i32,
         pool: &MySqlPool,
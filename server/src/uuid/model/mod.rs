@@ -1,6 +1,7 @@
 pub use attachment::*;
 pub use blog_post::*;
 pub use comment::*;
+pub use discriminator::Discriminator;
 pub use entity::*;
 pub use entity_revision::*;
 pub use page::*;
@@ -9,6 +10,9 @@ pub use taxonomy_term::*;
 pub use user::*;
 pub use uuid::*;
 
+// Pages have a mutation of the same name.
+pub use entity::checkout_revision_mutation;
+
 mod attachment;
 mod blog_post;
 mod comment;
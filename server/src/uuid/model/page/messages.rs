@@ -1,11 +1,14 @@
 use actix_web::HttpResponse;
 use async_trait::async_trait;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::database::Connection;
 use crate::instance::Instance;
 use crate::message::MessageResponder;
 use crate::operation::{self, Operation};
+use crate::schema::{MessageSchema, MessageSchemas};
 use crate::uuid::Uuid;
 
 use super::{Page, PageCheckoutRevisionError, PageRejectRevisionError};
@@ -46,11 +49,32 @@ impl MessageResponder for PageMessage {
     }
 }
 
+impl MessageSchemas for PageMessage {
+    fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema> {
+        vec![
+            MessageSchema::new::<add_revision_mutation::Payload>(
+                "PageAddRevisionMutation",
+                generator,
+            ),
+            MessageSchema::new::<checkout_revision_mutation::Payload>(
+                "PageCheckoutRevisionMutation",
+                generator,
+            ),
+            MessageSchema::new::<create_mutation::Payload>("PageCreateMutation", generator),
+            MessageSchema::new::<reject_revision_mutation::Payload>(
+                "PageRejectRevisionMutation",
+                generator,
+            ),
+            MessageSchema::new::<pages_query::Payload>("PagesQuery", generator),
+        ]
+    }
+}
+
 pub mod add_revision_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub content: String,
         pub title: String,
@@ -58,7 +82,7 @@ pub mod add_revision_mutation {
         pub user_id: i32,
     }
 
-    #[derive(Deserialize, Serialize)]
+    #[derive(Deserialize, Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub revision_id: i32,
@@ -87,8 +111,8 @@ pub mod add_revision_mutation {
 pub mod checkout_revision_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub revision_id: i32,
         pub user_id: i32,
@@ -115,18 +139,24 @@ pub mod checkout_revision_mutation {
                 PageCheckoutRevisionError::DatabaseError { .. }
                 | PageCheckoutRevisionError::EventError { .. }
                 | PageCheckoutRevisionError::UuidError { .. } => {
-                    operation::Error::InternalServerError { error: Box::new(e) }
+                    operation::Error::InternalServerError {
+                        code: operation::ErrorCode::InternalServerError,
+                        error: Box::new(e),
+                    }
                 }
                 PageCheckoutRevisionError::RevisionAlreadyCheckedOut => {
                     operation::Error::BadRequest {
+                        code: operation::ErrorCode::RevisionAlreadyCheckedOut,
                         reason: "revision is already checked out".to_string(),
                     }
                 }
                 PageCheckoutRevisionError::InvalidRevision { .. } => operation::Error::BadRequest {
+                    code: operation::ErrorCode::InvalidRevision,
                     reason: "revision invalid".to_string(),
                 },
                 PageCheckoutRevisionError::InvalidRepository { .. } => {
                     operation::Error::BadRequest {
+                        code: operation::ErrorCode::InvalidRepository,
                         reason: "repository invalid".to_string(),
                     }
                 }
@@ -138,8 +168,8 @@ pub mod checkout_revision_mutation {
 pub mod create_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub content: String,
         pub discussions_enabled: bool,
@@ -166,8 +196,8 @@ pub mod create_mutation {
 pub mod reject_revision_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub revision_id: i32,
         pub user_id: i32,
@@ -194,20 +224,27 @@ pub mod reject_revision_mutation {
                 PageRejectRevisionError::DatabaseError { .. }
                 | PageRejectRevisionError::EventError { .. }
                 | PageRejectRevisionError::UuidError { .. } => {
-                    operation::Error::InternalServerError { error: Box::new(e) }
+                    operation::Error::InternalServerError {
+                        code: operation::ErrorCode::InternalServerError,
+                        error: Box::new(e),
+                    }
                 }
                 PageRejectRevisionError::RevisionAlreadyRejected => operation::Error::BadRequest {
+                    code: operation::ErrorCode::RevisionAlreadyRejected,
                     reason: "revision has already been rejected".to_string(),
                 },
                 PageRejectRevisionError::RevisionCurrentlyCheckedOut => {
                     operation::Error::BadRequest {
+                        code: operation::ErrorCode::RevisionCurrentlyCheckedOut,
                         reason: "revision is checked out currently".to_string(),
                     }
                 }
                 PageRejectRevisionError::InvalidRevision { .. } => operation::Error::BadRequest {
+                    code: operation::ErrorCode::InvalidRevision,
                     reason: "revision invalid".to_string(),
                 },
                 PageRejectRevisionError::InvalidRepository { .. } => operation::Error::BadRequest {
+                    code: operation::ErrorCode::InvalidRepository,
                     reason: "repository invalid".to_string(),
                 },
             }
@@ -218,13 +255,13 @@ pub mod reject_revision_mutation {
 pub mod pages_query {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub instance: Option<Instance>,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         pub pages: Vec<i32>,
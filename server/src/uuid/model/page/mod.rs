@@ -1,11 +1,19 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
+use chrono::Utc;
 use futures::join;
+use schemars::JsonSchema;
 use serde::Serialize;
 use sqlx::MySqlPool;
 use thiserror::Error;
 
+use super::uuid::skip_unfetchable;
 use super::{ConcreteUuid, Uuid, UuidError, UuidFetcher};
-use crate::database::Executor;
+use crate::cache::cache;
+use crate::changes;
+use crate::clock;
+use crate::database::{placeholders, Executor};
 use crate::datetime::DateTime;
 use crate::format_alias;
 use crate::instance::Instance;
@@ -20,7 +28,7 @@ use crate::uuid::model::page::pages_query;
 
 mod messages;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Page {
     #[serde(rename(serialize = "__typename"))]
@@ -121,6 +129,91 @@ impl UuidFetcher for Page {
     }
 }
 
+#[derive(sqlx::FromRow)]
+struct PageRow {
+    id: i32,
+    trashed: i8,
+    subdomain: String,
+    current_revision_id: Option<i32>,
+    license_id: i32,
+    title: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct PageRevisionRow {
+    id: i32,
+    page_repository_id: i32,
+    date: chrono::DateTime<Utc>,
+}
+
+impl Page {
+    pub async fn fetch_many<'a, E>(ids: &[i32], executor: E) -> Result<Vec<Uuid>, UuidError>
+    where
+        E: Executor<'a>,
+    {
+        let mut transaction = executor.begin().await?;
+
+        let query = format!(
+            r#"
+                SELECT p.id, u.trashed, i.subdomain, p.current_revision_id, p.license_id, r.title
+                    FROM page_repository p
+                    JOIN uuid u ON u.id = p.id
+                    JOIN instance i ON i.id = p.instance_id
+                    LEFT JOIN page_revision r ON r.id = p.current_revision_id
+                    WHERE p.id IN ({})
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, PageRow>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+        let pages = query.fetch_all(&mut transaction).await?;
+
+        let query = format!(
+            r#"
+                SELECT id, page_repository_id, date
+                    FROM page_revision
+                    WHERE page_repository_id IN ({})
+                    ORDER BY id
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, PageRevisionRow>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+        let mut revisions: HashMap<i32, Vec<PageRevisionRow>> = HashMap::new();
+        for revision in query.fetch_all(&mut transaction).await? {
+            revisions
+                .entry(revision.page_repository_id)
+                .or_default()
+                .push(revision);
+        }
+
+        transaction.commit().await?;
+
+        let mut uuids = Vec::with_capacity(pages.len());
+        for page in pages {
+            let id = page.id;
+            let revisions = revisions.remove(&id).unwrap_or_default();
+            if let Some(uuid) = skip_unfetchable(id, Self::from_rows(page, revisions))? {
+                uuids.push(uuid);
+            }
+        }
+        Ok(uuids)
+    }
+
+    fn from_rows(page: PageRow, revisions: Vec<PageRevisionRow>) -> Result<Uuid, UuidError> {
+        let id = page.id;
+        to_page!(
+            id,
+            Ok::<_, sqlx::Error>(page),
+            Ok::<_, UuidError>(revisions)
+        )
+    }
+}
+
 impl Page {
     pub async fn add_revision<'a, E>(
         payload: &add_revision_mutation::Payload,
@@ -138,6 +231,7 @@ impl Page {
         .fetch_optional(&mut transaction)
         .await?
         .ok_or(operation::Error::BadRequest {
+            code: operation::ErrorCode::PageNotFound,
             reason: "no page found for provided pageId".to_string(),
         })?;
 
@@ -165,7 +259,7 @@ impl Page {
             payload.page_id,
             payload.title,
             payload.content,
-            DateTime::now(),
+            clock::now(),
         )
         .execute(&mut transaction)
         .await?;
@@ -201,11 +295,16 @@ impl Page {
         )
         .await
         .map_err(|error| operation::Error::InternalServerError {
+            code: operation::ErrorCode::InternalServerError,
             error: Box::new(error),
         })?;
 
         let uuid = PageRevision::fetch_via_transaction(page_revision_id, &mut transaction).await?;
 
+        changes::record_affected_uuids(&[payload.page_id]);
+
+        cache().invalidate(&[payload.page_id]);
+
         transaction.commit().await?;
 
         Ok(uuid)
@@ -300,6 +399,10 @@ impl Page {
                 .save(&mut transaction)
                 .await?;
 
+                changes::record_affected_uuids(&[repository_id]);
+
+                cache().invalidate(&[repository_id]);
+
                 transaction.commit().await?;
 
                 Ok(())
@@ -468,6 +571,10 @@ impl Page {
                 .save(&mut transaction)
                 .await?;
 
+                changes::record_affected_uuids(&[repository_id]);
+
+                cache().invalidate(&[repository_id]);
+
                 transaction.commit().await?;
 
                 Ok(())
@@ -1,12 +1,14 @@
 use async_trait::async_trait;
+use chrono::Utc;
+use schemars::JsonSchema;
 use serde::Serialize;
 use sqlx::MySqlPool;
 
 use super::{ConcreteUuid, Uuid, UuidError, UuidFetcher};
-use crate::database::Executor;
+use crate::database::{placeholders, Executor};
 use crate::datetime::DateTime;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PageRevision {
     #[serde(rename(serialize = "__typename"))]
@@ -58,3 +60,54 @@ impl UuidFetcher for PageRevision {
         })
     }
 }
+
+#[derive(sqlx::FromRow)]
+struct PageRevisionRow {
+    id: i32,
+    trashed: i8,
+    title: String,
+    content: String,
+    date: chrono::DateTime<Utc>,
+    author_id: i32,
+    page_repository_id: i32,
+}
+
+impl PageRevision {
+    pub async fn fetch_many<'a, E>(ids: &[i32], executor: E) -> Result<Vec<Uuid>, UuidError>
+    where
+        E: Executor<'a>,
+    {
+        let query = format!(
+            r#"
+                SELECT r.id, u.trashed, r.title, r.content, r.date, r.author_id, r.page_repository_id
+                    FROM page_revision r
+                    JOIN uuid u ON u.id = r.id
+                    WHERE r.id IN ({})
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, PageRevisionRow>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+
+        Ok(query
+            .fetch_all(executor)
+            .await?
+            .into_iter()
+            .map(|revision| Uuid {
+                id: revision.id,
+                trashed: revision.trashed != 0,
+                alias: format!("/entity/repository/compare/0/{}", revision.id),
+                concrete_uuid: ConcreteUuid::PageRevision(PageRevision {
+                    __typename: "PageRevision".to_string(),
+                    title: revision.title,
+                    content: revision.content,
+                    date: revision.date.into(),
+                    author_id: revision.author_id,
+                    repository_id: revision.page_repository_id,
+                }),
+            })
+            .collect())
+    }
+}
@@ -1,11 +1,14 @@
 use actix_web::HttpResponse;
 use async_trait::async_trait;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::TaxonomyTerm;
 use crate::database::Connection;
 use crate::message::MessageResponder;
 use crate::operation::{self, Operation, SuccessOutput};
+use crate::schema::{MessageSchema, MessageSchemas};
 use crate::uuid::{TaxonomyType, Uuid};
 
 #[derive(Deserialize, Serialize)]
@@ -52,11 +55,38 @@ impl MessageResponder for TaxonomyTermMessage {
     }
 }
 
+impl MessageSchemas for TaxonomyTermMessage {
+    fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema> {
+        vec![
+            MessageSchema::new::<taxonomy_term_set_name_and_description_mutation::Payload>(
+                "TaxonomyTermSetNameAndDescriptionMutation",
+                generator,
+            ),
+            MessageSchema::new::<taxonomy_term_create_mutation::Payload>(
+                "TaxonomyTermCreateMutation",
+                generator,
+            ),
+            MessageSchema::new::<taxonomy_create_entity_links_mutation::Payload>(
+                "TaxonomyCreateEntityLinksMutation",
+                generator,
+            ),
+            MessageSchema::new::<taxonomy_delete_entity_links_mutation::Payload>(
+                "TaxonomyDeleteEntityLinksMutation",
+                generator,
+            ),
+            MessageSchema::new::<taxonomy_sort_mutation::Payload>(
+                "TaxonomySortMutation",
+                generator,
+            ),
+        ]
+    }
+}
+
 pub mod taxonomy_term_set_name_and_description_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub id: i32,
         pub user_id: i32,
@@ -64,7 +94,7 @@ pub mod taxonomy_term_set_name_and_description_mutation {
         pub description: Option<String>,
     }
 
-    #[derive(Serialize)]
+    #[derive(Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct Output {
         success: bool,
@@ -92,8 +122,8 @@ pub mod taxonomy_term_set_name_and_description_mutation {
 pub mod taxonomy_term_create_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub user_id: i32,
         pub taxonomy_type: TaxonomyType,
@@ -120,8 +150,8 @@ pub mod taxonomy_term_create_mutation {
 pub mod taxonomy_create_entity_links_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub user_id: i32,
         pub entity_ids: Vec<i32>,
@@ -147,8 +177,8 @@ pub mod taxonomy_create_entity_links_mutation {
 pub mod taxonomy_delete_entity_links_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub user_id: i32,
         pub entity_ids: Vec<i32>,
@@ -174,8 +204,8 @@ pub mod taxonomy_delete_entity_links_mutation {
 pub mod taxonomy_sort_mutation {
     use super::*;
 
-    #[derive(Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         pub user_id: i32,
         pub children_ids: Vec<i32>,
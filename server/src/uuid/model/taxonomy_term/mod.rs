@@ -1,8 +1,9 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use async_trait::async_trait;
 use convert_case::{Case, Casing};
 use futures::join;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::database::HasArguments;
 use sqlx::encode::IsNull;
@@ -12,7 +13,9 @@ use sqlx::MySqlPool;
 
 use super::{AssertExists, ConcreteUuid, Uuid, UuidError, UuidFetcher};
 
-use crate::database::Executor;
+use crate::cache::cache;
+use crate::changes;
+use crate::database::{placeholders, Executor};
 use crate::event::{
     CreateTaxonomyLinkEventPayload, CreateTaxonomyTermEventPayload, RemoveTaxonomyLinkEventPayload,
     SetTaxonomyTermEventPayload,
@@ -26,7 +29,7 @@ pub use messages::*;
 
 mod messages;
 
-#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum TaxonomyType {
     Root, // Level 0
@@ -69,7 +72,7 @@ impl<'q> sqlx::Encode<'q, MySql> for TaxonomyType {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TaxonomyTerm {
     #[serde(rename(serialize = "__typename"))]
@@ -215,6 +218,39 @@ impl UuidFetcher for TaxonomyTerm {
     }
 }
 
+#[derive(sqlx::FromRow)]
+struct TaxonomyTermRow {
+    id: i32,
+    trashed: i8,
+    name: String,
+    term_type: String,
+    subdomain: String,
+    description: Option<String>,
+    weight: Option<i32>,
+    parent_id: Option<i32>,
+    taxonomy_id: i32,
+}
+
+#[derive(sqlx::FromRow)]
+struct TaxonomyEntityRow {
+    term_taxonomy_id: i32,
+    entity_id: i32,
+}
+
+#[derive(sqlx::FromRow)]
+struct TaxonomyChildRow {
+    id: i32,
+    parent_id: i32,
+}
+
+#[derive(sqlx::FromRow)]
+struct SubjectRow {
+    term_id: i32,
+    id: i32,
+    name: String,
+}
+
+#[derive(Clone)]
 pub struct Subject {
     pub taxonomy_term_id: i32,
     pub name: String,
@@ -299,6 +335,179 @@ impl TaxonomyTerm {
         }
     }
 
+    /// Batched version of [`TaxonomyTerm::fetch_canonical_subject`]. Terms without a canonical
+    /// subject are missing in the result.
+    pub async fn fetch_canonical_subjects<'a, E>(
+        ids: &[i32],
+        executor: E,
+    ) -> Result<HashMap<i32, Subject>, sqlx::Error>
+    where
+        E: Executor<'a>,
+    {
+        let ids: Vec<i32> = ids
+            .iter()
+            .copied()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let query = format!(
+            r#"
+                SELECT requested.id as term_id, t.name as name, t1.id as id
+                    FROM term_taxonomy t0
+                    JOIN term_taxonomy t1 ON t1.parent_id = t0.id
+                    LEFT JOIN term_taxonomy t2 ON t2.parent_id = t1.id
+                    LEFT JOIN term_taxonomy t3 ON t3.parent_id = t2.id
+                    LEFT JOIN term_taxonomy t4 ON t4.parent_id = t3.id
+                    LEFT JOIN term_taxonomy t5 ON t5.parent_id = t4.id
+                    LEFT JOIN term_taxonomy t6 ON t6.parent_id = t5.id
+                    LEFT JOIN term_taxonomy t7 ON t7.parent_id = t6.id
+                    LEFT JOIN term_taxonomy t8 ON t8.parent_id = t7.id
+                    LEFT JOIN term_taxonomy t9 ON t9.parent_id = t8.id
+                    LEFT JOIN term_taxonomy t10 ON t10.parent_id = t9.id
+                    LEFT JOIN term_taxonomy t11 ON t11.parent_id = t10.id
+                    LEFT JOIN term_taxonomy t12 ON t12.parent_id = t11.id
+                    LEFT JOIN term_taxonomy t13 ON t13.parent_id = t12.id
+                    LEFT JOIN term_taxonomy t14 ON t14.parent_id = t13.id
+                    LEFT JOIN term_taxonomy t15 ON t15.parent_id = t14.id
+                    LEFT JOIN term_taxonomy t16 ON t16.parent_id = t15.id
+                    LEFT JOIN term_taxonomy t17 ON t17.parent_id = t16.id
+                    LEFT JOIN term_taxonomy t18 ON t18.parent_id = t17.id
+                    LEFT JOIN term_taxonomy t19 ON t19.parent_id = t18.id
+                    LEFT JOIN term_taxonomy t20 ON t20.parent_id = t19.id
+                    JOIN term t on t1.term_id = t.id
+                    JOIN ({}) requested ON requested.id IN (
+                        t1.id, t2.id, t3.id, t4.id, t5.id, t6.id, t7.id, t8.id, t9.id, t10.id,
+                        t11.id, t12.id, t13.id, t14.id, t15.id, t16.id, t17.id, t18.id, t19.id,
+                        t20.id
+                    )
+                    WHERE
+                        t0.id = 146728 OR
+                        t0.id = 106081 OR
+                        (t0.parent_id IS NULL AND t2.id != 146728 AND t1.id != 106081)
+            "#,
+            vec!["SELECT ? AS id"; ids.len()].join(" UNION ALL ")
+        );
+        let mut query = sqlx::query_as::<_, SubjectRow>(&query);
+        for id in &ids {
+            query = query.bind(id);
+        }
+
+        let mut subjects = HashMap::new();
+        for row in query.fetch_all(executor).await? {
+            subjects.entry(row.term_id).or_insert(Subject {
+                taxonomy_term_id: row.id,
+                name: row.name,
+            });
+        }
+        Ok(subjects)
+    }
+
+    pub async fn fetch_many<'a, E>(ids: &[i32], executor: E) -> Result<Vec<Uuid>, UuidError>
+    where
+        E: Executor<'a>,
+    {
+        let mut transaction = executor.begin().await?;
+
+        let query = format!(
+            r#"
+                SELECT
+                    term_taxonomy.id, u.trashed, term.name, type.name as term_type,
+                    instance.subdomain, term_taxonomy.description, term_taxonomy.weight,
+                    term_taxonomy.parent_id, term_taxonomy.taxonomy_id
+                FROM term_taxonomy
+                JOIN term ON term.id = term_taxonomy.term_id
+                JOIN taxonomy ON taxonomy.id = term_taxonomy.taxonomy_id
+                JOIN type ON type.id = taxonomy.type_id
+                JOIN instance ON instance.id = taxonomy.instance_id
+                JOIN uuid u ON u.id = term_taxonomy.id
+                WHERE term_taxonomy.id IN ({})
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, TaxonomyTermRow>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+        let taxonomy_terms = query.fetch_all(&mut transaction).await?;
+
+        let query = format!(
+            r#"
+                SELECT term_taxonomy_id, entity_id
+                    FROM term_taxonomy_entity
+                    WHERE term_taxonomy_id IN ({})
+                    ORDER BY position ASC
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, TaxonomyEntityRow>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+        let mut entities: HashMap<i32, Vec<TaxonomyEntityRow>> = HashMap::new();
+        for entity in query.fetch_all(&mut transaction).await? {
+            entities
+                .entry(entity.term_taxonomy_id)
+                .or_default()
+                .push(entity);
+        }
+
+        let query = format!(
+            r#"
+                SELECT id, parent_id
+                    FROM term_taxonomy
+                    WHERE parent_id IN ({})
+                    ORDER BY weight ASC
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, TaxonomyChildRow>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+        let mut children: HashMap<i32, Vec<TaxonomyChildRow>> = HashMap::new();
+        for child in query.fetch_all(&mut transaction).await? {
+            children.entry(child.parent_id).or_default().push(child);
+        }
+
+        let mut subjects = Self::fetch_canonical_subjects(ids, &mut transaction).await?;
+
+        transaction.commit().await?;
+
+        taxonomy_terms
+            .into_iter()
+            .map(|taxonomy_term| {
+                let id = taxonomy_term.id;
+                Self::from_rows(
+                    taxonomy_term,
+                    entities.remove(&id).unwrap_or_default(),
+                    children.remove(&id).unwrap_or_default(),
+                    subjects.remove(&id),
+                )
+            })
+            .collect()
+    }
+
+    fn from_rows(
+        taxonomy_term: TaxonomyTermRow,
+        entities: Vec<TaxonomyEntityRow>,
+        children: Vec<TaxonomyChildRow>,
+        subject: Option<Subject>,
+    ) -> Result<Uuid, UuidError> {
+        let id = taxonomy_term.id;
+        to_taxonomy_term!(
+            id,
+            Ok::<_, sqlx::Error>(taxonomy_term),
+            Ok::<_, UuidError>(entities),
+            Ok::<_, UuidError>(children),
+            Ok::<_, UuidError>(subject)
+        )
+    }
+
     fn normalize_type(typename: &str) -> String {
         typename.to_case(Case::Camel)
     }
@@ -323,6 +532,7 @@ impl TaxonomyTerm {
         .fetch_optional(executor)
         .await?
         .ok_or(operation::Error::BadRequest {
+            code: operation::ErrorCode::TaxonomyTermNotFound,
             reason: format!("Taxonomy term with id {term_taxonomy_id} does not exist"),
         })?
         .instance_id)
@@ -350,6 +560,7 @@ impl TaxonomyTerm {
         .fetch_optional(&mut transaction)
         .await?
         .ok_or(operation::Error::BadRequest {
+            code: operation::ErrorCode::TaxonomyTermNotFound,
             reason: format!("Taxonomy term with id {} does not exist", payload.id),
         })?;
 
@@ -368,17 +579,17 @@ impl TaxonomyTerm {
             sqlx::Error::Database(db_error) => {
                 if db_error.message().contains("uq_term_name_language") {
                     return operation::Error::BadRequest {
+                        code: operation::ErrorCode::DuplicateTaxonomyTermName,
                         reason: "Two taxonomy terms cannot have same name in same instance"
                             .to_string(),
                     };
                 };
                 operation::Error::InternalServerError {
+                    code: operation::ErrorCode::DatabaseError,
                     error: Box::new(db_error),
                 }
             }
-            _ => operation::Error::InternalServerError {
-                error: Box::new(error),
-            },
+            _ => error.into(),
         })?;
 
         sqlx::query!(
@@ -397,6 +608,11 @@ impl TaxonomyTerm {
             .save(&mut transaction)
             .await?;
 
+        changes::record_affected_uuids(&[payload.id]);
+
+        cache().invalidate(&[payload.id]);
+        cache().invalidate_taxonomy();
+
         transaction.commit().await?;
 
         Ok(())
@@ -508,6 +724,11 @@ impl TaxonomyTerm {
 
         let taxonomy_term = Self::fetch_via_transaction(taxonomy_term_id, &mut transaction).await?;
 
+        changes::record_affected_uuids(&[payload.parent_id]);
+
+        cache().invalidate(&[payload.parent_id]);
+        cache().invalidate_taxonomy();
+
         transaction.commit().await?;
 
         Ok(taxonomy_term)
@@ -535,6 +756,7 @@ impl TaxonomyTerm {
         .fetch_optional(&mut transaction)
         .await?
         .ok_or(operation::Error::BadRequest {
+            code: operation::ErrorCode::TaxonomyTermNotFound,
             reason: "Given target id is no taxonomy term".to_string(),
         })?;
         let term_type: TaxonomyType = taxonomy.term_type.parse()?;
@@ -547,12 +769,14 @@ impl TaxonomyTerm {
             let entity_type = Entity::fetch_entity_type(*child_id, &mut transaction)
                 .await?
                 .ok_or(operation::Error::BadRequest {
+                    code: operation::ErrorCode::EntityNotFound,
                     reason: format!("entity with id {child_id} does not exist"),
                 })?;
 
             match entity_type {
                 EntityType::CoursePage | EntityType::GroupedExercise | EntityType::Solution => {
                     return Err(operation::Error::BadRequest {
+                        code: operation::ErrorCode::InvalidTaxonomyLink,
                         reason: format!(
                             "entity with id {child_id} cannot be linked to a taxonomy term"
                         ),
@@ -561,6 +785,7 @@ impl TaxonomyTerm {
                 EntityType::Exercise | EntityType::ExerciseGroup => {
                     if !taxonomy_is_folder {
                         return Err(operation::Error::BadRequest {
+                            code: operation::ErrorCode::InvalidTaxonomyLink,
                             reason: format!(
                                 "entity with id {child_id} is an exercise or exercise group and can only be linked to a topic folder"
                             )
@@ -570,6 +795,7 @@ impl TaxonomyTerm {
                 _ => {
                     if taxonomy_is_folder {
                         return Err(operation::Error::BadRequest {
+                            code: operation::ErrorCode::InvalidTaxonomyLink,
                             reason: format!(
                                 "entity with id {child_id} cannot be linked to a topic folder"
                             ),
@@ -608,6 +834,7 @@ impl TaxonomyTerm {
 
             if taxonomy.instance_id != child_instance_id {
                 return Err(operation::Error::BadRequest {
+                    code: operation::ErrorCode::InvalidInstance,
                     reason: format!(
                         "Entity {} and taxonomy term {} are not in the same instance",
                         child_id, payload.taxonomy_term_id
@@ -650,6 +877,13 @@ impl TaxonomyTerm {
             .await?;
         }
 
+        changes::record_affected_uuids(&[payload.taxonomy_term_id]);
+
+        cache().invalidate(&[payload.taxonomy_term_id]);
+        changes::record_affected_uuids(&payload.entity_ids);
+        cache().invalidate(&payload.entity_ids);
+        cache().invalidate_taxonomy();
+
         transaction.commit().await?;
 
         Ok(())
@@ -679,6 +913,7 @@ impl TaxonomyTerm {
             .fetch_optional(&mut transaction)
             .await?
             .ok_or(operation::Error::BadRequest {
+                code: operation::ErrorCode::InvalidTaxonomyLink,
                 reason: format!(
                     "Id {} is not linked to taxonomy term {}",
                     child_id, payload.taxonomy_term_id
@@ -698,6 +933,7 @@ impl TaxonomyTerm {
             .quantity as i32
             {
                 return Err(operation::Error::BadRequest {
+                    code: operation::ErrorCode::InvalidTaxonomyLink,
                     reason: format!(
                         "Entity with id {child_id} has to be linked to at least one taxonomy"
                     ),
@@ -721,6 +957,13 @@ impl TaxonomyTerm {
             .await?;
         }
 
+        changes::record_affected_uuids(&[payload.taxonomy_term_id]);
+
+        cache().invalidate(&[payload.taxonomy_term_id]);
+        changes::record_affected_uuids(&payload.entity_ids);
+        cache().invalidate(&payload.entity_ids);
+        cache().invalidate_taxonomy();
+
         transaction.commit().await?;
 
         Ok(())
@@ -762,6 +1005,7 @@ impl TaxonomyTerm {
             .is_subset(&HashSet::<i32>::from_iter(children_ids.clone()))
         {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::InvalidChildren,
                 reason: "children_ids have to be a subset of children entities and taxonomy terms of the given taxonomy term".to_string()
             });
         }
@@ -832,6 +1076,11 @@ impl TaxonomyTerm {
             .save(&mut transaction)
             .await?;
 
+        changes::record_affected_uuids(&[payload.taxonomy_term_id]);
+
+        cache().invalidate(&[payload.taxonomy_term_id]);
+        cache().invalidate_taxonomy();
+
         transaction.commit().await?;
 
         Ok(())
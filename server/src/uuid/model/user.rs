@@ -1,14 +1,18 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
+use chrono::Utc;
 use futures::join;
+use schemars::JsonSchema;
 use serde::Serialize;
 use sqlx::MySqlPool;
 
 use super::{ConcreteUuid, Uuid, UuidError, UuidFetcher};
-use crate::database::Executor;
+use crate::database::{placeholders, Executor};
 use crate::datetime::DateTime;
 use crate::format_alias;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct User {
     #[serde(rename(serialize = "__typename"))]
@@ -100,7 +104,80 @@ impl UuidFetcher for User {
     }
 }
 
+#[derive(sqlx::FromRow)]
+struct UserRow {
+    id: i32,
+    trashed: i8,
+    username: String,
+    date: chrono::DateTime<Utc>,
+    last_login: Option<chrono::DateTime<Utc>>,
+    description: Option<String>,
+}
+
 impl User {
+    pub async fn fetch_many<'a, E>(ids: &[i32], executor: E) -> Result<Vec<Uuid>, UuidError>
+    where
+        E: Executor<'a>,
+    {
+        let mut transaction = executor.begin().await?;
+
+        let query = format!(
+            r#"
+                SELECT user.id, trashed, username, date, last_login, description
+                    FROM user
+                    JOIN uuid ON user.id = uuid.id
+                    WHERE user.id IN ({})
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, UserRow>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+        let users = query.fetch_all(&mut transaction).await?;
+
+        let query = format!(
+            r#"
+                SELECT ru.user_id, r.name
+                    FROM role r
+                    JOIN role_user ru on r.id = ru.role_id
+                    WHERE ru.user_id IN ({})
+            "#,
+            placeholders(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, (i32, String)>(&query);
+        for id in ids {
+            query = query.bind(id);
+        }
+        let mut roles: HashMap<i32, Vec<String>> = HashMap::new();
+        for (user_id, name) in query.fetch_all(&mut transaction).await? {
+            roles.entry(user_id).or_default().push(name);
+        }
+
+        transaction.commit().await?;
+
+        Ok(users
+            .into_iter()
+            .map(|user| Uuid {
+                id: user.id,
+                trashed: user.trashed != 0,
+                alias: format_alias(
+                    Self::get_context().as_deref(),
+                    user.id,
+                    Some(&user.username),
+                ),
+                concrete_uuid: ConcreteUuid::User(User {
+                    __typename: "User".to_string(),
+                    username: user.username,
+                    date: user.date.into(),
+                    last_login: user.last_login.map(|date| date.into()),
+                    description: user.description,
+                    roles: roles.remove(&user.id).unwrap_or_default(),
+                }),
+            })
+            .collect())
+    }
+
     pub fn get_context() -> Option<String> {
         Some("user".to_string())
     }
@@ -1,6 +1,9 @@
+use std::collections::HashMap;
+
 use crate::operation;
 use crate::uuid::messages::uuid_set_state_mutation;
 use async_trait::async_trait;
+use schemars::JsonSchema;
 use serde::Serialize;
 use sqlx::MySqlPool;
 use thiserror::Error;
@@ -11,11 +14,13 @@ use super::{
     entity_revision::EntityRevision, page::Page, page_revision::PageRevision,
     taxonomy_term::TaxonomyTerm, user::User,
 };
-use crate::database::Executor;
+use crate::cache::cache;
+use crate::changes;
+use crate::database::{placeholders, Executor};
 use crate::event::SetUuidStateEventPayload;
 use crate::instance::Instance;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct Uuid {
     pub id: i32,
     pub trashed: bool,
@@ -24,7 +29,7 @@ pub struct Uuid {
     pub concrete_uuid: ConcreteUuid,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(untagged)]
 pub enum ConcreteUuid {
     Attachment,
@@ -67,16 +72,28 @@ impl From<sqlx::Error> for UuidError {
 impl From<UuidError> for operation::Error {
     fn from(error: UuidError) -> Self {
         match error {
-            UuidError::UnsupportedDiscriminator { .. }
-            | UuidError::UnsupportedEntityType { .. }
-            | UuidError::UnsupportedEntityRevisionType { .. }
-            | UuidError::EntityMissingRequiredParent
-            | UuidError::NotFound => operation::Error::NotFoundError,
-            UuidError::DatabaseError { .. } | UuidError::InvalidInstance => {
-                operation::Error::InternalServerError {
-                    error: Box::new(error),
+            UuidError::UnsupportedDiscriminator { discriminator } => {
+                operation::Error::NotFoundError {
+                    code: operation::ErrorCode::UnsupportedDiscriminator { discriminator },
                 }
             }
+            UuidError::UnsupportedEntityType { name } => operation::Error::NotFoundError {
+                code: operation::ErrorCode::UnsupportedEntityType { name },
+            },
+            UuidError::UnsupportedEntityRevisionType { name } => operation::Error::NotFoundError {
+                code: operation::ErrorCode::UnsupportedEntityRevisionType { name },
+            },
+            UuidError::EntityMissingRequiredParent => operation::Error::NotFoundError {
+                code: operation::ErrorCode::EntityMissingParent,
+            },
+            UuidError::NotFound => operation::Error::NotFoundError {
+                code: operation::ErrorCode::UuidNotFound,
+            },
+            UuidError::DatabaseError { inner } => inner.into(),
+            UuidError::InvalidInstance => operation::Error::InternalServerError {
+                code: operation::ErrorCode::InvalidInstance,
+                error: Box::new(error),
+            },
         }
     }
 }
@@ -101,6 +118,7 @@ pub trait AssertExists: UuidFetcher {
     {
         if let Err(UuidError::NotFound) = Self::fetch_via_transaction(id, executor).await {
             return Err(operation::Error::BadRequest {
+                code: operation::ErrorCode::UuidNotFound,
                 reason: format!("Id {id} does not exist or does not correspond to the type"),
             });
         }
@@ -183,6 +201,109 @@ impl UuidFetcher for Uuid {
 }
 
 impl Uuid {
+    /// Fetches many UUIDs at once. The ids are grouped by their discriminator so that each group
+    /// can be loaded with `WHERE id IN (...)` queries. Ids which cannot be fetched map to `None`.
+    pub async fn fetch_many<'a, E>(
+        ids: &[i32],
+        executor: E,
+    ) -> Result<HashMap<i32, Option<Uuid>>, UuidError>
+    where
+        E: Executor<'a>,
+    {
+        let mut uuids: HashMap<i32, Option<Uuid>> = ids.iter().map(|id| (*id, None)).collect();
+
+        if uuids.is_empty() {
+            return Ok(uuids);
+        }
+
+        let mut transaction = executor.begin().await?;
+
+        let ids: Vec<i32> = uuids.keys().copied().collect();
+        let groups = fetch_discriminators(&ids, &mut transaction).await?;
+
+        for (discriminator, ids) in groups {
+            let fetched = match discriminator {
+                Discriminator::Attachment => Attachment::fetch_many(&ids, &mut transaction).await?,
+                Discriminator::BlogPost => BlogPost::fetch_many(&ids, &mut transaction).await?,
+                Discriminator::Comment => Comment::fetch_many(&ids, &mut transaction).await?,
+                Discriminator::Entity => Entity::fetch_many(&ids, &mut transaction).await?,
+                Discriminator::EntityRevision => {
+                    EntityRevision::fetch_many(&ids, &mut transaction).await?
+                }
+                Discriminator::Page => Page::fetch_many(&ids, &mut transaction).await?,
+                Discriminator::PageRevision => {
+                    PageRevision::fetch_many(&ids, &mut transaction).await?
+                }
+                Discriminator::TaxonomyTerm => {
+                    TaxonomyTerm::fetch_many(&ids, &mut transaction).await?
+                }
+                Discriminator::User => User::fetch_many(&ids, &mut transaction).await?,
+            };
+
+            for uuid in fetched {
+                uuids.insert(uuid.id, Some(uuid));
+            }
+        }
+
+        transaction.commit().await?;
+
+        Ok(uuids)
+    }
+
+    /// Batched version of [`Uuid::fetch_context`]. Ids which do not exist are missing in the
+    /// result.
+    pub async fn fetch_contexts<'a, E>(
+        ids: &[i32],
+        executor: E,
+    ) -> Result<HashMap<i32, Option<String>>, UuidError>
+    where
+        E: Executor<'a>,
+    {
+        let mut contexts = HashMap::new();
+
+        if ids.is_empty() {
+            return Ok(contexts);
+        }
+
+        let mut transaction = executor.begin().await?;
+
+        for (discriminator, ids) in fetch_discriminators(ids, &mut transaction).await? {
+            let subjects = match discriminator {
+                Discriminator::Entity => {
+                    Entity::fetch_canonical_subjects(&ids, &mut transaction).await?
+                }
+                Discriminator::EntityRevision => {
+                    EntityRevision::fetch_canonical_subjects(&ids, &mut transaction).await?
+                }
+                Discriminator::TaxonomyTerm => {
+                    TaxonomyTerm::fetch_canonical_subjects(&ids, &mut transaction).await?
+                }
+                _ => HashMap::new(),
+            };
+
+            for id in ids {
+                let context = match discriminator {
+                    Discriminator::Attachment => Attachment::get_context(),
+                    Discriminator::BlogPost => BlogPost::get_context(),
+                    Discriminator::Comment => None,
+                    Discriminator::Page => None,
+                    Discriminator::PageRevision => None,
+                    Discriminator::User => User::get_context(),
+                    Discriminator::Entity
+                    | Discriminator::EntityRevision
+                    | Discriminator::TaxonomyTerm => {
+                        subjects.get(&id).map(|subject| subject.name.clone())
+                    }
+                };
+                contexts.insert(id, context);
+            }
+        }
+
+        transaction.commit().await?;
+
+        Ok(contexts)
+    }
+
     pub async fn fetch_context(id: i32, pool: &MySqlPool) -> Result<Option<String>, UuidError> {
         let uuid = fetch_one_uuid!(id, pool)?;
         let discriminator = get_discriminator!(uuid)?;
@@ -250,6 +371,60 @@ impl Uuid {
     }
 }
 
+/// Groups the given ids by their discriminator. Ids which do not exist are left out.
+async fn fetch_discriminators<'a, E>(
+    ids: &[i32],
+    executor: E,
+) -> Result<HashMap<Discriminator, Vec<i32>>, UuidError>
+where
+    E: Executor<'a>,
+{
+    let query = format!(
+        "SELECT id, discriminator FROM uuid WHERE id IN ({})",
+        placeholders(ids.len())
+    );
+    let mut query = sqlx::query_as::<_, (i32, String)>(&query);
+    for id in ids {
+        query = query.bind(*id);
+    }
+
+    let mut groups: HashMap<Discriminator, Vec<i32>> = HashMap::new();
+    for (id, discriminator) in query.fetch_all(executor).await? {
+        match discriminator.parse::<Discriminator>() {
+            Ok(discriminator) => groups.entry(discriminator).or_default().push(id),
+            Err(_) => {
+                tracing::warn!(
+                    id,
+                    discriminator,
+                    "skipped uuid with unsupported discriminator"
+                )
+            }
+        }
+    }
+    Ok(groups)
+}
+
+/// Turns a UUID which cannot be fetched (e.g. because it is missing or an entity lacks its parent)
+/// into `None`, so that a single broken UUID does not fail the whole batch. Only errors of the
+/// database abort the batch.
+pub(super) fn skip_unfetchable(
+    id: i32,
+    result: Result<Uuid, UuidError>,
+) -> Result<Option<Uuid>, UuidError> {
+    match result {
+        Ok(uuid) => Ok(Some(uuid)),
+        Err(UuidError::NotFound)
+        | Err(UuidError::DatabaseError {
+            inner: sqlx::Error::RowNotFound,
+        }) => Ok(None),
+        Err(error @ UuidError::DatabaseError { .. }) => Err(error),
+        Err(error) => {
+            tracing::warn!(id, %error, "skipped uuid which cannot be fetched");
+            Ok(None)
+        }
+    }
+}
+
 impl Uuid {
     pub async fn set_uuid_state<'a, E>(
         payload: &uuid_set_state_mutation::Payload,
@@ -293,6 +468,7 @@ impl Uuid {
                     // But we can leave it as kind of reminder in case the query is wrongly refactored.
                     if uuid.discriminator == "entityRevision" || uuid.discriminator == "user" {
                         return Err(operation::Error::BadRequest {
+                            code: operation::ErrorCode::UuidNotTrashable,
                             reason: format!(
                                 "uuid {} with type \"{}\" cannot be deleted via a setState mutation",
                                 id,
@@ -307,11 +483,15 @@ impl Uuid {
                     }
                     let instance: Instance = uuid.subdomain.parse().map_err(|error| {
                         operation::Error::InternalServerError {
+                            code: operation::ErrorCode::InvalidInstance,
                             error: Box::new(error),
                         }
                     })?;
 
                     Uuid::set_state(*id, payload.trashed, &mut transaction).await?;
+                    if uuid.discriminator == "taxonomyTerm" {
+                        cache().invalidate_taxonomy();
+                    }
 
                     SetUuidStateEventPayload::new(payload.trashed, payload.user_id, *id, instance)
                         .save(&mut transaction)
@@ -319,6 +499,7 @@ impl Uuid {
                 }
                 Err(sqlx::Error::RowNotFound) => {
                     return Err(operation::Error::BadRequest {
+                        code: operation::ErrorCode::UuidNotTrashable,
                         reason: "Uuid does not exist or cannot be trashed".to_string(),
                     })
                 }
@@ -341,6 +522,9 @@ impl Uuid {
     where
         E: Executor<'a>,
     {
+        changes::record_affected_uuids(&[id]);
+        cache().invalidate(&[id]);
+
         sqlx::query!("UPDATE uuid SET trashed = ? WHERE id = ?", trashed, id)
             .execute(executor)
             .await
@@ -1,10 +1,13 @@
 use actix_web::HttpResponse;
 use async_trait::async_trait;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::database::Connection;
 use crate::message::MessageResponder;
 use crate::operation::{self, Operation};
+use crate::schema::{MessageSchema, MessageSchemas};
 
 use super::model::{Vocabulary, VocabularyError};
 
@@ -26,13 +29,22 @@ impl MessageResponder for VocabularyMessage {
     }
 }
 
+impl MessageSchemas for VocabularyMessage {
+    fn message_schemas(generator: &mut SchemaGenerator) -> Vec<MessageSchema> {
+        vec![MessageSchema::new::<taxonomy_vocabulary_query::Payload>(
+            "VocabularyTaxonomyQuery",
+            generator,
+        )]
+    }
+}
+
 pub mod taxonomy_vocabulary_query {
     use crate::instance::Instance;
 
     use super::*;
 
-    #[derive(Debug, Deserialize, Serialize)]
-    #[serde(rename_all = "camelCase")]
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
     pub struct Payload {
         instance: Instance,
     }
@@ -57,6 +69,7 @@ pub mod taxonomy_vocabulary_query {
     impl From<VocabularyError> for operation::Error {
         fn from(error: VocabularyError) -> Self {
             Self::InternalServerError {
+                code: operation::ErrorCode::InternalServerError,
                 error: Box::new(error),
             }
         }
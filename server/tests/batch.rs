@@ -0,0 +1,95 @@
+mod batch {
+    use actix_web::{test, App};
+    use test_utils::{assert_eq, *};
+
+    use server::{configure_app, create_database_pool};
+
+    #[actix_rt::test]
+    async fn returns_results_in_order() {
+        let pool = create_database_pool().await.unwrap();
+        let app = test::init_service(configure_app(App::new(), pool)).await;
+        let req = test::TestRequest::post()
+            .uri("/batch")
+            .set_json(json!([
+                { "type": "UuidQuery", "payload": { "id": 1 } },
+                { "type": "UuidQuery", "payload": { "id": 1_000_000_000 } },
+                { "type": "UuidQuery", "payload": { "id": 1855 } }
+            ]))
+            .to_request();
+
+        let result: Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_has_length(&result, 3);
+        assert_eq!(result[0]["status"], 200);
+        assert_eq!(result[0]["body"]["__typename"], "User");
+        assert_eq!(result[1]["status"], 404);
+        assert_eq!(result[1]["body"]["code"], "UUID_NOT_FOUND");
+        assert_eq!(result[2]["status"], 200);
+        assert_eq!(result[2]["body"]["id"], 1855);
+    }
+
+    #[actix_rt::test]
+    async fn stops_at_first_failure_in_transaction() {
+        let pool = create_database_pool().await.unwrap();
+        let app = test::init_service(configure_app(App::new(), pool)).await;
+        let req = test::TestRequest::post()
+            .uri("/batch")
+            .insert_header(("Transaction", "true"))
+            .insert_header(("Rollback", "true"))
+            .set_json(json!([
+                {
+                    "type": "UuidSetStateMutation",
+                    "payload": { "ids": [1855], "userId": 1, "trashed": true }
+                },
+                {
+                    "type": "UuidSetStateMutation",
+                    "payload": { "ids": [1], "userId": 1, "trashed": true }
+                },
+                { "type": "UuidQuery", "payload": { "id": 1855 } }
+            ]))
+            .to_request();
+
+        let result: Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_has_length(&result, 2);
+        assert_eq!(result[0]["status"], 200);
+        assert_eq!(result[1]["status"], 400);
+    }
+
+    #[actix_rt::test]
+    async fn rejects_too_many_messages() {
+        let pool = create_database_pool().await.unwrap();
+        let app = test::init_service(configure_app(App::new(), pool)).await;
+        let messages = vec![json!({ "type": "UuidQuery", "payload": { "id": 1 } }); 101];
+        let req = test::TestRequest::post()
+            .uri("/batch")
+            .set_json(messages)
+            .to_request();
+
+        let response = test::call_service(&app, req).await;
+
+        assert_eq!(response.status(), 400);
+        let body: Value = test::read_body_json(response).await;
+        assert_eq!(body["code"], "INVALID_PARAMETER");
+    }
+
+    #[actix_rt::test]
+    async fn rejects_idempotency_key() {
+        let pool = create_database_pool().await.unwrap();
+        let app = test::init_service(configure_app(App::new(), pool)).await;
+        let req = test::TestRequest::post()
+            .uri("/batch")
+            .insert_header(("Idempotency-Key", "batch-key"))
+            .set_json(json!([{
+                "type": "UuidSetStateMutation",
+                "payload": { "ids": [1855], "userId": 1, "trashed": true }
+            }]))
+            .to_request();
+
+        let response = test::call_service(&app, req).await;
+
+        assert_eq!(response.status(), 400);
+        let body: Value = test::read_body_json(response).await;
+        assert_eq!(body["code"], "INVALID_PARAMETER");
+    }
+}
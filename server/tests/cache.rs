@@ -0,0 +1,14 @@
+mod cache_stats_query {
+    use test_utils::{assert_eq, *};
+
+    #[actix_rt::test]
+    async fn returns_statistics_of_cache() {
+        Message::new("CacheStatsQuery", Value::Null)
+            .execute()
+            .await
+            .should_be_ok_with(|result| {
+                assert_eq!(result["enabled"], false);
+                assert_eq!(result["size"], 0);
+            });
+    }
+}
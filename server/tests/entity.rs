@@ -731,7 +731,7 @@ mod deleted_entities_query {
         )
         .execute()
         .await
-        .should_be_bad_request();
+        .should_be_bad_request_with_code("INVALID_PARAMETER");
     }
 }
 
@@ -752,7 +752,9 @@ mod set_license_mutation {
         )
         .execute_on(&mut transaction)
         .await
-        .should_be_ok_with_body(json!({ "success": true, }));
+        .should_be_ok_with(
+            |result| assert_json_include!(actual: result, expected: json!({ "success": true })),
+        );
 
         Message::new("UuidQuery", json!({ "id": entity_id }))
             .execute_on(&mut transaction)
@@ -786,7 +788,7 @@ mod set_license_mutation {
         )
         .execute()
         .await
-        .should_be_bad_request();
+        .should_be_bad_request_with_code("ENTITY_NOT_FOUND");
     }
 
     #[actix_rt::test]
@@ -797,7 +799,7 @@ mod set_license_mutation {
         )
         .execute()
         .await
-        .should_be_bad_request();
+        .should_be_bad_request_with_code("USER_NOT_FOUND");
     }
 
     #[actix_rt::test]
@@ -846,7 +848,9 @@ mod sort_mutation {
         )
         .execute_on(&mut transaction)
         .await
-        .should_be_ok_with_body(json!({ "success": true }));
+        .should_be_ok_with(
+            |result| assert_json_include!(actual: result, expected: json!({ "success": true })),
+        );
 
         Message::new("UuidQuery", json!({ "id": entity_id }))
             .execute_on(&mut transaction)
@@ -869,7 +873,9 @@ mod sort_mutation {
         )
         .execute_on(&mut transaction)
         .await
-        .should_be_ok_with_body(json!({ "success": true }));
+        .should_be_ok_with(
+            |result| assert_json_include!(actual: result, expected: json!({ "success": true })),
+        );
 
         Message::new("UuidQuery", json!({ "id": entity_id }))
             .execute_on(&mut transaction)
@@ -0,0 +1,107 @@
+mod event_stream {
+    use std::future::poll_fn;
+    use std::pin::Pin;
+
+    use actix_web::body::MessageBody;
+    use actix_web::{test, App};
+    use test_utils::{assert_eq, *};
+
+    use server::event_stream::EventFeed;
+    use server::{configure_app, create_database_pool};
+
+    /// Opens the stream at `uri` and returns the data of its first `count` messages.
+    async fn read_messages(uri: &str, last_event_id: &str, count: usize) -> Vec<Value> {
+        let pool = create_database_pool().await.unwrap();
+        let feed = EventFeed::start(pool.clone(), &Default::default())
+            .await
+            .unwrap();
+        let app = test::init_service(configure_app(App::new(), pool).app_data(feed)).await;
+
+        let req = test::TestRequest::get()
+            .uri(uri)
+            .insert_header(("Last-Event-ID", last_event_id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            resp.headers().get("Content-Type").unwrap(),
+            "text/event-stream"
+        );
+
+        let mut body = resp.into_body();
+        let mut messages = Vec::new();
+        while messages.len() < count {
+            let chunk = poll_fn(|cx| Pin::new(&mut body).poll_next(cx))
+                .await
+                .unwrap()
+                .unwrap();
+            let chunk = String::from_utf8(chunk.to_vec()).unwrap();
+            let data = chunk
+                .lines()
+                .find_map(|line| line.strip_prefix("data: "))
+                .unwrap();
+            let data: Value = serde_json::from_str(data).unwrap();
+
+            assert!(chunk.starts_with(&format!("id: {}\n", data["id"])));
+            messages.push(data);
+        }
+
+        messages
+    }
+
+    #[actix_rt::test]
+    async fn sends_events_after_last_event_id() {
+        let messages = read_messages("/events/stream", "86590", 1).await;
+
+        assert_eq!(
+            messages[0],
+            json!({
+                "__typename": "SetTaxonomyTermNotificationEvent",
+                "id": 86591,
+                "instance": "en",
+                "date": "2020-06-16T12:50:13+02:00",
+                "actorId": 1,
+                "objectId": 35607,
+                "taxonomyTermId": 35607
+            })
+        );
+    }
+
+    #[actix_rt::test]
+    async fn sends_same_events_as_events_query_with_filters() {
+        let mut expected = Message::new("EventsQuery", json!({ "first": 5, "objectId": 35607 }))
+            .execute()
+            .await
+            .get_json()["events"]
+            .as_array()
+            .unwrap()
+            .clone();
+        expected.reverse();
+        let last_event_id = (expected[0]["id"].as_i64().unwrap() - 1).to_string();
+
+        let messages = read_messages(
+            "/events/stream?objectId=35607",
+            &last_event_id,
+            expected.len(),
+        )
+        .await;
+
+        assert_eq!(messages, expected);
+    }
+
+    #[actix_rt::test]
+    async fn fails_for_unknown_filter() {
+        let pool = create_database_pool().await.unwrap();
+        let feed = EventFeed::start(pool.clone(), &Default::default())
+            .await
+            .unwrap();
+        let app = test::init_service(configure_app(App::new(), pool).app_data(feed)).await;
+
+        let req = test::TestRequest::get()
+            .uri("/events/stream?userId=1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), 400);
+    }
+}
@@ -0,0 +1,59 @@
+mod ready {
+    use std::time::Duration;
+
+    use actix_web::{test, App};
+    use sqlx::mysql::MySqlPoolOptions;
+    use test_utils::{assert_eq, *};
+
+    use server::config::Config;
+    use server::{configure_app, create_database_pool};
+
+    #[actix_rt::test]
+    async fn is_ready_when_database_is_available() {
+        let pool = create_database_pool().await.unwrap();
+        let app = test::init_service(configure_app(App::new(), pool)).await;
+
+        let req = test::TestRequest::get()
+            .uri("/.well-known/ready")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), 200);
+
+        let body: Value = test::read_body_json(resp).await;
+
+        assert_eq!(body["ready"], true);
+        assert_eq!(body["pool"]["saturated"], false);
+        assert!(body["checks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|check| check["ok"] == true && check.get("missing").is_none()));
+    }
+
+    #[actix_rt::test]
+    async fn is_not_ready_when_pool_has_no_free_connection() {
+        let pool = MySqlPoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(60))
+            .connect_with(Config::load().unwrap().database.connect_options().unwrap())
+            .await
+            .unwrap();
+        let _connection = pool.acquire().await.unwrap();
+        let app = test::init_service(configure_app(App::new(), pool.clone())).await;
+
+        let req = test::TestRequest::get()
+            .uri("/.well-known/ready")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), 503);
+
+        let body: Value = test::read_body_json(resp).await;
+
+        assert_eq!(body["ready"], false);
+        assert_eq!(body["pool"]["saturated"], true);
+        assert_eq!(body["checks"][0]["name"], "database");
+        assert_eq!(body["checks"][0]["ok"], false);
+    }
+}
@@ -0,0 +1,130 @@
+mod idempotency_key {
+    use actix_web::{test, App};
+    use test_utils::{assert_eq, *};
+
+    use server::{configure_app, create_database_pool};
+
+    fn random_key() -> String {
+        format!("test-{}", rand::random::<u64>())
+    }
+
+    // Trashing a user fails, so the test database is not changed.
+    fn failing_mutation() -> Value {
+        json!({
+            "type": "UuidSetStateMutation",
+            "payload": { "ids": [1], "userId": 1, "trashed": true }
+        })
+    }
+
+    #[actix_rt::test]
+    async fn replays_stored_response_for_repeated_key() {
+        let pool = create_database_pool().await.unwrap();
+        let app = test::init_service(configure_app(App::new(), pool)).await;
+        let key = random_key();
+
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header(("Idempotency-Key", key.as_str()))
+            .set_json(failing_mutation())
+            .to_request();
+        let first = test::call_service(&app, req).await;
+
+        assert_eq!(first.status(), 400);
+        assert!(first.headers().get("Idempotency-Replayed").is_none());
+
+        let first_body: Value = test::read_body_json(first).await;
+
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header(("Idempotency-Key", key.as_str()))
+            .set_json(failing_mutation())
+            .to_request();
+        let second = test::call_service(&app, req).await;
+
+        assert_eq!(second.status(), 400);
+        assert_eq!(
+            second.headers().get("Idempotency-Replayed").unwrap(),
+            "true"
+        );
+
+        let second_body: Value = test::read_body_json(second).await;
+
+        assert_eq!(first_body, second_body);
+    }
+
+    #[actix_rt::test]
+    async fn fails_when_key_is_reused_for_another_message() {
+        let pool = create_database_pool().await.unwrap();
+        let app = test::init_service(configure_app(App::new(), pool)).await;
+        let key = random_key();
+
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header(("Idempotency-Key", key.as_str()))
+            .set_json(failing_mutation())
+            .to_request();
+        test::call_service(&app, req).await;
+
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header(("Idempotency-Key", key.as_str()))
+            .set_json(json!({
+                "type": "UuidSetStateMutation",
+                "payload": { "ids": [1], "userId": 1, "trashed": false }
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), 422);
+
+        let body: Value = test::read_body_json(resp).await;
+
+        assert_eq!(body["code"], "IDEMPOTENCY_KEY_REUSED");
+    }
+
+    #[actix_rt::test]
+    async fn takes_over_reservation_whose_lease_has_run_out() {
+        let pool = create_database_pool().await.unwrap();
+        let key = random_key();
+        sqlx::query(
+            r#"
+                INSERT INTO idempotency_key (`key`, fingerprint, created_at)
+                    VALUES (?, ?, NOW() - INTERVAL 1 HOUR)
+            "#,
+        )
+        .bind(&key)
+        .bind("0".repeat(32))
+        .execute(&pool)
+        .await
+        .unwrap();
+        let app = test::init_service(configure_app(App::new(), pool)).await;
+
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header(("Idempotency-Key", key.as_str()))
+            .set_json(failing_mutation())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), 400);
+        assert!(resp.headers().get("Idempotency-Replayed").is_none());
+    }
+
+    #[actix_rt::test]
+    async fn ignores_key_for_queries() {
+        let pool = create_database_pool().await.unwrap();
+        let app = test::init_service(configure_app(App::new(), pool)).await;
+        let key = random_key();
+
+        for id in [1, 1855] {
+            let req = test::TestRequest::post()
+                .uri("/")
+                .insert_header(("Idempotency-Key", key.as_str()))
+                .set_json(json!({ "type": "UuidQuery", "payload": { "id": id } }))
+                .to_request();
+            let result: Value = test::call_and_read_body_json(&app, req).await;
+
+            assert_eq!(result["id"], id);
+        }
+    }
+}
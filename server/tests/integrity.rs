@@ -0,0 +1,58 @@
+mod integrity_rules {
+    use server::integrity::find_rule;
+    use server::uuid::EntityType;
+    use test_utils::{assert_eq, *};
+
+    #[actix_rt::test]
+    async fn reports_solution_without_parent() {
+        let mut transaction = begin_transaction().await;
+
+        let solution = EntityBuilder::new(EntityType::Solution)
+            .revision(1, &[("content", "{}")])
+            .build(&mut transaction)
+            .await
+            .unwrap();
+
+        let violations = find_rule("entity-without-required-parent")
+            .unwrap()
+            .check(&mut transaction)
+            .await
+            .unwrap();
+
+        assert!(violations.ids.contains(&solution.id));
+    }
+
+    #[actix_rt::test]
+    async fn reports_current_revision_of_other_entity() {
+        let mut transaction = begin_transaction().await;
+
+        let article = EntityBuilder::new(EntityType::Article)
+            .revision(1, &[("title", "Article")])
+            .build(&mut transaction)
+            .await
+            .unwrap();
+        let other_article = EntityBuilder::new(EntityType::Article)
+            .build(&mut transaction)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE entity SET current_revision_id = ? WHERE id = ?")
+            .bind(article.revision_ids[0])
+            .bind(other_article.id)
+            .execute(&mut transaction)
+            .await
+            .unwrap();
+
+        let violations = find_rule("entity-current-revision-of-other-repository")
+            .unwrap()
+            .check(&mut transaction)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            violations.rule,
+            "entity-current-revision-of-other-repository"
+        );
+        assert!(violations.ids.contains(&other_article.id));
+        assert!(!violations.ids.contains(&article.id));
+    }
+}
@@ -0,0 +1,76 @@
+mod message {
+    use actix_web::{test, App};
+    use test_utils::{assert_eq, *};
+
+    use server::{configure_app, create_database_pool};
+
+    async fn post(uri: &str, body: Value) -> (u16, Value) {
+        let pool = create_database_pool().await.unwrap();
+        let app = test::init_service(configure_app(App::new(), pool)).await;
+        let req = test::TestRequest::post()
+            .uri(uri)
+            .set_json(body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let status = resp.status().as_u16();
+
+        (status, test::read_body_json(resp).await)
+    }
+
+    #[actix_rt::test]
+    async fn fails_with_known_types_for_unknown_message_type() {
+        let (status, body) =
+            post("/", json!({ "type": "UuidQuerry", "payload": { "id": 1 } })).await;
+
+        assert_eq!(status, 400);
+        assert_eq!(body["code"], "UNKNOWN_MESSAGE_TYPE");
+        assert_eq!(body["message"], "unknown message type `UuidQuerry`");
+        assert!(body["details"]["types"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("UuidQuery")));
+    }
+
+    #[actix_rt::test]
+    async fn fails_with_field_error_for_unknown_payload_field() {
+        let (status, body) = post(
+            "/",
+            json!({ "type": "UuidQuery", "payload": { "uuid": 1 } }),
+        )
+        .await;
+
+        assert_eq!(status, 400);
+        assert_eq!(body["code"], "INVALID_PAYLOAD");
+        assert_eq!(
+            body["message"],
+            "invalid payload of UuidQuery: unknown field `uuid`, expected `id`"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn fails_when_type_is_missing() {
+        let (status, body) = post("/", json!({ "payload": { "id": 1 } })).await;
+
+        assert_eq!(status, 400);
+        assert_eq!(body["code"], "MISSING_PARAMETER");
+    }
+
+    #[actix_rt::test]
+    async fn fails_with_position_of_invalid_message_in_batch() {
+        let (status, body) = post(
+            "/batch",
+            json!([
+                { "type": "UuidQuery", "payload": { "id": 1 } },
+                { "type": "UuidQuery", "payload": { "id": "1" } }
+            ]),
+        )
+        .await;
+
+        assert_eq!(status, 400);
+        assert_eq!(body["code"], "INVALID_PAYLOAD");
+        assert_eq!(
+            body["message"],
+            "message 1: invalid payload of UuidQuery: invalid type: string \"1\", expected i32"
+        );
+    }
+}
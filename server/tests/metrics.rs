@@ -0,0 +1,27 @@
+mod metrics {
+    use actix_web::{test, App};
+    use test_utils::*;
+
+    use server::{configure_app, create_database_pool};
+
+    #[actix_rt::test]
+    async fn counts_handled_messages() {
+        let pool = create_database_pool().await.unwrap();
+        let app = test::init_service(configure_app(App::new(), pool)).await;
+
+        let req = test::TestRequest::post()
+            .uri("/")
+            .set_json(json!({ "type": "UuidQuery", "payload": { "id": 1 } }))
+            .to_request();
+        test::call_service(&app, req).await;
+
+        let req = test::TestRequest::get().uri("/metrics").to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = std::str::from_utf8(&body).unwrap();
+
+        assert!(body.contains(r#"messages_total{operation_type="UuidQuery",status="200"}"#));
+        assert!(body.contains(r#"message_duration_seconds_count{operation_type="UuidQuery""#));
+        assert!(body.contains(r#"database_pool_connections{state="idle"}"#));
+        assert!(body.contains(r#"database_pool_connections{state="active"}"#));
+    }
+}
@@ -0,0 +1,93 @@
+mod ndjson {
+    use actix_web::{test, App};
+    use test_utils::{assert_eq, *};
+
+    use server::{configure_app, create_database_pool};
+
+    async fn stream_lines(message: Value) -> (u16, Vec<Value>) {
+        let pool = create_database_pool().await.unwrap();
+        let app = test::init_service(configure_app(App::new(), pool)).await;
+
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header(("Accept", "application/x-ndjson"))
+            .set_json(message)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let status = resp.status().as_u16();
+        let body = test::read_body(resp).await;
+        let lines = body
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+
+        (status, lines)
+    }
+
+    #[actix_rt::test]
+    async fn streams_events_with_cursor_in_last_line() {
+        let (status, lines) = stream_lines(json!({
+            "type": "EventsQuery",
+            "payload": { "first": 100, "after": 80_015 }
+        }))
+        .await;
+
+        assert_eq!(status, 200);
+        assert_eq!(lines.len(), 101);
+        assert_eq!(lines[0]["id"], 80014);
+        assert_eq!(
+            lines[100],
+            json!({ "cursor": { "after": lines[99]["id"], "hasNextPage": true } })
+        );
+    }
+
+    #[actix_rt::test]
+    async fn streams_same_events_as_json_response() {
+        let expected = Message::new("EventsQuery", json!({ "first": 10, "actorId": 1 }))
+            .execute()
+            .await
+            .get_json();
+
+        let (_, lines) = stream_lines(json!({
+            "type": "EventsQuery",
+            "payload": { "first": 10, "actorId": 1 }
+        }))
+        .await;
+
+        assert_eq!(Value::Array(lines[..10].to_vec()), expected["events"]);
+        assert_eq!(
+            lines[10]["cursor"]["hasNextPage"],
+            expected["hasNextPage"].clone()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn streams_entities_metadata() {
+        let (status, lines) = stream_lines(json!({
+            "type": "EntitiesMetadataQuery",
+            "payload": { "first": 5, "after": 1494 }
+        }))
+        .await;
+
+        assert_eq!(status, 200);
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0]["id"], "https://serlo.org/1495");
+        assert_eq!(
+            lines[5],
+            json!({ "cursor": { "after": lines[4]["identifier"]["value"], "hasNextPage": true } })
+        );
+    }
+
+    #[actix_rt::test]
+    async fn fails_with_usual_error_response_before_first_line() {
+        let (status, lines) = stream_lines(json!({
+            "type": "EventsQuery",
+            "payload": { "first": 1_000_000 }
+        }))
+        .await;
+
+        assert_eq!(status, 400);
+        assert_eq!(lines[0]["code"], "INVALID_PARAMETER");
+    }
+}
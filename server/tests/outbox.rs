@@ -0,0 +1,130 @@
+mod outbox {
+    use std::sync::{Arc, Mutex};
+
+    use actix_web::{web, App, HttpResponse, HttpServer};
+    use test_utils::{assert_eq, *};
+
+    use server::config::OutboxConfig;
+    use server::outbox::{self, Dispatcher};
+
+    /// Starts a webhook which records the bodies of all requests and returns its URL.
+    fn start_webhook(received: Arc<Mutex<Vec<Value>>>) -> String {
+        let server = HttpServer::new(move || {
+            let received = received.clone();
+            App::new().default_service(web::to(move |body: web::Json<Value>| {
+                received.lock().unwrap().push(body.into_inner());
+                async { HttpResponse::Ok().finish() }
+            }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let address = server.addrs()[0];
+
+        actix_rt::spawn(server.run());
+
+        format!("http://{address}/events")
+    }
+
+    #[actix_rt::test]
+    async fn sends_new_events_to_webhook() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let webhook_url = start_webhook(received.clone());
+        let config = OutboxConfig {
+            webhook_urls: vec![webhook_url],
+            ..Default::default()
+        };
+        outbox::init(&config);
+        let mut transaction = begin_transaction().await;
+
+        let event_ids = Message::new(
+            "UuidSetStateMutation",
+            json!({ "ids": [1855], "userId": 1, "trashed": true }),
+        )
+        .execute_on(&mut transaction)
+        .await
+        .get_json()["eventIds"]
+            .clone();
+
+        let dispatcher = Dispatcher::new(config);
+
+        assert!(dispatcher.distribute(&mut transaction).await.unwrap() > 0);
+
+        while let Some(delivery) = dispatcher.claim_next(&mut transaction).await.unwrap() {
+            let result = dispatcher.send(&delivery).await;
+            dispatcher
+                .complete(&delivery, result, &mut transaction)
+                .await
+                .unwrap();
+        }
+
+        let event = Message::new("EventQuery", json!({ "id": event_ids[0] }))
+            .execute_on(&mut transaction)
+            .await
+            .get_json();
+
+        assert_eq!(received.lock().unwrap().last(), Some(&event));
+    }
+
+    #[actix_rt::test]
+    async fn removes_events_without_webhooks_and_failed_deliveries() {
+        let config = OutboxConfig {
+            webhook_urls: vec!["http://127.0.0.1:1/events".to_string()],
+            max_attempts: 1,
+            failed_retention_seconds: 0,
+            ..Default::default()
+        };
+        outbox::init(&config);
+        let mut transaction = begin_transaction().await;
+
+        Message::new(
+            "UuidSetStateMutation",
+            json!({ "ids": [1855], "userId": 1, "trashed": true }),
+        )
+        .execute_on(&mut transaction)
+        .await;
+
+        let dispatcher = Dispatcher::new(OutboxConfig::default());
+
+        assert!(dispatcher.distribute(&mut transaction).await.unwrap() > 0);
+        assert!(dispatcher
+            .claim_next(&mut transaction)
+            .await
+            .unwrap()
+            .is_none());
+
+        let dispatcher = Dispatcher::new(config);
+
+        Message::new(
+            "UuidSetStateMutation",
+            json!({ "ids": [1855], "userId": 1, "trashed": false }),
+        )
+        .execute_on(&mut transaction)
+        .await;
+        dispatcher.distribute(&mut transaction).await.unwrap();
+
+        let delivery = dispatcher
+            .claim_next(&mut transaction)
+            .await
+            .unwrap()
+            .unwrap();
+        let result = dispatcher.send(&delivery).await;
+
+        assert!(result.is_err());
+
+        dispatcher
+            .complete(&delivery, result, &mut transaction)
+            .await
+            .unwrap();
+        // Rows created in the current second are not older than 0 seconds yet.
+        sqlx::query(
+            "UPDATE event_outbox SET created_at = created_at - INTERVAL 1 SECOND WHERE event_id = ?",
+        )
+        .bind(delivery.event_id)
+        .execute(&mut transaction)
+        .await
+        .unwrap();
+
+        assert!(dispatcher.remove_given_up(&mut transaction).await.unwrap() > 0);
+    }
+}
@@ -0,0 +1,125 @@
+//! Verifies the pacts of api.serlo.org without node or a running server. Each interaction is
+//! replayed through the messages of the server inside a transaction which is rolled back.
+//!
+//! The pact file is given by `PACT_FILE`, or the latest pacts are loaded from `PACT_BROKER_URL`.
+//! Since one of both variables is needed, the verification is ignored by default and runs with
+//! `cargo test --test pacts -- --ignored`. It fails when neither variable is set.
+mod matching;
+mod pact;
+mod states;
+
+mod pact_verification {
+    use server::create_database_pool;
+
+    use super::pact::{load_pacts, Verifier};
+    use super::states::STATES;
+
+    #[actix_rt::test]
+    #[ignore = "needs PACT_FILE or PACT_BROKER_URL"]
+    async fn fulfills_pacts_of_api() {
+        let pacts = load_pacts()
+            .await
+            .expect("PACT_FILE or PACT_BROKER_URL needs to be set");
+        let verifier = Verifier::new(create_database_pool().await.unwrap(), STATES);
+
+        let mut failures = Vec::new();
+        for pact in &pacts {
+            failures.extend(verifier.verify(pact).await);
+        }
+
+        assert!(
+            failures.is_empty(),
+            "{} interactions failed:\n\n{}",
+            failures.len(),
+            failures.join("\n\n")
+        );
+    }
+}
+
+mod matching_rules {
+    use test_utils::{assert_eq, *};
+
+    use super::matching::MatchingRules;
+
+    fn compare(expected: Value, actual: Value, matching_rules: Value) -> Vec<String> {
+        MatchingRules::from_pact(Some(&matching_rules))
+            .unwrap()
+            .compare(&expected, &actual)
+    }
+
+    #[test]
+    fn compares_values_without_rules_by_equality() {
+        assert_eq!(
+            compare(
+                json!({ "id": 1, "tags": ["a"] }),
+                json!({ "id": 1, "tags": ["a"], "trashed": false }),
+                json!({})
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            compare(
+                json!({ "id": 1, "tags": ["a"] }),
+                json!({ "id": 2 }),
+                json!({})
+            ),
+            vec!["$.id: expected 1 but got 2", "$.tags: field is missing"]
+        );
+    }
+
+    #[test]
+    fn applies_type_rules_to_children_of_version_2_paths() {
+        let rules = json!({ "$.body": { "match": "type" } });
+
+        assert_eq!(
+            compare(
+                json!({ "id": 1, "alias": "/1" }),
+                json!({ "id": 2, "alias": "/2" }),
+                rules.clone()
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            compare(json!({ "id": 1 }), json!({ "id": "1" }), rules),
+            vec![r#"$.id: expected a value like 1, got "1""#]
+        );
+    }
+
+    #[test]
+    fn compares_elements_of_arrays_with_min_to_first_expected_element() {
+        let rules = json!({
+            "body": {
+                "$.events": { "matchers": [{ "match": "type", "min": 1 }] },
+                "$.events[*].date": { "matchers": [{ "match": "regex", "regex": "\\d{4}-.*" }] }
+            }
+        });
+
+        assert_eq!(
+            compare(
+                json!({ "events": [{ "id": 1, "date": "2014-01-01" }] }),
+                json!({ "events": [
+                    { "id": 5, "date": "2015-02-03" },
+                    { "id": 6, "date": "2016-04-05" }
+                ] }),
+                rules.clone()
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            compare(
+                json!({ "events": [{ "id": 1, "date": "2014-01-01" }] }),
+                json!({ "events": [{ "id": 5, "date": "yesterday" }] }),
+                rules.clone()
+            ),
+            vec![r#"$.events[0].date: expected \d{4}-.*, got "yesterday""#]
+        );
+        assert_eq!(
+            compare(
+                json!({ "events": [{ "id": 1, "date": "2014-01-01" }] }),
+                json!({ "events": [] }),
+                rules
+            ),
+            vec!["$.events: expected at least 1 elements, got []"]
+        );
+    }
+}
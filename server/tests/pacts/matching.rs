@@ -0,0 +1,341 @@
+//! Matching rules of the pact specification (versions 2 and 3) for JSON bodies.
+//!
+//! A rule applies to its path and all paths below it unless there is a more specific rule. Without
+//! a rule values need to be equal, arrays need to have the same length and objects need to contain
+//! the expected fields (further fields are allowed).
+use regex::Regex;
+use serde_json::Value;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Field(String),
+    Index(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Field(String),
+    Index(usize),
+    AnyField,
+    AnyIndex,
+}
+
+impl Token {
+    fn matches(&self, segment: &Segment) -> bool {
+        match (self, segment) {
+            (Token::Field(name), Segment::Field(other)) => name == other,
+            (Token::Index(index), Segment::Index(other)) => index == other,
+            (Token::AnyField, _) | (Token::AnyIndex, _) => true,
+            _ => false,
+        }
+    }
+
+    fn weight(&self) -> usize {
+        match self {
+            Token::Field(_) | Token::Index(_) => 2,
+            Token::AnyField | Token::AnyIndex => 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Rule {
+    path: Vec<Token>,
+    matchers: Vec<Value>,
+    combine_with_or: bool,
+}
+
+/// Matching rules of the body of a response.
+#[derive(Debug, Default)]
+pub struct MatchingRules {
+    rules: Vec<Rule>,
+}
+
+impl MatchingRules {
+    /// Reads the rules of the body from the `matchingRules` of a pact response. Version 2 uses
+    /// paths like `$.body.id`, version 3 groups the rules by category.
+    pub fn from_pact(matching_rules: Option<&Value>) -> Result<Self, String> {
+        let matching_rules = match matching_rules.and_then(Value::as_object) {
+            Some(matching_rules) => matching_rules,
+            None => return Ok(Self::default()),
+        };
+
+        let mut rules = Vec::new();
+
+        if let Some(body_rules) = matching_rules.get("body").and_then(Value::as_object) {
+            for (path, rule) in body_rules {
+                rules.push(Rule {
+                    path: parse_path(path)?,
+                    matchers: rule["matchers"].as_array().cloned().unwrap_or_default(),
+                    combine_with_or: rule["combine"] == "OR",
+                });
+            }
+        } else {
+            for (path, matcher) in matching_rules {
+                if let Some(body_path) = path.strip_prefix("$.body") {
+                    rules.push(Rule {
+                        path: parse_path(&format!("${body_path}"))?,
+                        matchers: vec![matcher.clone()],
+                        combine_with_or: false,
+                    });
+                }
+            }
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Returns the mismatches between the expected and the actual body.
+    pub fn compare(&self, expected: &Value, actual: &Value) -> Vec<String> {
+        let mut mismatches = Vec::new();
+        self.compare_at(expected, actual, &mut Vec::new(), &mut mismatches);
+        mismatches
+    }
+
+    fn compare_at(
+        &self,
+        expected: &Value,
+        actual: &Value,
+        path: &mut Vec<Segment>,
+        mismatches: &mut Vec<String>,
+    ) {
+        let rule = match self.find_rule(path) {
+            Some(rule) => rule,
+            None => return self.compare_without_rule(expected, actual, path, mismatches),
+        };
+
+        let results: Vec<Result<bool, String>> = rule
+            .matchers
+            .iter()
+            .map(|matcher| apply_matcher(matcher, expected, actual))
+            .collect();
+        let failures: Vec<&String> = results.iter().filter_map(|r| r.as_ref().err()).collect();
+        let failed = if rule.combine_with_or {
+            failures.len() == results.len()
+        } else {
+            !failures.is_empty()
+        };
+
+        if failed {
+            for failure in failures {
+                mismatches.push(format!("{}: {failure}", format_path(path)));
+            }
+            return;
+        }
+
+        // Only type matchers look into the values of arrays and objects.
+        if !results.iter().any(|result| matches!(result, Ok(true))) {
+            return;
+        }
+
+        match (expected, actual) {
+            (Value::Array(expected), Value::Array(actual)) => {
+                if let Some(template) = expected.first() {
+                    for (index, element) in actual.iter().enumerate() {
+                        path.push(Segment::Index(index));
+                        self.compare_at(template, element, path, mismatches);
+                        path.pop();
+                    }
+                }
+            }
+            (Value::Object(_), Value::Object(_)) => {
+                self.compare_fields(expected, actual, path, mismatches)
+            }
+            _ => {}
+        }
+    }
+
+    fn compare_without_rule(
+        &self,
+        expected: &Value,
+        actual: &Value,
+        path: &mut Vec<Segment>,
+        mismatches: &mut Vec<String>,
+    ) {
+        match (expected, actual) {
+            (Value::Array(expected), Value::Array(actual)) => {
+                if expected.len() != actual.len() {
+                    mismatches.push(format!(
+                        "{}: expected {} elements but got {}",
+                        format_path(path),
+                        expected.len(),
+                        actual.len()
+                    ));
+                    return;
+                }
+                for (index, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                    path.push(Segment::Index(index));
+                    self.compare_at(expected, actual, path, mismatches);
+                    path.pop();
+                }
+            }
+            (Value::Object(_), Value::Object(_)) => {
+                self.compare_fields(expected, actual, path, mismatches)
+            }
+            _ if expected != actual => mismatches.push(format!(
+                "{}: expected {expected} but got {actual}",
+                format_path(path)
+            )),
+            _ => {}
+        }
+    }
+
+    fn compare_fields(
+        &self,
+        expected: &Value,
+        actual: &Value,
+        path: &mut Vec<Segment>,
+        mismatches: &mut Vec<String>,
+    ) {
+        for (name, expected) in expected.as_object().into_iter().flatten() {
+            path.push(Segment::Field(name.clone()));
+            match actual.get(name) {
+                Some(actual) => self.compare_at(expected, actual, path, mismatches),
+                None => mismatches.push(format!("{}: field is missing", format_path(path))),
+            }
+            path.pop();
+        }
+    }
+
+    /// Returns the most specific rule for `path` or one of its ancestors.
+    fn find_rule(&self, path: &[Segment]) -> Option<&Rule> {
+        self.rules
+            .iter()
+            .filter(|rule| {
+                rule.path.len() <= path.len()
+                    && rule
+                        .path
+                        .iter()
+                        .zip(path)
+                        .all(|(token, segment)| token.matches(segment))
+            })
+            .max_by_key(|rule| {
+                (
+                    rule.path.len(),
+                    rule.path.iter().map(Token::weight).sum::<usize>(),
+                )
+            })
+    }
+}
+
+/// Checks a single value. Returns `Ok(true)` when the children of arrays and objects shall be
+/// compared as well.
+fn apply_matcher(matcher: &Value, expected: &Value, actual: &Value) -> Result<bool, String> {
+    check_matcher(matcher, expected, actual).map_err(|error| format!("{error}, got {actual}"))
+}
+
+fn check_matcher(matcher: &Value, expected: &Value, actual: &Value) -> Result<bool, String> {
+    let match_type = matcher["match"].as_str().unwrap_or("type");
+
+    match match_type {
+        "type" => {
+            if std::mem::discriminant(expected) != std::mem::discriminant(actual) {
+                return Err(format!("expected a value like {expected}"));
+            }
+            if let Value::Array(elements) = actual {
+                check_length(matcher, elements.len())?;
+            }
+            Ok(true)
+        }
+        "equality" => check(expected == actual, || format!("expected {expected}")).map(|_| false),
+        "regex" => {
+            let pattern = matcher["regex"]
+                .as_str()
+                .ok_or("regex matcher without regex")?;
+            let regex =
+                Regex::new(&format!("^(?:{pattern})$")).map_err(|error| error.to_string())?;
+            let value = match actual {
+                Value::String(value) => value.clone(),
+                Value::Number(_) | Value::Bool(_) => actual.to_string(),
+                _ => return Err(format!("expected a string matching {pattern}")),
+            };
+            check(regex.is_match(&value), || format!("expected {pattern}")).map(|_| false)
+        }
+        "include" => {
+            let value = matcher["value"].as_str().unwrap_or_default();
+            check(
+                actual.as_str().is_some_and(|actual| actual.contains(value)),
+                || format!("expected a string including {value:?}"),
+            )
+            .map(|_| false)
+        }
+        "integer" => check(actual.is_i64() || actual.is_u64(), || {
+            "expected an integer".to_string()
+        })
+        .map(|_| false),
+        "decimal" => check(actual.is_f64(), || "expected a decimal".to_string()).map(|_| false),
+        "number" => check(actual.is_number(), || "expected a number".to_string()).map(|_| false),
+        "boolean" => check(actual.is_boolean(), || "expected a boolean".to_string()).map(|_| false),
+        "null" => check(actual.is_null(), || "expected null".to_string()).map(|_| false),
+        // The formats of dates and times are not validated.
+        "date" | "time" | "timestamp" | "datetime" => {
+            check(actual.is_string(), || "expected a date".to_string()).map(|_| false)
+        }
+        other => Err(format!("matcher {other:?} is not supported")),
+    }
+}
+
+fn check_length(matcher: &Value, length: usize) -> Result<(), String> {
+    if let Some(min) = matcher["min"].as_u64() {
+        check(length as u64 >= min, || {
+            format!("expected at least {min} elements")
+        })?;
+    }
+    if let Some(max) = matcher["max"].as_u64() {
+        check(length as u64 <= max, || {
+            format!("expected at most {max} elements")
+        })?;
+    }
+    Ok(())
+}
+
+fn check(condition: bool, message: impl FnOnce() -> String) -> Result<(), String> {
+    if condition {
+        Ok(())
+    } else {
+        Err(message())
+    }
+}
+
+/// Parses paths like `$.events[*].id` or `$['user-ids'][0]`.
+fn parse_path(path: &str) -> Result<Vec<Token>, String> {
+    let invalid = || format!("invalid path {path:?} in matching rules");
+    let mut rest = path.strip_prefix('$').ok_or_else(invalid)?;
+    let mut tokens = Vec::new();
+
+    while !rest.is_empty() {
+        if let Some(after_dot) = rest.strip_prefix('.') {
+            let end = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+            let name = &after_dot[..end];
+            tokens.push(match name {
+                "" => return Err(invalid()),
+                "*" => Token::AnyField,
+                name => Token::Field(name.to_string()),
+            });
+            rest = &after_dot[end..];
+        } else if let Some(after_bracket) = rest.strip_prefix('[') {
+            let end = after_bracket.find(']').ok_or_else(invalid)?;
+            let content = &after_bracket[..end];
+            tokens.push(if content == "*" {
+                Token::AnyIndex
+            } else if let Ok(index) = content.parse() {
+                Token::Index(index)
+            } else {
+                Token::Field(content.trim_matches('\'').to_string())
+            });
+            rest = &after_bracket[end + 1..];
+        } else {
+            return Err(invalid());
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn format_path(path: &[Segment]) -> String {
+    path.iter()
+        .fold("$".to_string(), |result, segment| match segment {
+            Segment::Field(name) => format!("{result}.{name}"),
+            Segment::Index(index) => format!("{result}[{index}]"),
+        })
+}
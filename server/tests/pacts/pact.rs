@@ -0,0 +1,203 @@
+//! Loading pacts and replaying their interactions against the messages of the server.
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+
+use actix_web::body::to_bytes;
+use futures::future::BoxFuture;
+use serde_json::Value;
+use server::database::Connection;
+use server::message::{Message, MessageResponder};
+use server::routes::invalid_message;
+use sqlx::{MySql, MySqlPool, Transaction};
+
+use super::matching::MatchingRules;
+
+pub const PROVIDER: &str = "serlo.org-database-layer";
+
+/// Sets up a provider state inside the transaction of an interaction. The second argument are
+/// the parameters of the state (`null` for pacts of version 2).
+pub type StateSetup = for<'a> fn(
+    &'a mut Transaction<'static, MySql>,
+    &'a Value,
+) -> BoxFuture<'a, Result<(), sqlx::Error>>;
+
+pub struct Pact {
+    pub source: String,
+    pub content: Value,
+}
+
+impl Pact {
+    pub fn interactions(&self) -> &[Value] {
+        self.content["interactions"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// Loads the pact file `PACT_FILE` or else the latest pacts of the broker `PACT_BROKER_URL`
+/// (with `PACT_BROKER_USERNAME` and `PACT_BROKER_PASSWORD`). Returns `None` when neither is set.
+pub async fn load_pacts() -> Option<Vec<Pact>> {
+    if let Ok(path) = env::var("PACT_FILE") {
+        let content = fs::read_to_string(&path).expect("pact file can be read");
+        return Some(vec![Pact {
+            content: serde_json::from_str(&content).expect("pact file contains JSON"),
+            source: path,
+        }]);
+    }
+
+    let broker_url = env::var("PACT_BROKER_URL").ok()?;
+    let broker_url = broker_url.trim_end_matches('/');
+    let client = reqwest::Client::new();
+    let fetch = |url: String| {
+        let request = client.get(&url).basic_auth(
+            env::var("PACT_BROKER_USERNAME").unwrap_or_default(),
+            env::var("PACT_BROKER_PASSWORD").ok(),
+        );
+        async move {
+            let body = request
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .unwrap_or_else(|error| panic!("could not fetch {url}: {error}"))
+                .text()
+                .await
+                .unwrap_or_else(|error| panic!("could not fetch {url}: {error}"));
+            serde_json::from_str::<Value>(&body).expect("pact broker responds with JSON")
+        }
+    };
+
+    let latest = fetch(format!("{broker_url}/pacts/provider/{PROVIDER}/latest")).await;
+    let mut pacts = Vec::new();
+    for link in latest["_links"]["pb:pacts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        if let Some(href) = link["href"].as_str() {
+            pacts.push(Pact {
+                source: href.to_string(),
+                content: fetch(href.to_string()).await,
+            });
+        }
+    }
+
+    Some(pacts)
+}
+
+pub struct Verifier {
+    pool: MySqlPool,
+    states: HashMap<&'static str, StateSetup>,
+}
+
+impl Verifier {
+    pub fn new(pool: MySqlPool, states: &[(&'static str, StateSetup)]) -> Self {
+        Self {
+            pool,
+            states: states.iter().copied().collect(),
+        }
+    }
+
+    /// Replays all interactions of `pact` and returns the failures.
+    pub async fn verify(&self, pact: &Pact) -> Vec<String> {
+        let mut failures = Vec::new();
+
+        for interaction in pact.interactions() {
+            let mismatches = match self.verify_interaction(interaction).await {
+                Ok(mismatches) => mismatches,
+                Err(error) => vec![error],
+            };
+
+            if !mismatches.is_empty() {
+                failures.push(format!(
+                    "{} ({}):\n  {}",
+                    interaction["description"].as_str().unwrap_or_default(),
+                    pact.source,
+                    mismatches.join("\n  ")
+                ));
+            }
+        }
+
+        failures
+    }
+
+    async fn verify_interaction(&self, interaction: &Value) -> Result<Vec<String>, String> {
+        let request = &interaction["request"];
+        let expected = &interaction["response"];
+
+        if request["method"] != "POST" || request["path"] != "/" {
+            return Err(format!(
+                "only messages (POST /) are supported, got {} {}",
+                request["method"], request["path"]
+            ));
+        }
+
+        let mut transaction = self.pool.begin().await.map_err(|e| e.to_string())?;
+
+        for (name, params) in provider_states(interaction) {
+            // Like in the pact tests with node, most states are given by the database dump.
+            if let Some(setup) = self.states.get(name.as_str()) {
+                setup(&mut transaction, &params)
+                    .await
+                    .map_err(|error| format!("provider state {name:?} failed: {error}"))?;
+            }
+        }
+
+        let response = match Message::from_value(request["body"].clone()) {
+            Ok(message) => {
+                message
+                    .handle(Connection::Transaction(&mut transaction))
+                    .await
+            }
+            Err(error) => invalid_message(&error, &error.to_string()),
+        };
+        transaction.rollback().await.map_err(|e| e.to_string())?;
+
+        let status = response.status().as_u16();
+        let body = to_bytes(response.into_body())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut mismatches = Vec::new();
+
+        if expected["status"].as_u64() != Some(status.into()) {
+            mismatches.push(format!(
+                "expected status {} but got {status}",
+                expected["status"]
+            ));
+        }
+
+        if let Some(expected_body) = expected.get("body") {
+            let actual_body = serde_json::from_slice::<Value>(&body).map_err(|error| {
+                format!(
+                    "response is no JSON ({error}): {}",
+                    String::from_utf8_lossy(&body)
+                )
+            })?;
+            let rules = MatchingRules::from_pact(expected.get("matchingRules"))?;
+            mismatches.extend(rules.compare(expected_body, &actual_body));
+        }
+
+        Ok(mismatches)
+    }
+}
+
+/// Returns the names and parameters of the provider states (`providerState` in version 2 and
+/// `providerStates` in version 3).
+fn provider_states(interaction: &Value) -> Vec<(String, Value)> {
+    if let Some(name) = interaction["providerState"].as_str() {
+        return vec![(name.to_string(), Value::Null)];
+    }
+
+    interaction["providerStates"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|state| {
+            state["name"]
+                .as_str()
+                .map(|name| (name.to_string(), state["params"].clone()))
+        })
+        .collect()
+}
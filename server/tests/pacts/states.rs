@@ -0,0 +1,24 @@
+//! Provider states of the pacts of api.serlo.org.
+//!
+//! States which are not listed here are assumed to be given by the database dump (like in the
+//! pact tests with node). A state is set up inside the transaction of its interaction, e.g.
+//!
+//! ```ignore
+//! fn uuid_is_trashed<'a>(
+//!     transaction: &'a mut Transaction<'static, MySql>,
+//!     params: &'a Value,
+//! ) -> BoxFuture<'a, Result<(), sqlx::Error>> {
+//!     Box::pin(async move {
+//!         sqlx::query("UPDATE uuid SET trashed = 1 WHERE id = ?")
+//!             .bind(params["id"].as_i64())
+//!             .execute(&mut *transaction)
+//!             .await?;
+//!         Ok(())
+//!     })
+//! }
+//!
+//! pub const STATES: &[(&str, StateSetup)] = &[("uuid is trashed", uuid_is_trashed)];
+//! ```
+use super::pact::StateSetup;
+
+pub const STATES: &[(&str, StateSetup)] = &[];
@@ -0,0 +1,79 @@
+mod replica {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use actix_web::{test, App};
+    use sqlx::mysql::MySqlPoolOptions;
+    use sqlx::MySqlPool;
+    use test_utils::{assert_eq, *};
+
+    use server::config::Config;
+    use server::configure_app_with_replica;
+
+    /// Creates a pool which counts how often a connection is handed out. The connections of a
+    /// read only pool cannot write (like the ones of a replica).
+    async fn counting_pool(read_only: bool) -> (MySqlPool, Arc<AtomicUsize>) {
+        let acquires = Arc::new(AtomicUsize::new(0));
+        let connected = acquires.clone();
+        let acquired = acquires.clone();
+
+        let pool = MySqlPoolOptions::new()
+            .after_connect(move |connection, _| {
+                connected.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async move {
+                    if read_only {
+                        sqlx::query("SET SESSION TRANSACTION READ ONLY")
+                            .execute(connection)
+                            .await?;
+                    }
+                    Ok(())
+                })
+            })
+            .before_acquire(move |_, _| {
+                acquired.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Ok(true) })
+            })
+            .connect_with(Config::load().unwrap().database.connect_options().unwrap())
+            .await
+            .unwrap();
+
+        // Connecting already hands out a connection to check it.
+        acquires.store(0, Ordering::SeqCst);
+
+        (pool, acquires)
+    }
+
+    #[actix_rt::test]
+    async fn executes_queries_with_replica_pool_and_mutations_with_primary_pool() {
+        let (pool, primary_acquires) = counting_pool(false).await;
+        let (replica_pool, replica_acquires) = counting_pool(true).await;
+        let app =
+            test::init_service(configure_app_with_replica(App::new(), pool, replica_pool)).await;
+
+        let req = test::TestRequest::post()
+            .uri("/")
+            .set_json(json!({ "type": "UuidQuery", "payload": { "id": 1 } }))
+            .to_request();
+        let result: Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(result["__typename"], "User");
+        assert_eq!(primary_acquires.load(Ordering::SeqCst), 0);
+        assert!(replica_acquires.load(Ordering::SeqCst) > 0);
+
+        replica_acquires.store(0, Ordering::SeqCst);
+
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header(("Rollback", "true"))
+            .set_json(json!({
+                "type": "UuidSetStateMutation",
+                "payload": { "ids": [1855], "userId": 1, "trashed": true }
+            }))
+            .to_request();
+        let result: Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(result["success"], true);
+        assert!(primary_acquires.load(Ordering::SeqCst) > 0);
+        assert_eq!(replica_acquires.load(Ordering::SeqCst), 0);
+    }
+}
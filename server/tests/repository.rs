@@ -0,0 +1,685 @@
+mod entity_revisions {
+    use serde_json::from_value;
+    use test_utils::{assert_eq, *};
+
+    use server::event::EventType;
+    use server::instance::Instance;
+    use server::operation::{Error, ErrorCode};
+    use server::uuid::Entity;
+
+    fn repository() -> InMemoryRepository {
+        let mut repository = InMemoryRepository::default();
+        repository.add_entity(1, Instance::De, Some(2));
+        repository.insert_entity_revision(2, 1);
+        repository.insert_entity_revision(3, 1);
+        repository
+    }
+
+    fn payload(revision_id: i32) -> Value {
+        json!({ "revisionId": revision_id, "userId": 10, "reason": "reason" })
+    }
+
+    #[actix_rt::test]
+    async fn rejecting_checked_out_revision_fails() {
+        let mut repository = repository();
+
+        let result =
+            Entity::reject_revision_with(&from_value(payload(2)).unwrap(), &mut repository).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::BadRequest {
+                code: ErrorCode::RevisionCurrentlyCheckedOut,
+                ..
+            })
+        ));
+        assert!(!repository.is_trashed(2));
+        assert!(repository.events.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn rejecting_revision_trashes_it_and_creates_event() {
+        let mut repository = repository();
+
+        Entity::reject_revision_with(&from_value(payload(3)).unwrap(), &mut repository)
+            .await
+            .unwrap();
+
+        assert!(repository.is_trashed(3));
+        assert_eq!(repository.events.len(), 1);
+        assert_eq!(
+            repository.events[0].abstract_event.__typename,
+            EventType::RejectRevision
+        );
+        assert_eq!(
+            repository.events[0]
+                .abstract_event
+                .uuid_parameters
+                .get("repository"),
+            Some(1)
+        );
+    }
+
+    #[actix_rt::test]
+    async fn rejecting_revision_twice_fails() {
+        let mut repository = repository();
+        let payload = from_value(payload(3)).unwrap();
+
+        Entity::reject_revision_with(&payload, &mut repository)
+            .await
+            .unwrap();
+        let result = Entity::reject_revision_with(&payload, &mut repository).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::BadRequest {
+                code: ErrorCode::RevisionAlreadyRejected,
+                ..
+            })
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn rejecting_unknown_revision_fails() {
+        let result =
+            Entity::reject_revision_with(&from_value(payload(4)).unwrap(), &mut repository()).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::NotFoundError {
+                code: ErrorCode::UuidNotFound
+            })
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn checking_out_revision_changes_current_revision() {
+        let mut repository = repository();
+
+        Entity::checkout_revision_with(&from_value(payload(3)).unwrap(), &mut repository)
+            .await
+            .unwrap();
+
+        assert_eq!(repository.entities[&1].current_revision_id, Some(3));
+        assert_eq!(
+            repository.events[0].abstract_event.__typename,
+            EventType::CheckoutRevision
+        );
+    }
+
+    #[actix_rt::test]
+    async fn checking_out_current_revision_fails() {
+        let result =
+            Entity::checkout_revision_with(&from_value(payload(2)).unwrap(), &mut repository())
+                .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::BadRequest {
+                code: ErrorCode::RevisionAlreadyCheckedOut,
+                ..
+            })
+        ));
+    }
+
+    fn add_revision_payload(fields: Value) -> Value {
+        json!({
+            "input": {
+                "changes": "changes",
+                "entityId": 1,
+                "needsReview": false,
+                "subscribeThis": false,
+                "subscribeThisByEmail": false,
+                "fields": fields
+            },
+            "revisionType": "ArticleRevision",
+            "userId": 10
+        })
+    }
+
+    #[actix_rt::test]
+    async fn adds_revision_and_checks_it_out() {
+        let mut repository = repository();
+
+        let revision_id = Entity::add_revision_with(
+            &from_value(add_revision_payload(json!({ "content": "content" }))).unwrap(),
+            &mut repository,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(repository.entity_revisions[&revision_id].repository_id, 1);
+        assert_eq!(
+            repository.entity_revision_fields[&revision_id]["changes"],
+            "changes"
+        );
+        assert_eq!(
+            repository.entities[&1].current_revision_id,
+            Some(revision_id)
+        );
+        assert_eq!(
+            repository
+                .events
+                .iter()
+                .map(|event| event.abstract_event.__typename.clone())
+                .collect::<Vec<_>>(),
+            vec![EventType::CreateEntityRevision, EventType::CheckoutRevision]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn adding_revision_with_unchanged_fields_returns_latest_revision() {
+        let mut repository = repository();
+        let payload = add_revision_payload(json!({ "content": "content" }));
+
+        let revision_id =
+            Entity::add_revision_with(&from_value(payload.clone()).unwrap(), &mut repository)
+                .await
+                .unwrap();
+        let result =
+            Entity::add_revision_with(&from_value(payload).unwrap(), &mut repository).await;
+
+        assert_eq!(result.unwrap(), revision_id);
+        assert_eq!(repository.events.len(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn adding_revision_to_missing_entity_fails() {
+        let mut payload = add_revision_payload(json!({ "content": "content" }));
+        payload["input"]["entityId"] = json!(5);
+
+        let result =
+            Entity::add_revision_with(&from_value(payload).unwrap(), &mut repository()).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::BadRequest {
+                code: ErrorCode::EntityNotFound,
+                ..
+            })
+        ));
+    }
+}
+
+mod entity_creation {
+    use serde_json::from_value;
+    use test_utils::{assert_eq, *};
+
+    use server::event::EventType;
+    use server::instance::Instance;
+    use server::operation::{Error, ErrorCode};
+    use server::uuid::Entity;
+
+    fn repository() -> InMemoryRepository {
+        let mut repository = InMemoryRepository::default();
+        repository.add_taxonomy_term(1, Instance::En);
+        repository.add_entity(2, Instance::De, Some(3));
+        repository.insert_entity_revision(3, 2);
+        repository
+    }
+
+    fn payload(entity_type: &str, parent: Value) -> Value {
+        let mut input = json!({
+            "changes": "changes",
+            "licenseId": 1,
+            "subscribeThis": false,
+            "needsReview": false,
+            "subscribeThisByEmail": false,
+            "fields": { "content": "content" }
+        });
+        input
+            .as_object_mut()
+            .unwrap()
+            .extend(parent.as_object().unwrap().clone());
+
+        json!({ "input": input, "entityType": entity_type, "userId": 10 })
+    }
+
+    fn event_types(repository: &InMemoryRepository) -> Vec<EventType> {
+        repository
+            .events
+            .iter()
+            .map(|event| event.abstract_event.__typename.clone())
+            .collect()
+    }
+
+    #[actix_rt::test]
+    async fn creates_entity_in_taxonomy_term_and_checks_out_revision() {
+        let mut repository = repository();
+
+        let entity_id = Entity::create_with(
+            &from_value(payload("Article", json!({ "taxonomyTermId": 1 }))).unwrap(),
+            &mut repository,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(repository.entities[&entity_id].instance, Instance::En);
+        assert_eq!(repository.taxonomy_links, vec![(entity_id, 1)]);
+        assert_eq!(
+            event_types(&repository),
+            vec![
+                EventType::CreateTaxonomyLink,
+                EventType::CreateEntity,
+                EventType::CreateEntityRevision,
+                EventType::CheckoutRevision
+            ]
+        );
+
+        let revision_id = repository.entities[&entity_id].current_revision_id.unwrap();
+        assert_eq!(
+            repository.entity_revisions[&revision_id].repository_id,
+            entity_id
+        );
+    }
+
+    #[actix_rt::test]
+    async fn creates_child_entity_which_needs_review() {
+        let mut repository = repository();
+        let mut payload = payload("Solution", json!({ "parentId": 2 }));
+        payload["input"]["needsReview"] = json!(true);
+        payload["input"]["subscribeThis"] = json!(true);
+
+        let entity_id = Entity::create_with(&from_value(payload).unwrap(), &mut repository)
+            .await
+            .unwrap();
+
+        assert_eq!(repository.entities[&entity_id].instance, Instance::De);
+        assert_eq!(repository.entities[&entity_id].current_revision_id, None);
+        assert_eq!(repository.entity_links, vec![(2, entity_id)]);
+        assert_eq!(
+            event_types(&repository),
+            vec![
+                EventType::CreateEntityLink,
+                EventType::CreateEntity,
+                EventType::CreateEntityRevision
+            ]
+        );
+        assert_eq!(repository.subscriptions.len(), 1);
+        assert_eq!(repository.subscriptions[0].object_id, entity_id);
+    }
+
+    #[actix_rt::test]
+    async fn creating_second_solution_fails() {
+        let mut repository = repository();
+        let payload = payload("Solution", json!({ "parentId": 2 }));
+
+        Entity::create_with(&from_value(payload.clone()).unwrap(), &mut repository)
+            .await
+            .unwrap();
+        let result = Entity::create_with(&from_value(payload).unwrap(), &mut repository).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::BadRequest {
+                code: ErrorCode::SolutionAlreadyExists,
+                ..
+            })
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn creating_solution_next_to_trashed_one_succeeds() {
+        let mut repository = repository();
+        let payload = payload("Solution", json!({ "parentId": 2 }));
+
+        let solution_id =
+            Entity::create_with(&from_value(payload.clone()).unwrap(), &mut repository)
+                .await
+                .unwrap();
+        repository.trashed_uuids.insert(solution_id);
+
+        assert!(
+            Entity::create_with(&from_value(payload).unwrap(), &mut repository)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn creating_entity_without_parent_fails() {
+        let mut repository = repository();
+
+        let result = Entity::create_with(
+            &from_value(payload("GroupedExercise", json!({ "taxonomyTermId": 1 }))).unwrap(),
+            &mut repository,
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::BadRequest {
+                code: ErrorCode::MissingParameter,
+                ..
+            })
+        ));
+        assert!(repository.events.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn creating_entity_in_unknown_taxonomy_term_fails() {
+        let mut repository = repository();
+
+        let result = Entity::create_with(
+            &from_value(payload("Article", json!({ "taxonomyTermId": 4 }))).unwrap(),
+            &mut repository,
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::BadRequest {
+                code: ErrorCode::TaxonomyTermNotFound,
+                ..
+            })
+        ));
+        assert_eq!(repository.entities.len(), 1);
+    }
+}
+
+mod comments {
+    use serde_json::from_value;
+    use test_utils::{assert_eq, *};
+
+    use server::event::EventType;
+    use server::instance::Instance;
+    use server::operation::{Error, ErrorCode};
+    use server::thread::Threads;
+
+    fn repository() -> InMemoryRepository {
+        let mut repository = InMemoryRepository::default();
+        repository.add_thread(1, Instance::De, false);
+        repository.add_thread(2, Instance::De, true);
+        repository
+    }
+
+    fn payload(thread_id: i32, content: &str) -> Value {
+        json!({
+            "threadId": thread_id,
+            "content": content,
+            "userId": 10,
+            "subscribe": true,
+            "sendEmail": false
+        })
+    }
+
+    #[actix_rt::test]
+    async fn comments_thread_and_subscribes_to_it() {
+        let mut repository = repository();
+
+        let comment_id =
+            Threads::comment_thread_with(&from_value(payload(1, "Hi")).unwrap(), &mut repository)
+                .await
+                .unwrap();
+
+        assert_eq!(repository.comments.len(), 1);
+        assert_eq!(repository.comments[0].id, comment_id);
+        assert_eq!(repository.comments[0].thread_id, 1);
+        assert_eq!(repository.comments[0].content, "Hi");
+        assert_eq!(
+            repository.events[0].abstract_event.__typename,
+            EventType::CreateComment
+        );
+        assert_eq!(
+            repository.events[0]
+                .abstract_event
+                .uuid_parameters
+                .get("discussion"),
+            Some(1)
+        );
+
+        let mut subscribed_ids: Vec<i32> = repository
+            .subscriptions
+            .iter()
+            .map(|subscription| subscription.object_id)
+            .collect();
+        subscribed_ids.sort_unstable();
+        assert_eq!(subscribed_ids, vec![1, comment_id]);
+    }
+
+    #[actix_rt::test]
+    async fn commenting_archived_thread_fails() {
+        let mut repository = repository();
+
+        let result =
+            Threads::comment_thread_with(&from_value(payload(2, "Hi")).unwrap(), &mut repository)
+                .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::BadRequest {
+                code: ErrorCode::ThreadAlreadyArchived,
+                ..
+            })
+        ));
+        assert!(repository.comments.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn commenting_with_empty_content_fails() {
+        let mut repository = repository();
+
+        let result =
+            Threads::comment_thread_with(&from_value(payload(1, "")).unwrap(), &mut repository)
+                .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::BadRequest {
+                code: ErrorCode::EmptyContent,
+                ..
+            })
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn commenting_unknown_thread_fails() {
+        let result =
+            Threads::comment_thread_with(&from_value(payload(3, "Hi")).unwrap(), &mut repository())
+                .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::BadRequest {
+                code: ErrorCode::ThreadNotFound,
+                ..
+            })
+        ));
+    }
+}
+
+mod notifications {
+    use serde_json::from_value;
+    use test_utils::{assert_eq, *};
+
+    use server::instance::Instance;
+    use server::notification::Notifications;
+    use server::thread::Threads;
+    use server::uuid::Entity;
+
+    #[actix_rt::test]
+    async fn notifies_subscribers_except_actor_once() {
+        let mut repository = InMemoryRepository::default();
+        repository.add_entity(1, Instance::De, Some(2));
+        repository.insert_entity_revision(3, 1);
+        repository.add_subscription(1, 10, false);
+        repository.add_subscription(3, 20, true);
+        repository.add_subscription(1, 20, true);
+
+        Entity::reject_revision_with(
+            &from_value(json!({ "revisionId": 3, "userId": 10, "reason": "" })).unwrap(),
+            &mut repository,
+        )
+        .await
+        .unwrap();
+
+        assert!(!repository.notifications.contains_key(&10));
+        assert_eq!(repository.notifications[&20].len(), 1);
+        assert!(repository.notifications[&20][0].email);
+    }
+
+    #[actix_rt::test]
+    async fn notifies_subscribers_of_thread_about_comment() {
+        let mut repository = InMemoryRepository::default();
+        repository.add_thread(1, Instance::De, false);
+        repository.add_subscription(1, 20, false);
+
+        Threads::comment_thread_with(
+            &from_value(json!({
+                "threadId": 1,
+                "content": "Hi",
+                "userId": 10,
+                "subscribe": false,
+                "sendEmail": false
+            }))
+            .unwrap(),
+            &mut repository,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(repository.notifications[&20].len(), 1);
+        assert_eq!(
+            repository.notifications[&20][0].event_id,
+            repository.events[0].abstract_event.id
+        );
+        assert!(!repository.notifications[&20][0].email);
+    }
+
+    #[actix_rt::test]
+    async fn does_not_notify_without_subscribers() {
+        let mut repository = InMemoryRepository::default();
+        repository.add_entity(1, Instance::De, Some(2));
+        repository.insert_entity_revision(3, 1);
+
+        Entity::reject_revision_with(
+            &from_value(json!({ "revisionId": 3, "userId": 10, "reason": "" })).unwrap(),
+            &mut repository,
+        )
+        .await
+        .unwrap();
+
+        assert!(repository.notifications.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn sets_state_of_given_notifications() {
+        let mut repository = InMemoryRepository::default();
+        repository.add_entity(1, Instance::De, Some(2));
+        repository.insert_entity_revision(3, 1);
+        repository.insert_entity_revision(4, 1);
+        repository.add_subscription(1, 20, false);
+
+        for revision_id in [3, 4] {
+            Entity::reject_revision_with(
+                &from_value(json!({ "revisionId": revision_id, "userId": 10, "reason": "" }))
+                    .unwrap(),
+                &mut repository,
+            )
+            .await
+            .unwrap();
+        }
+        let first_id = repository.notifications[&20][0].id;
+
+        Notifications::set_notification_state_with(
+            &from_value(json!({ "ids": [first_id], "userId": 20, "unread": false })).unwrap(),
+            &mut repository,
+        )
+        .await
+        .unwrap();
+
+        let unread: Vec<bool> = repository.notifications[&20]
+            .iter()
+            .map(|notification| notification.unread)
+            .collect();
+        assert_eq!(unread, vec![false, true]);
+    }
+}
+
+mod uuids {
+    use serde_json::from_value;
+    use test_utils::*;
+
+    use server::instance::Instance;
+    use server::operation::{Error, ErrorCode};
+    use server::uuid::Entity;
+
+    #[actix_rt::test]
+    async fn checking_out_rejected_revision_restores_it() {
+        let mut repository = InMemoryRepository::default();
+        repository.add_entity(1, Instance::De, Some(2));
+        repository.insert_entity_revision(2, 1);
+        repository.insert_entity_revision(3, 1);
+        let payload = json!({ "revisionId": 3, "userId": 10, "reason": "" });
+
+        Entity::reject_revision_with(&from_value(payload.clone()).unwrap(), &mut repository)
+            .await
+            .unwrap();
+        assert!(repository.is_trashed(3));
+
+        Entity::checkout_revision_with(&from_value(payload).unwrap(), &mut repository)
+            .await
+            .unwrap();
+        assert!(!repository.is_trashed(3));
+    }
+
+    #[actix_rt::test]
+    async fn rejecting_uuid_which_is_no_revision_fails() {
+        let mut repository = InMemoryRepository::default();
+        repository.add_entity(1, Instance::De, None);
+
+        let result = Entity::reject_revision_with(
+            &from_value(json!({ "revisionId": 1, "userId": 10, "reason": "" })).unwrap(),
+            &mut repository,
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::BadRequest {
+                code: ErrorCode::InvalidRevision,
+                ..
+            })
+        ));
+        assert!(!repository.is_trashed(1));
+    }
+}
+
+mod subscriptions {
+    use serde_json::from_value;
+    use test_utils::{assert_eq, *};
+
+    use server::subscription::Subscription;
+
+    #[actix_rt::test]
+    async fn subscribes_and_unsubscribes() {
+        let mut repository = InMemoryRepository::default();
+        repository.add_subscription(1, 10, false);
+
+        Subscription::change_subscription_with(
+            &from_value(
+                json!({ "ids": [1, 2], "userId": 10, "subscribe": true, "sendEmail": true }),
+            )
+            .unwrap(),
+            &mut repository,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(repository.subscriptions.len(), 2);
+        assert!(repository.subscriptions.iter().all(|s| s.send_email));
+
+        Subscription::change_subscription_with(
+            &from_value(
+                json!({ "ids": [1], "userId": 10, "subscribe": false, "sendEmail": false }),
+            )
+            .unwrap(),
+            &mut repository,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(repository.subscriptions.len(), 1);
+        assert_eq!(repository.subscriptions[0].object_id, 2);
+    }
+}
@@ -0,0 +1,29 @@
+mod schema {
+    use actix_web::{test, App};
+    use test_utils::{assert_eq, *};
+
+    use server::{configure_app, create_database_pool};
+
+    #[actix_rt::test]
+    async fn describes_payload_and_output_of_messages() {
+        let pool = create_database_pool().await.unwrap();
+        let app = test::init_service(configure_app(App::new(), pool)).await;
+        let req = test::TestRequest::get().uri("/schema").to_request();
+
+        let result: Value = test::call_and_read_body_json(&app, req).await;
+        let schemas = &result["components"]["schemas"];
+
+        assert_eq!(result["openapi"], "3.0.3");
+        assert_eq!(result["info"]["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            schemas["UuidQueryPayload"]["properties"]["id"]["type"],
+            "integer"
+        );
+        assert_eq!(
+            schemas["UuidQueryMessage"]["properties"]["payload"]["$ref"],
+            "#/components/schemas/UuidQueryPayload"
+        );
+        assert!(schemas["UuidQueryOutput"].is_object());
+        assert!(schemas["ErrorResponse"].is_object());
+    }
+}
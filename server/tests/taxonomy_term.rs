@@ -17,7 +17,9 @@ mod set_name_and_description_mutation {
             )
             .execute_on(&mut transaction)
             .await
-            .should_be_ok_with_body(json!({ "success": true }));
+            .should_be_ok_with(
+                |result| assert_json_include!(actual: result, expected: json!({ "success": true })),
+            );
 
             Message::new("UuidQuery", json!({ "id": 7 }))
                 .execute_on(&mut transaction)
@@ -181,7 +183,12 @@ mod create_entity_link_mutation {
             json! ({ "userId": 1, "entityIds": children_ids, "taxonomyTermId": taxonomy_term_id }),
         )
         .execute_on(&mut transaction)
-        .await;
+        .await
+        .should_be_ok_with(|result| {
+            assert_eq!(result["success"], true);
+            assert_eq!(result["affectedUuids"], json!([1288, 1495, 16078]));
+            assert_eq!(result["eventIds"].as_array().unwrap().len(), 2);
+        });
 
         for child_id in children_ids.iter() {
             Message::new("UuidQuery", json!({ "id": child_id }))
@@ -325,7 +332,9 @@ mod create_entity_link_mutation {
         )
         .execute()
         .await
-        .should_be_ok_with_body(json!({ "success": true }));
+        .should_be_ok_with(
+            |result| assert_json_include!(actual: result, expected: json!({ "success": true })),
+        );
 
         assert_eq!(
             count_before,
@@ -100,7 +100,7 @@ mod thread_mutations {
     use actix_web::http::StatusCode;
     use chrono::*;
     use rstest::*;
-    use server::datetime::DateTime;
+    use server::clock;
     use test_utils::{assert_eq, *};
 
     #[rstest]
@@ -307,7 +307,7 @@ mod thread_mutations {
                 .await
                 .should_be_ok_with(|result| {
                     let latest_event = &result["events"][ids.len() - index - 1];
-                    let event_age = DateTime::now().signed_duration_since(
+                    let event_age = clock::now().signed_duration_since(
                         serde_json::from_value(latest_event["date"].clone()).unwrap(),
                     );
                     if should_trigger_event[index] {
@@ -0,0 +1,82 @@
+mod timeout {
+    use actix_web::web::Data;
+    use actix_web::{test, App};
+    use test_utils::{assert_eq, *};
+
+    use server::config::{Config, TimeoutConfig};
+    use server::{configure_app, create_database_pool};
+
+    #[actix_rt::test]
+    async fn fails_when_message_exceeds_requested_timeout() {
+        let pool = create_database_pool().await.unwrap();
+        let app = test::init_service(configure_app(App::new(), pool)).await;
+
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header(("Timeout", "0.001"))
+            .set_json(json!({ "type": "EntitiesMetadataQuery", "payload": { "first": 10_000 } }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), 504);
+
+        let body: Value = test::read_body_json(resp).await;
+
+        assert_eq!(body["code"], "MESSAGE_TIMEOUT");
+    }
+
+    #[actix_rt::test]
+    async fn uses_timeout_of_message_type() {
+        let pool = create_database_pool().await.unwrap();
+        let config = TimeoutConfig {
+            messages: [("EntitiesMetadataQuery".to_string(), 0)].into(),
+            ..Default::default()
+        };
+        let app =
+            test::init_service(configure_app(App::new(), pool).app_data(Data::new(config))).await;
+
+        let req = test::TestRequest::post()
+            .uri("/batch")
+            .set_json(json!([
+                { "type": "EntitiesMetadataQuery", "payload": { "first": 10_000 } },
+                { "type": "UuidQuery", "payload": { "id": 1 } }
+            ]))
+            .to_request();
+        let result: Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(result[0]["status"], 504);
+        assert_eq!(result[1]["status"], 200);
+    }
+
+    #[actix_rt::test]
+    async fn kills_query_of_message_exceeding_its_timeout() {
+        let config = Config::load().unwrap().database;
+        let options = config.connect_options().unwrap();
+        let pool = config
+            .pool_options(&options)
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .unwrap();
+        let app = test::init_service(configure_app(App::new(), pool)).await;
+
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header(("Timeout", "0.001"))
+            .set_json(json!({ "type": "EntitiesMetadataQuery", "payload": { "first": 10_000 } }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), 504);
+
+        // The only connection is free again before the slow query would have finished.
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header(("Timeout", "1"))
+            .set_json(json!({ "type": "UuidQuery", "payload": { "id": 1 } }))
+            .to_request();
+        let result: Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(result["__typename"], "User");
+    }
+}
@@ -1,3 +1,30 @@
+mod active_authors_query {
+    use chrono::Duration;
+    use server::datetime::DateTime;
+    use test_utils::*;
+
+    #[actix_rt::test]
+    async fn only_counts_edits_of_last_90_days() {
+        let clock = MockClock::freeze(DateTime::ymd(2014, 3, 1));
+
+        Message::new("ActiveAuthorsQuery", json!({}))
+            .execute()
+            .await
+            .should_be_ok_with(|result| {
+                let user_ids = result.as_array().unwrap();
+                assert!(!user_ids.is_empty());
+                assert!(user_ids.iter().all(Value::is_i64));
+            });
+
+        clock.advance(Duration::days(16 * 365));
+
+        Message::new("ActiveAuthorsQuery", json!({}))
+            .execute()
+            .await
+            .should_be_ok_with_body(json!([]));
+    }
+}
+
 mod user_activity_by_type_query {
     use test_utils::*;
 
@@ -191,14 +218,20 @@ mod user_delete_bots_mutation {
         Message::new("UserDeleteBotsMutation", json!({ "botIds": [user_id] }))
             .execute_on(&mut transaction)
             .await
-            .should_be_ok_with_body(
-                json!({ "success": true, "emailHashes": ["cd5610c5b6be1e5a62fb621031ae3856"] }),
-            );
+            .should_be_ok_with(|result| {
+                assert_json_include!(
+                    actual: result,
+                    expected: json!({
+                        "success": true,
+                        "emailHashes": ["cd5610c5b6be1e5a62fb621031ae3856"]
+                    })
+                )
+            });
 
         Message::new("UuidQuery", json!({ "id": user_id }))
             .execute_on(&mut transaction)
             .await
-            .should_be_not_found();
+            .should_be_not_found_with_code("UUID_NOT_FOUND");
     }
 }
 
@@ -217,7 +250,9 @@ mod user_delete_regular_users_mutation {
         )
         .execute_on(&mut transaction)
         .await
-        .should_be_ok_with_body(json!({ "success": true, }));
+        .should_be_ok_with(
+            |result| assert_json_include!(actual: result, expected: json!({ "success": true })),
+        );
 
         Message::new("UuidQuery", json!({ "id": user_id }))
             .execute_on(&mut transaction)
@@ -346,6 +381,31 @@ mod user_delete_regular_users_mutation {
         assert!(flag_does_not_exist.is_none());
     }
 
+    #[actix_rt::test]
+    async fn reports_user_and_reassigned_uuids_as_affected() {
+        let mut transaction = begin_transaction().await;
+        let user_id: i32 = 10;
+        let revision_id: i32 =
+            sqlx::query_scalar("select id from entity_revision where author_id = ? limit 1")
+                .bind(user_id)
+                .fetch_one(&mut transaction)
+                .await
+                .unwrap();
+
+        Message::new(
+            "UserDeleteRegularUsersMutation",
+            json!({ "userId": user_id }),
+        )
+        .execute_on(&mut transaction)
+        .await
+        .should_be_ok_with(|result| {
+            let affected_uuids = result["affectedUuids"].as_array().unwrap();
+
+            assert!(affected_uuids.contains(&json!(user_id)));
+            assert!(affected_uuids.contains(&json!(revision_id)));
+        });
+    }
+
     #[actix_rt::test]
     async fn fails_when_user_does_not_exist() {
         Message::new("UserDeleteRegularUsersMutation", json!({ "userId": -1 }))
@@ -629,7 +689,9 @@ mod user_set_description_mutation {
         )
         .execute_on(&mut transaction)
         .await
-        .should_be_ok_with_body(json!({ "success": true }));
+        .should_be_ok_with(
+            |result| assert_json_include!(actual: result, expected: json!({ "success": true })),
+        );
 
         Message::new("UuidQuery", json!({ "id": user_id }))
             .execute_on(&mut transaction)
@@ -674,7 +736,12 @@ mod user_set_email_mutation {
         )
         .execute_on(&mut transaction)
         .await
-        .should_be_ok_with_body(json!({ "success": true, "username": username }));
+        .should_be_ok_with(|result| {
+            assert_json_include!(
+                actual: result,
+                expected: json!({ "success": true, "username": username })
+            )
+        });
 
         let email = get_email(user_id, &mut transaction).await.unwrap();
 
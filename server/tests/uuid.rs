@@ -2,6 +2,7 @@ mod uuid_query {
     use actix_web::{test, App, HttpResponse};
     use test_utils::{assert_eq, *};
 
+    use server::uuid::{EntityType, Uuid, UuidError, UuidFetcher};
     use server::{configure_app, create_database_pool};
 
     #[actix_rt::test]
@@ -56,6 +57,115 @@ mod uuid_query {
                 assert_eq!(result["alias"], "/entity/repository/compare/0/26769")
             });
     }
+
+    #[actix_rt::test]
+    async fn solution_is_null_for_math_puzzle() {
+        let mut transaction = begin_transaction().await;
+
+        let math_puzzle = EntityBuilder::unsupported("math-puzzle")
+            .build(&mut transaction)
+            .await
+            .unwrap();
+        let solution = EntityBuilder::new(EntityType::Solution)
+            .parent(math_puzzle.id)
+            .revision(1, &[("content", "{}")])
+            .build(&mut transaction)
+            .await
+            .unwrap();
+
+        let result = Uuid::fetch_via_transaction(solution.id, &mut transaction).await;
+
+        assert!(matches!(
+            result,
+            Err(UuidError::EntityMissingRequiredParent)
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn returns_error_body_for_missing_uuid() {
+        let result = Message::new("UuidQuery", json!({ "id": 1_000_000_000 }))
+            .execute()
+            .await;
+
+        assert_eq!(result.status, 404);
+        assert_eq!(
+            result.get_json(),
+            json!({
+                "code": "UUID_NOT_FOUND",
+                "message": "Requested value could not be found.",
+                "details": null
+            })
+        );
+    }
+}
+
+mod uuids_query {
+    use server::uuid::EntityType;
+    use test_utils::{assert_eq, *};
+
+    #[actix_rt::test]
+    async fn returns_same_uuids_as_uuid_query() {
+        let mut transaction = begin_transaction().await;
+        let ids = [
+            1, 1385, 1855, 15468, 15469, 16256, 26769, 30672, 35596, 2217, 2219, 2221,
+        ];
+
+        let result = Message::new("UuidsQuery", json!({ "ids": ids }))
+            .execute_on(&mut transaction)
+            .await
+            .get_json();
+
+        for id in ids {
+            let expected = Message::new("UuidQuery", json!({ "id": id }))
+                .execute_on(&mut transaction)
+                .await
+                .get_json();
+
+            assert_eq!(result[id.to_string()], expected);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn returns_null_for_missing_uuids() {
+        Message::new("UuidsQuery", json!({ "ids": [1, 1_000_000_000] }))
+            .execute()
+            .await
+            .should_be_ok_with(|result| {
+                assert_eq!(result["1"]["__typename"], "User");
+                assert_eq!(result["1000000000"], Value::Null);
+            });
+    }
+
+    #[actix_rt::test]
+    async fn returns_null_for_uuids_which_cannot_be_fetched() {
+        let mut transaction = begin_transaction().await;
+        let solution = EntityBuilder::new(EntityType::Solution)
+            .build(&mut transaction)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE uuid SET discriminator = 'unknown' WHERE id = 1385")
+            .execute(&mut transaction)
+            .await
+            .unwrap();
+
+        Message::new("UuidsQuery", json!({ "ids": [1, solution.id, 1385, 1855] }))
+            .execute_on(&mut transaction)
+            .await
+            .should_be_ok_with(|result| {
+                assert_eq!(result["1"]["__typename"], "User");
+                assert_eq!(result[solution.id.to_string()], Value::Null);
+                assert_eq!(result["1385"], Value::Null);
+                assert_eq!(result["1855"]["id"], 1855);
+            });
+    }
+
+    #[actix_rt::test]
+    async fn returns_empty_map_for_no_ids() {
+        Message::new("UuidsQuery", json!({ "ids": [] }))
+            .execute()
+            .await
+            .should_be_ok_with_body(json!({}));
+    }
 }
 
 mod set_uuid_state_mutation {
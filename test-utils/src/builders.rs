@@ -0,0 +1,658 @@
+//! Builders which insert consistent fixtures (including the rows of `uuid`) for tests.
+//!
+//! Each builder runs in a nested transaction of the given executor, so the fixtures are rolled
+//! back together with the transaction of the test:
+//!
+//! ```ignore
+//! let mut transaction = begin_transaction().await;
+//! let exercise = EntityBuilder::new(EntityType::Exercise)
+//!     .taxonomy_term(taxonomy_term_id)
+//!     .revision(author_id, &[("content", "{}")])
+//!     .build(&mut transaction)
+//!     .await?;
+//! let solution = EntityBuilder::new(EntityType::Solution)
+//!     .parent(exercise.id)
+//!     .build(&mut transaction)
+//!     .await?;
+//! ```
+use server::instance::Instance;
+use server::uuid::{EntityType, TaxonomyType};
+use sqlx::MySql;
+
+use crate::random_string;
+
+/// Id of the user which authors fixtures when no author is given.
+const DEFAULT_AUTHOR_ID: i32 = 1;
+
+/// Id of the license "cc-by-sa 4.0".
+const DEFAULT_LICENSE_ID: i32 = 1;
+
+/// Ids of an entity or page and its revisions (in the order they were added).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RepositoryFixture {
+    pub id: i32,
+    pub revision_ids: Vec<i32>,
+}
+
+pub struct EntityBuilder {
+    entity_type: EntityTypeName,
+    instance: Instance,
+    license_id: i32,
+    trashed: bool,
+    parent_id: Option<i32>,
+    taxonomy_term_id: Option<i32>,
+    revisions: Vec<RevisionFixture>,
+    current_revision: Option<usize>,
+}
+
+enum EntityTypeName {
+    Supported(EntityType),
+    /// Name in the table `type` of types the server does not support (like `"math-puzzle"`).
+    Unsupported(String),
+}
+
+struct RevisionFixture {
+    author_id: i32,
+    fields: Vec<(String, String)>,
+}
+
+impl EntityBuilder {
+    pub fn new(entity_type: EntityType) -> Self {
+        Self::with_type(EntityTypeName::Supported(entity_type))
+    }
+
+    /// Creates an entity of a type the server does not support, like `"math-puzzle"`.
+    pub fn unsupported(type_name: &str) -> Self {
+        Self::with_type(EntityTypeName::Unsupported(type_name.to_string()))
+    }
+
+    fn with_type(entity_type: EntityTypeName) -> Self {
+        Self {
+            entity_type,
+            instance: Instance::De,
+            license_id: DEFAULT_LICENSE_ID,
+            trashed: false,
+            parent_id: None,
+            taxonomy_term_id: None,
+            revisions: Vec::new(),
+            current_revision: None,
+        }
+    }
+
+    pub fn instance(mut self, instance: Instance) -> Self {
+        self.instance = instance;
+        self
+    }
+
+    pub fn license_id(mut self, license_id: i32) -> Self {
+        self.license_id = license_id;
+        self
+    }
+
+    pub fn trashed(mut self, trashed: bool) -> Self {
+        self.trashed = trashed;
+        self
+    }
+
+    /// Links the entity as child of another entity (like solutions or course pages).
+    pub fn parent(mut self, parent_id: i32) -> Self {
+        self.parent_id = Some(parent_id);
+        self
+    }
+
+    pub fn taxonomy_term(mut self, taxonomy_term_id: i32) -> Self {
+        self.taxonomy_term_id = Some(taxonomy_term_id);
+        self
+    }
+
+    /// Adds a revision with the given fields (e.g. `("title", "Pythagoras")`) and checks it out.
+    pub fn revision(mut self, author_id: i32, fields: &[(&str, &str)]) -> Self {
+        self.current_revision = Some(self.revisions.len());
+        self.add_revision(author_id, fields)
+    }
+
+    /// Adds a revision which is not checked out (e.g. one which still needs review).
+    pub fn pending_revision(self, author_id: i32, fields: &[(&str, &str)]) -> Self {
+        self.add_revision(author_id, fields)
+    }
+
+    fn add_revision(mut self, author_id: i32, fields: &[(&str, &str)]) -> Self {
+        self.revisions.push(RevisionFixture {
+            author_id,
+            fields: fields
+                .iter()
+                .map(|(field, value)| (field.to_string(), value.to_string()))
+                .collect(),
+        });
+        self
+    }
+
+    pub async fn build<'a, E>(self, executor: E) -> Result<RepositoryFixture, sqlx::Error>
+    where
+        E: sqlx::Acquire<'a, Database = MySql>,
+    {
+        let mut transaction = executor.begin().await?;
+
+        let id = insert_uuid("entity", self.trashed, &mut transaction).await?;
+        let instance_id = self.instance.fetch_id(&mut transaction).await?;
+
+        let query = sqlx::query(
+            r#"
+                INSERT INTO entity (id, type_id, instance_id, license_id, date)
+                    SELECT ?, id, ?, ?, NOW() FROM type WHERE name = ?
+            "#,
+        )
+        .bind(id)
+        .bind(instance_id)
+        .bind(self.license_id);
+        let result = match &self.entity_type {
+            EntityTypeName::Supported(entity_type) => query.bind(entity_type),
+            EntityTypeName::Unsupported(type_name) => query.bind(type_name),
+        }
+        .execute(&mut transaction)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        if let Some(parent_id) = self.parent_id {
+            sqlx::query(
+                r#"
+                    INSERT INTO entity_link (parent_id, child_id, type_id, entity_link.order)
+                        SELECT ?, ?, 9, IFNULL(MAX(entity_link.order), 0) + 1
+                        FROM entity_link WHERE parent_id = ?
+                "#,
+            )
+            .bind(parent_id)
+            .bind(id)
+            .bind(parent_id)
+            .execute(&mut transaction)
+            .await?;
+        }
+
+        if let Some(taxonomy_term_id) = self.taxonomy_term_id {
+            sqlx::query(
+                r#"
+                    INSERT INTO term_taxonomy_entity (entity_id, term_taxonomy_id, position)
+                        SELECT ?, ?, IFNULL(MAX(position), 0) + 1
+                        FROM term_taxonomy_entity WHERE term_taxonomy_id = ?
+                "#,
+            )
+            .bind(id)
+            .bind(taxonomy_term_id)
+            .bind(taxonomy_term_id)
+            .execute(&mut transaction)
+            .await?;
+        }
+
+        let mut revision_ids = Vec::new();
+        for revision in &self.revisions {
+            let revision_id = insert_uuid("entityRevision", false, &mut transaction).await?;
+
+            sqlx::query(
+                r#"
+                    INSERT INTO entity_revision (id, author_id, repository_id, date)
+                        VALUES (?, ?, ?, NOW())
+                "#,
+            )
+            .bind(revision_id)
+            .bind(revision.author_id)
+            .bind(id)
+            .execute(&mut transaction)
+            .await?;
+
+            for (field, value) in &revision.fields {
+                sqlx::query(
+                    r#"
+                        INSERT INTO entity_revision_field (entity_revision_id, field, value)
+                            VALUES (?, ?, ?)
+                    "#,
+                )
+                .bind(revision_id)
+                .bind(field)
+                .bind(value)
+                .execute(&mut transaction)
+                .await?;
+            }
+
+            revision_ids.push(revision_id);
+        }
+
+        if let Some(index) = self.current_revision {
+            sqlx::query("UPDATE entity SET current_revision_id = ? WHERE id = ?")
+                .bind(revision_ids[index])
+                .bind(id)
+                .execute(&mut transaction)
+                .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(RepositoryFixture { id, revision_ids })
+    }
+}
+
+pub struct TaxonomyTermBuilder {
+    taxonomy_type: TaxonomyType,
+    instance: Instance,
+    name: String,
+    description: Option<String>,
+    parent_id: Option<i32>,
+    trashed: bool,
+}
+
+impl TaxonomyTermBuilder {
+    pub fn new(taxonomy_type: TaxonomyType) -> Self {
+        Self {
+            taxonomy_type,
+            instance: Instance::De,
+            name: random_string(10),
+            description: None,
+            parent_id: None,
+            trashed: false,
+        }
+    }
+
+    pub fn instance(mut self, instance: Instance) -> Self {
+        self.instance = instance;
+        self
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    pub fn parent(mut self, parent_id: i32) -> Self {
+        self.parent_id = Some(parent_id);
+        self
+    }
+
+    pub fn trashed(mut self, trashed: bool) -> Self {
+        self.trashed = trashed;
+        self
+    }
+
+    /// Inserts the term into the taxonomy of its type in its instance, which needs to exist.
+    pub async fn build<'a, E>(self, executor: E) -> Result<i32, sqlx::Error>
+    where
+        E: sqlx::Acquire<'a, Database = MySql>,
+    {
+        let mut transaction = executor.begin().await?;
+
+        let id = insert_uuid("taxonomyTerm", self.trashed, &mut transaction).await?;
+        let instance_id = self.instance.fetch_id(&mut transaction).await?;
+
+        let taxonomy_id: i32 = sqlx::query_scalar(
+            r#"
+                SELECT taxonomy.id FROM taxonomy
+                JOIN type ON taxonomy.type_id = type.id
+                WHERE type.name = ? AND taxonomy.instance_id = ?
+            "#,
+        )
+        .bind(self.taxonomy_type)
+        .bind(instance_id)
+        .fetch_one(&mut transaction)
+        .await?;
+
+        let term_id = sqlx::query("INSERT INTO term (name, instance_id) VALUES (?, ?)")
+            .bind(&self.name)
+            .bind(instance_id)
+            .execute(&mut transaction)
+            .await?
+            .last_insert_id();
+
+        sqlx::query(
+            r#"
+                INSERT INTO term_taxonomy (id, term_id, taxonomy_id, parent_id, description, weight)
+                    SELECT ?, ?, ?, ?, ?, IFNULL(MAX(weight), 0) + 1
+                    FROM term_taxonomy WHERE parent_id <=> ?
+            "#,
+        )
+        .bind(id)
+        .bind(term_id)
+        .bind(taxonomy_id)
+        .bind(self.parent_id)
+        .bind(&self.description)
+        .bind(self.parent_id)
+        .execute(&mut transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(id)
+    }
+}
+
+pub struct UserBuilder {
+    username: String,
+    email: String,
+    description: Option<String>,
+    roles: Vec<String>,
+    trashed: bool,
+}
+
+impl UserBuilder {
+    pub fn new() -> Self {
+        Self {
+            username: random_string(10),
+            email: random_string(10),
+            description: None,
+            roles: Vec::new(),
+            trashed: false,
+        }
+    }
+
+    pub fn username(mut self, username: &str) -> Self {
+        self.username = username.to_string();
+        self
+    }
+
+    pub fn email(mut self, email: &str) -> Self {
+        self.email = email.to_string();
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Adds a role by its name (like `"login"` or `"sysadmin"`).
+    pub fn role(mut self, role_name: &str) -> Self {
+        self.roles.push(role_name.to_string());
+        self
+    }
+
+    pub fn trashed(mut self, trashed: bool) -> Self {
+        self.trashed = trashed;
+        self
+    }
+
+    pub async fn build<'a, E>(self, executor: E) -> Result<i32, sqlx::Error>
+    where
+        E: sqlx::Acquire<'a, Database = MySql>,
+    {
+        let mut transaction = executor.begin().await?;
+
+        let id = insert_uuid("user", self.trashed, &mut transaction).await?;
+
+        sqlx::query(
+            r#"
+                INSERT INTO user (id, username, email, password, token, date, description)
+                    VALUES (?, ?, ?, "", ?, NOW(), ?)
+            "#,
+        )
+        .bind(id)
+        .bind(&self.username)
+        .bind(&self.email)
+        .bind(random_string(10))
+        .bind(&self.description)
+        .execute(&mut transaction)
+        .await?;
+
+        for role in &self.roles {
+            let result = sqlx::query(
+                r#"
+                    INSERT INTO role_user (user_id, role_id)
+                        SELECT ?, id FROM role WHERE name = ?
+                "#,
+            )
+            .bind(id)
+            .bind(role)
+            .execute(&mut transaction)
+            .await?;
+
+            if result.rows_affected() == 0 {
+                return Err(sqlx::Error::RowNotFound);
+            }
+        }
+
+        transaction.commit().await?;
+
+        Ok(id)
+    }
+}
+
+impl Default for UserBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct ThreadBuilder {
+    object_id: i32,
+    author_id: i32,
+    title: Option<String>,
+    archived: bool,
+    trashed: bool,
+    comments: Vec<(i32, String)>,
+}
+
+impl ThreadBuilder {
+    /// Creates a thread about the uuid `object_id` whose first comment has `content`.
+    pub fn new(object_id: i32, content: &str) -> Self {
+        Self {
+            object_id,
+            author_id: DEFAULT_AUTHOR_ID,
+            title: None,
+            archived: false,
+            trashed: false,
+            comments: vec![(DEFAULT_AUTHOR_ID, content.to_string())],
+        }
+    }
+
+    pub fn author(mut self, author_id: i32) -> Self {
+        self.author_id = author_id;
+        self.comments[0].0 = author_id;
+        self
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub fn archived(mut self, archived: bool) -> Self {
+        self.archived = archived;
+        self
+    }
+
+    pub fn trashed(mut self, trashed: bool) -> Self {
+        self.trashed = trashed;
+        self
+    }
+
+    /// Adds a reply to the thread.
+    pub fn comment(mut self, author_id: i32, content: &str) -> Self {
+        self.comments.push((author_id, content.to_string()));
+        self
+    }
+
+    /// Returns the ids of the thread and its comments. The id of the thread is the one of its
+    /// first comment.
+    pub async fn build<'a, E>(self, executor: E) -> Result<Vec<i32>, sqlx::Error>
+    where
+        E: sqlx::Acquire<'a, Database = MySql>,
+    {
+        let mut transaction = executor.begin().await?;
+
+        // Like the server, comments get the instance of the object they belong to.
+        let instance_id: i32 = sqlx::query_scalar(
+            r#"
+                SELECT instance_id FROM (
+                    SELECT id, instance_id FROM entity
+                    UNION ALL SELECT id, instance_id FROM page_repository
+                    UNION ALL SELECT term_taxonomy.id, term.instance_id FROM term_taxonomy
+                        JOIN term ON term.id = term_taxonomy.term_id
+                    UNION ALL SELECT id, 1 FROM user
+                ) object WHERE id = ?
+            "#,
+        )
+        .bind(self.object_id)
+        .fetch_optional(&mut transaction)
+        .await?
+        .unwrap_or(1);
+
+        let mut ids: Vec<i32> = Vec::new();
+        for (index, (author_id, content)) in self.comments.iter().enumerate() {
+            let id = insert_uuid("comment", self.trashed && index == 0, &mut transaction).await?;
+            let thread_id = ids.first().copied();
+
+            sqlx::query(
+                r#"
+                    INSERT INTO comment
+                        (id, date, archived, title, content, uuid_id, parent_id, author_id, instance_id)
+                        VALUES (?, NOW(), ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(id)
+            .bind(self.archived)
+            .bind(thread_id.map_or(self.title.as_ref(), |_| None))
+            .bind(content)
+            .bind(thread_id.map_or(Some(self.object_id), |_| None))
+            .bind(thread_id)
+            .bind(author_id)
+            .bind(instance_id)
+            .execute(&mut transaction)
+            .await?;
+
+            ids.push(id);
+        }
+
+        transaction.commit().await?;
+
+        Ok(ids)
+    }
+}
+
+pub struct PageBuilder {
+    instance: Instance,
+    license_id: i32,
+    discussions_enabled: bool,
+    trashed: bool,
+    revisions: Vec<(i32, String, String)>,
+}
+
+impl PageBuilder {
+    pub fn new() -> Self {
+        Self {
+            instance: Instance::De,
+            license_id: DEFAULT_LICENSE_ID,
+            discussions_enabled: false,
+            trashed: false,
+            revisions: Vec::new(),
+        }
+    }
+
+    pub fn instance(mut self, instance: Instance) -> Self {
+        self.instance = instance;
+        self
+    }
+
+    pub fn license_id(mut self, license_id: i32) -> Self {
+        self.license_id = license_id;
+        self
+    }
+
+    pub fn discussions_enabled(mut self, discussions_enabled: bool) -> Self {
+        self.discussions_enabled = discussions_enabled;
+        self
+    }
+
+    pub fn trashed(mut self, trashed: bool) -> Self {
+        self.trashed = trashed;
+        self
+    }
+
+    /// Adds a revision. The last revision is checked out.
+    pub fn revision(mut self, author_id: i32, title: &str, content: &str) -> Self {
+        self.revisions
+            .push((author_id, title.to_string(), content.to_string()));
+        self
+    }
+
+    pub async fn build<'a, E>(self, executor: E) -> Result<RepositoryFixture, sqlx::Error>
+    where
+        E: sqlx::Acquire<'a, Database = MySql>,
+    {
+        let mut transaction = executor.begin().await?;
+
+        let id = insert_uuid("page", self.trashed, &mut transaction).await?;
+        let instance_id = self.instance.fetch_id(&mut transaction).await?;
+
+        sqlx::query(
+            r#"
+                INSERT INTO page_repository (id, instance_id, license_id, discussions_enabled)
+                    VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(id)
+        .bind(instance_id)
+        .bind(self.license_id)
+        .bind(self.discussions_enabled)
+        .execute(&mut transaction)
+        .await?;
+
+        let mut revision_ids = Vec::new();
+        for (author_id, title, content) in &self.revisions {
+            let revision_id = insert_uuid("pageRevision", false, &mut transaction).await?;
+
+            sqlx::query(
+                r#"
+                    INSERT INTO page_revision (id, author_id, page_repository_id, title, content, date)
+                        VALUES (?, ?, ?, ?, ?, NOW())
+                "#,
+            )
+            .bind(revision_id)
+            .bind(author_id)
+            .bind(id)
+            .bind(title)
+            .bind(content)
+            .execute(&mut transaction)
+            .await?;
+
+            revision_ids.push(revision_id);
+        }
+
+        if let Some(revision_id) = revision_ids.last() {
+            sqlx::query("UPDATE page_repository SET current_revision_id = ? WHERE id = ?")
+                .bind(revision_id)
+                .bind(id)
+                .execute(&mut transaction)
+                .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(RepositoryFixture { id, revision_ids })
+    }
+}
+
+impl Default for PageBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Inserts the row into `uuid` and returns its id.
+async fn insert_uuid(
+    discriminator: &str,
+    trashed: bool,
+    transaction: &mut sqlx::Transaction<'_, MySql>,
+) -> Result<i32, sqlx::Error> {
+    Ok(
+        sqlx::query("INSERT INTO uuid (trashed, discriminator) VALUES (?, ?)")
+            .bind(trashed)
+            .bind(discriminator)
+            .execute(&mut *transaction)
+            .await?
+            .last_insert_id() as i32,
+    )
+}
@@ -11,12 +11,19 @@ pub use serde_json::{json, to_value, Value};
 use std::collections::HashMap;
 use std::str::FromStr;
 
+pub use builders::*;
+pub use server::clock::MockClock;
+pub use server::repository::InMemoryRepository;
+
 use server::create_database_pool;
 use server::database::Connection;
 use server::message::{Message as ServerMessage, MessageResponder};
 use server::uuid::abstract_entity_revision::EntityRevisionType;
 use server::uuid::{EntityType, TaxonomyType};
 
+mod builders;
+mod snapshot;
+
 pub struct Message<'a> {
     message_type: &'a str,
     payload: Value,
@@ -71,23 +78,47 @@ impl MessageResult {
         self.should_be_response(200, expected_result);
     }
 
+    /// Compares the output with the snapshot `server/tests/snapshots/<name>.json`.
+    pub fn should_match_snapshot(self, name: &str) {
+        self.should_match_snapshot_with_redactions(name, &[]);
+    }
+
+    /// Like [`MessageResult::should_match_snapshot`] but replaces volatile values at the given
+    /// paths (like `/events/*/date`) before the comparison.
+    pub fn should_match_snapshot_with_redactions(self, name: &str, redactions: &[&str]) {
+        assert_eq!(self.status, 200);
+        snapshot::assert_snapshot(name, self.get_json(), redactions);
+    }
+
     pub fn should_be_ok(self) {
         assert_eq!(self.status, 200);
     }
 
     pub fn should_be_not_found(self) {
-        self.should_be_response(404, Value::Null);
+        assert_eq!(self.status, 404);
+        assert_error_body(&self.get_json());
+    }
+
+    pub fn should_be_not_found_with_code(self, code: &str) {
+        assert_eq!(self.status, 404);
+        assert_eq!(self.get_json()["code"], code);
     }
 
     pub fn should_be_bad_request(self) {
         assert_eq!(self.status, 400);
 
         let json_body = self.get_json();
+        assert_error_body(&json_body);
         assert_eq!(json_body["success"], false);
         assert!(json_body["reason"].is_string());
         assert!(!json_body["reason"].as_str().unwrap().is_empty());
     }
 
+    pub fn should_be_bad_request_with_code(self, code: &str) {
+        assert_eq!(self.status, 400);
+        assert_eq!(self.get_json()["code"], code);
+    }
+
     pub fn get_json(self) -> Value {
         from_slice(&self.body).unwrap()
     }
@@ -98,6 +129,12 @@ impl MessageResult {
     }
 }
 
+fn assert_error_body(body: &Value) {
+    assert!(body["code"].is_string());
+    assert!(body["message"].is_string());
+    assert!(body.get("details").is_some());
+}
+
 pub fn assert_has_length(value: &Value, length: usize) {
     assert_eq!(value.as_array().unwrap().len(), length);
 }
@@ -0,0 +1,150 @@
+//! Snapshots of JSON outputs in `server/tests/snapshots`.
+//!
+//! A snapshot is compared with the pretty-printed JSON in `<name>.json`. Run the tests with
+//! `UPDATE_SNAPSHOTS=1` to create or update the snapshot files instead. Volatile values (like
+//! dates or generated ids) can be redacted by paths like `/events/*/date` where `*` matches every
+//! element of an array or every field of an object.
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+
+const UPDATE_ENV_VAR: &str = "UPDATE_SNAPSHOTS";
+
+const REDACTED: &str = "[redacted]";
+
+pub fn assert_snapshot(name: &str, mut actual: Value, redactions: &[&str]) {
+    for redaction in redactions {
+        let path: Vec<&str> = redaction.split('/').skip(1).collect();
+        redact(&mut actual, &path);
+    }
+
+    let path = snapshot_path(name);
+
+    if env::var(UPDATE_ENV_VAR).as_deref() == Ok("1") {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+        return;
+    }
+
+    let expected = match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str::<Value>(&content).unwrap(),
+        Err(_) => panic!(
+            "snapshot {} does not exist, run the test with {UPDATE_ENV_VAR}=1 to create it",
+            path.display()
+        ),
+    };
+
+    assert_eq!(
+        actual,
+        expected,
+        "output does not match snapshot {} (run the test with {UPDATE_ENV_VAR}=1 to update it)",
+        path.display()
+    );
+}
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../server/tests/snapshots")
+        .join(format!("{name}.json"))
+}
+
+fn redact(value: &mut Value, path: &[&str]) {
+    let (segment, rest) = match path.split_first() {
+        Some(split) => split,
+        None => {
+            *value = Value::String(REDACTED.to_string());
+            return;
+        }
+    };
+
+    match value {
+        Value::Array(elements) if *segment == "*" => elements
+            .iter_mut()
+            .for_each(|element| redact(element, rest)),
+        Value::Array(elements) => {
+            if let Some(element) = segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| elements.get_mut(index))
+            {
+                redact(element, rest)
+            }
+        }
+        Value::Object(fields) if *segment == "*" => {
+            fields.values_mut().for_each(|field| redact(field, rest))
+        }
+        Value::Object(fields) => {
+            if let Some(field) = fields.get_mut(*segment) {
+                redact(field, rest)
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn redacted(mut value: Value, redaction: &str) -> Value {
+        let path: Vec<&str> = redaction.split('/').skip(1).collect();
+        redact(&mut value, &path);
+        value
+    }
+
+    #[test]
+    fn redacts_field() {
+        assert_eq!(
+            redacted(json!({ "id": 1, "date": "2014-03-01" }), "/date"),
+            json!({ "id": 1, "date": REDACTED })
+        );
+    }
+
+    #[test]
+    fn redacts_every_array_element_for_wildcard() {
+        assert_eq!(
+            redacted(
+                json!({ "events": [{ "id": 1, "date": "a" }, { "id": 2, "date": "b" }] }),
+                "/events/*/date"
+            ),
+            json!({ "events": [{ "id": 1, "date": REDACTED }, { "id": 2, "date": REDACTED }] })
+        );
+    }
+
+    #[test]
+    fn redacts_every_object_field_for_wildcard() {
+        assert_eq!(
+            redacted(json!({ "de": { "id": 1 }, "en": { "id": 2 } }), "/*/id"),
+            json!({ "de": { "id": REDACTED }, "en": { "id": REDACTED } })
+        );
+    }
+
+    #[test]
+    fn redacts_array_element_at_index() {
+        assert_eq!(
+            redacted(json!([{ "id": 1 }, { "id": 2 }]), "/1/id"),
+            json!([{ "id": 1 }, { "id": REDACTED }])
+        );
+    }
+
+    #[test]
+    fn ignores_missing_paths() {
+        let value = json!({ "events": [{ "id": 1 }], "count": 1 });
+
+        for redaction in [
+            "/date",
+            "/events/1/id",
+            "/events/first/id",
+            "/count/id",
+            "/events/*/date",
+        ] {
+            assert_eq!(redacted(value.clone(), redaction), value);
+        }
+    }
+}